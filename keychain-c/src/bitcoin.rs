@@ -1,14 +1,75 @@
 use error::ErrorPtr;
 use key_path::KeyPath;
-use keychain::networks::bitcoin::KeyPath as RKeyPath;
+use keychain::networks::bitcoin::{
+  verify_message as rverify_message, KeyPath as RKeyPath, NetworkType as RNetworkType, Psbt,
+  SighashType, SignatureFormat, SigningInput, Transaction
+};
 use keychain::KeyPath as IKeyPath;
-use keychain::Network as RNetwork;
+use keychain::{Error as RError, Network as RNetwork};
+use keychain_c::KeychainPtr;
 use network::Network;
+use utils::data::DataPtr;
 use utils::panic::handle_exception_result;
+use utils::ptr::Ptr;
 use utils::result::CResult;
+use utils::string::{CharPtr, ToCString};
 
 static BITCOIN: Network = Network(RNetwork::BITCOIN.0);
 
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub enum BitcoinNetworkType {
+  Mainnet = 0,
+  Testnet = 1,
+  Regtest = 2
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub enum BitcoinSignatureFormat {
+  Simple = 0,
+  Full = 1
+}
+
+impl From<BitcoinSignatureFormat> for SignatureFormat {
+  fn from(format: BitcoinSignatureFormat) -> Self {
+    match format {
+      BitcoinSignatureFormat::Simple => SignatureFormat::Simple,
+      BitcoinSignatureFormat::Full => SignatureFormat::Full
+    }
+  }
+}
+
+// Input of the transaction, which is signed by the key at the path
+#[repr(C)]
+pub struct BitcoinSigningInput {
+  index: usize,
+  path: KeyPath,
+  value: u64,
+  sighash_type: u8
+}
+
+impl From<&BitcoinSigningInput> for SigningInput {
+  fn from(input: &BitcoinSigningInput) -> Self {
+    SigningInput {
+      index: input.index,
+      path: input.path.derivation_path(),
+      value: input.value,
+      sighash_type: SighashType(input.sighash_type)
+    }
+  }
+}
+
+impl From<BitcoinNetworkType> for RNetworkType {
+  fn from(network: BitcoinNetworkType) -> Self {
+    match network {
+      BitcoinNetworkType::Mainnet => RNetworkType::Mainnet,
+      BitcoinNetworkType::Testnet => RNetworkType::Testnet,
+      BitcoinNetworkType::Regtest => RNetworkType::Regtest
+    }
+  }
+}
+
 #[no_mangle]
 pub extern "C" fn NETWORK_BITCOIN() -> Network {
   BITCOIN
@@ -49,3 +110,117 @@ pub unsafe extern "C" fn keypath_bitcoin_new_bip84(
   })
   .response(path, error)
 }
+
+#[no_mangle]
+pub unsafe extern "C" fn keypath_bitcoin_new_bip86(
+  testnet: bool, account: u32, change: u32, address: u32, path: &mut KeyPath, error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    RKeyPath::bip86(testnet, account, change, address)
+      .map_err(|err| err.into())
+      .map(|kp| (&kp as &dyn IKeyPath).into())
+  })
+  .response(path, error)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn keychain_bitcoin_address(
+  keychain: &KeychainPtr, path: KeyPath, address: &mut CharPtr, error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    keychain.rust_ref().bitcoin_address(&path.derivation_path()).map(|address| address.to_cstr())
+  })
+  .response(address, error)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn keychain_bitcoin_network_address(
+  keychain: &KeychainPtr, network: BitcoinNetworkType, path: KeyPath, address: &mut CharPtr,
+  error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    keychain
+      .rust_ref()
+      .bitcoin_network_address(network.into(), &path.derivation_path())
+      .map(|address| address.to_cstr())
+  })
+  .response(address, error)
+}
+
+// Signs serialized transaction and returns it with script sigs and witnesses of the inputs
+#[no_mangle]
+pub unsafe extern "C" fn keychain_bitcoin_sign_transaction(
+  keychain: &KeychainPtr, tx: *const u8, tx_len: usize, inputs: *const BitcoinSigningInput,
+  inputs_len: usize, signed: &mut DataPtr, error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    let tx = Transaction::parse(std::slice::from_raw_parts(tx, tx_len))
+      .map_err(|err| RError::from_key_error(&RNetwork::BITCOIN, err))?;
+    let inputs: Vec<SigningInput> =
+      std::slice::from_raw_parts(inputs, inputs_len).iter().map(|input| input.into()).collect();
+    keychain
+      .rust_ref()
+      .bitcoin_sign_transaction(&tx, &inputs)
+      .map(|signed| DataPtr::from(signed.serialize()))
+  })
+  .response(signed, error)
+}
+
+// Signs base64 PSBT and returns it with partial signatures of this keychain
+#[no_mangle]
+pub unsafe extern "C" fn keychain_bitcoin_sign_psbt(
+  keychain: &KeychainPtr, psbt: CharPtr, signed: &mut CharPtr, error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    let psbt = Psbt::from_base64(psbt.rust_ref())
+      .map_err(|err| RError::from_key_error(&RNetwork::BITCOIN, err))?;
+    keychain.rust_ref().bitcoin_sign_psbt(&psbt).map(|signed| signed.to_base64().to_cstr())
+  })
+  .response(signed, error)
+}
+
+// Base64 BIP137 signature
+#[no_mangle]
+pub unsafe extern "C" fn keychain_bitcoin_sign_message(
+  keychain: &KeychainPtr, path: KeyPath, message: *const u8, message_len: usize,
+  signature: &mut CharPtr, error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    let message = std::slice::from_raw_parts(message, message_len);
+    keychain
+      .rust_ref()
+      .bitcoin_sign_message(message, &path.derivation_path())
+      .map(|signature| signature.to_cstr())
+  })
+  .response(signature, error)
+}
+
+// Base64 BIP322 signature
+#[no_mangle]
+pub unsafe extern "C" fn keychain_bitcoin_sign_message_bip322(
+  keychain: &KeychainPtr, path: KeyPath, message: *const u8, message_len: usize,
+  format: BitcoinSignatureFormat, signature: &mut CharPtr, error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    let message = std::slice::from_raw_parts(message, message_len);
+    keychain
+      .rust_ref()
+      .bitcoin_sign_message_bip322(message, format.into(), &path.derivation_path())
+      .map(|signature| signature.to_cstr())
+  })
+  .response(signature, error)
+}
+
+// Verifies BIP137 and BIP322 signatures
+#[no_mangle]
+pub unsafe extern "C" fn bitcoin_verify_message(
+  address: CharPtr, message: *const u8, message_len: usize, signature: CharPtr, valid: &mut bool,
+  error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    let message = std::slice::from_raw_parts(message, message_len);
+    rverify_message(address.rust_ref(), message, signature.rust_ref())
+      .map_err(|err| RError::from_key_error(&RNetwork::BITCOIN, err))
+  })
+  .response(valid, error)
+}
@@ -1,14 +1,130 @@
 use error::ErrorPtr;
 use key_path::KeyPath;
-use keychain::networks::cardano::KeyPath as RKeyPath;
-use keychain::KeyPath as IKeyPath;
-use keychain::Network as RNetwork;
+use keychain::networks::cardano::{
+  verify_data as rverify_data, witness_set as rwitness_set, DataSignature, Delegation,
+  KeyPath as RKeyPath, MasterKeyScheme, NetworkId, Pointer as RPointer, Role, VoteRegistration
+};
+use keychain::{DerivationPath, KeyPath as IKeyPath};
+use keychain::{Error as RError, Network as RNetwork};
+use keychain_c::KeychainPtr;
 use network::Network;
+use utils::data::DataPtr;
 use utils::panic::handle_exception_result;
+use utils::ptr::Ptr;
 use utils::result::CResult;
+use utils::string::{CharPtr, ToCString};
 
 static CARDANO: Network = Network(RNetwork::CARDANO.0);
 
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub enum CardanoMasterKeyScheme {
+  Legacy = 0,
+  Icarus = 1,
+  Ledger = 2,
+  Daedalus = 3
+}
+
+impl From<MasterKeyScheme> for CardanoMasterKeyScheme {
+  fn from(scheme: MasterKeyScheme) -> Self {
+    match scheme {
+      MasterKeyScheme::Legacy => CardanoMasterKeyScheme::Legacy,
+      MasterKeyScheme::Icarus => CardanoMasterKeyScheme::Icarus,
+      MasterKeyScheme::Ledger => CardanoMasterKeyScheme::Ledger,
+      MasterKeyScheme::Daedalus => CardanoMasterKeyScheme::Daedalus
+    }
+  }
+}
+
+impl From<CardanoMasterKeyScheme> for MasterKeyScheme {
+  fn from(scheme: CardanoMasterKeyScheme) -> Self {
+    match scheme {
+      CardanoMasterKeyScheme::Legacy => MasterKeyScheme::Legacy,
+      CardanoMasterKeyScheme::Icarus => MasterKeyScheme::Icarus,
+      CardanoMasterKeyScheme::Ledger => MasterKeyScheme::Ledger,
+      CardanoMasterKeyScheme::Daedalus => MasterKeyScheme::Daedalus
+    }
+  }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub enum CardanoRole {
+  External = 0,
+  Internal = 1,
+  Staking = 2,
+  DRep = 3,
+  CommitteeCold = 4,
+  CommitteeHot = 5
+}
+
+impl From<CardanoRole> for Role {
+  fn from(role: CardanoRole) -> Self {
+    match role {
+      CardanoRole::External => Role::External,
+      CardanoRole::Internal => Role::Internal,
+      CardanoRole::Staking => Role::Staking,
+      CardanoRole::DRep => Role::DRep,
+      CardanoRole::CommitteeCold => Role::CommitteeCold,
+      CardanoRole::CommitteeHot => Role::CommitteeHot
+    }
+  }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub enum CardanoNetworkId {
+  Mainnet = 0,
+  Preprod = 1,
+  Preview = 2
+}
+
+impl From<CardanoNetworkId> for NetworkId {
+  fn from(network: CardanoNetworkId) -> Self {
+    match network {
+      CardanoNetworkId::Mainnet => NetworkId::Mainnet,
+      CardanoNetworkId::Preprod => NetworkId::Preprod,
+      CardanoNetworkId::Preview => NetworkId::Preview
+    }
+  }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct CardanoPointer {
+  slot: u64,
+  tx_index: u64,
+  cert_index: u64
+}
+
+impl From<CardanoPointer> for RPointer {
+  fn from(pointer: CardanoPointer) -> Self {
+    RPointer { slot: pointer.slot, tx_index: pointer.tx_index, cert_index: pointer.cert_index }
+  }
+}
+
+// COSE_Sign1 and COSE_Key CBOR of CIP-30 signData
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct CardanoDataSignature {
+  signature: DataPtr,
+  key: DataPtr
+}
+
+impl From<DataSignature> for CardanoDataSignature {
+  fn from(signature: DataSignature) -> Self {
+    Self { signature: signature.signature.into(), key: signature.key.into() }
+  }
+}
+
+// CIP-36 voting power delegation to 32 bytes vote key
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct CardanoDelegation {
+  vote_key: DataPtr,
+  weight: u32
+}
+
 #[no_mangle]
 pub extern "C" fn NETWORK_CARDANO() -> Network {
   CARDANO
@@ -25,3 +141,242 @@ pub unsafe extern "C" fn keypath_cardano_new(
   })
   .response(path, error)
 }
+
+#[no_mangle]
+pub unsafe extern "C" fn keypath_cardano_new_cip1852(
+  account: u32, role: CardanoRole, index: u32, path: &mut KeyPath, error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    RKeyPath::cip1852(account, role.into(), index)
+      .map_err(|err| err.into())
+      .map(|kp| (&kp as &dyn IKeyPath).into())
+  })
+  .response(path, error)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn keypath_cardano_new_cip36(
+  account: u32, index: u32, path: &mut KeyPath, error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    RKeyPath::cip36(account, index)
+      .map_err(|err| err.into())
+      .map(|kp| (&kp as &dyn IKeyPath).into())
+  })
+  .response(path, error)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn keychain_cardano_account_pub_key(
+  keychain: &KeychainPtr, path: KeyPath, key: &mut DataPtr, error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    keychain
+      .rust_ref()
+      .cardano_account_pub_key(&path.derivation_path())
+      .map(|data| DataPtr::from(data))
+  })
+  .response(key, error)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn keychain_cardano_master_key_scheme(
+  keychain: &KeychainPtr, scheme: &mut CardanoMasterKeyScheme, error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    keychain.rust_ref().cardano_master_key_scheme().map(|scheme| scheme.into())
+  })
+  .response(scheme, error)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn keychain_cardano_base_address(
+  keychain: &KeychainPtr, payment: KeyPath, stake: KeyPath, network: CardanoNetworkId,
+  address: &mut CharPtr, error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    keychain
+      .rust_ref()
+      .cardano_base_address(&payment.derivation_path(), &stake.derivation_path(), network.into())
+      .map(|address| address.to_cstr())
+  })
+  .response(address, error)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn keychain_cardano_enterprise_address(
+  keychain: &KeychainPtr, payment: KeyPath, network: CardanoNetworkId, address: &mut CharPtr,
+  error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    keychain
+      .rust_ref()
+      .cardano_enterprise_address(&payment.derivation_path(), network.into())
+      .map(|address| address.to_cstr())
+  })
+  .response(address, error)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn keychain_cardano_pointer_address(
+  keychain: &KeychainPtr, payment: KeyPath, pointer: CardanoPointer, network: CardanoNetworkId,
+  address: &mut CharPtr, error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    keychain
+      .rust_ref()
+      .cardano_pointer_address(&payment.derivation_path(), &pointer.into(), network.into())
+      .map(|address| address.to_cstr())
+  })
+  .response(address, error)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn keychain_cardano_reward_address(
+  keychain: &KeychainPtr, stake: KeyPath, network: CardanoNetworkId, address: &mut CharPtr,
+  error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    keychain
+      .rust_ref()
+      .cardano_reward_address(&stake.derivation_path(), network.into())
+      .map(|address| address.to_cstr())
+  })
+  .response(address, error)
+}
+
+// Path is a string, as Daedalus random index paths don't fit KeyPath
+#[no_mangle]
+pub unsafe extern "C" fn keychain_cardano_byron_address(
+  keychain: &KeychainPtr, path: CharPtr, network: CardanoNetworkId, address: &mut CharPtr,
+  error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    let path = DerivationPath::from(path.rust_ref())?;
+    keychain
+      .rust_ref()
+      .cardano_byron_address(&path, network.into())
+      .map(|address| address.to_cstr())
+  })
+  .response(address, error)
+}
+
+// Path is null if the address doesn't belong to the keychain
+#[no_mangle]
+pub unsafe extern "C" fn keychain_cardano_byron_address_path(
+  keychain: &KeychainPtr, address: CharPtr, path: &mut CharPtr, error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    keychain
+      .rust_ref()
+      .cardano_byron_address_path(address.rust_ref())
+      .map(|path| path.map(|path| path.to_string().to_cstr()).unwrap_or(std::ptr::null()))
+  })
+  .response(path, error)
+}
+
+// Witness set CBOR with vkey witnesses for Shelley paths and bootstrap witnesses for Byron paths
+#[no_mangle]
+pub unsafe extern "C" fn keychain_cardano_sign_transaction(
+  keychain: &KeychainPtr, body: *const u8, body_len: usize, paths: *const KeyPath,
+  paths_len: usize, byron_paths: *const CharPtr, byron_paths_len: usize, network: CardanoNetworkId,
+  witness_set: &mut DataPtr, error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    let body = std::slice::from_raw_parts(body, body_len);
+    let paths: Vec<DerivationPath> = std::slice::from_raw_parts(paths, paths_len)
+      .iter()
+      .map(|path| path.derivation_path())
+      .collect();
+    let byron_paths = std::slice::from_raw_parts(byron_paths, byron_paths_len)
+      .iter()
+      .map(|path| DerivationPath::from(path.rust_ref()))
+      .collect::<Result<Vec<DerivationPath>, _>>()?;
+    let keychain = keychain.rust_ref();
+    let vkeys = keychain.cardano_sign_transaction(body, &paths)?;
+    let bootstraps = if byron_paths.is_empty() {
+      Vec::new()
+    } else {
+      keychain.cardano_sign_bootstrap_transaction(body, &byron_paths, network.into())?
+    };
+    Ok(DataPtr::from(rwitness_set(&vkeys, &bootstraps)))
+  })
+  .response(witness_set, error)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn keychain_cardano_sign_data(
+  keychain: &KeychainPtr, path: KeyPath, address: CharPtr, payload: *const u8, payload_len: usize,
+  signature: &mut CardanoDataSignature, error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    let payload = std::slice::from_raw_parts(payload, payload_len);
+    keychain
+      .rust_ref()
+      .cardano_sign_data(&path.derivation_path(), address.rust_ref(), payload)
+      .map(|signature| signature.into())
+  })
+  .response(signature, error)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn cardano_verify_data(
+  signature: &CardanoDataSignature, address: CharPtr, payload: *const u8, payload_len: usize,
+  valid: &mut bool, error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    let signature = DataSignature {
+      signature: Vec::from(signature.signature.rust_ref()),
+      key: Vec::from(signature.key.rust_ref())
+    };
+    let payload = std::slice::from_raw_parts(payload, payload_len);
+    rverify_data(&signature, address.rust_ref(), payload)
+      .map_err(|err| RError::from_key_error(&RNetwork::CARDANO, err))
+  })
+  .response(valid, error)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn delete_cardano_data_signature(signature: &mut CardanoDataSignature) {
+  signature.signature.free();
+  signature.key.free();
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn keychain_cardano_vote_key(
+  keychain: &KeychainPtr, path: KeyPath, key: &mut DataPtr, error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    keychain.rust_ref().cardano_vote_key(&path.derivation_path()).map(|data| DataPtr::from(data))
+  })
+  .response(key, error)
+}
+
+// CIP-36 registration metadata {61284: registration, 61285: {1: signature}}
+#[no_mangle]
+pub unsafe extern "C" fn keychain_cardano_sign_vote_registration(
+  keychain: &KeychainPtr, delegations: *const CardanoDelegation, delegations_len: usize,
+  stake: KeyPath, payment_address: CharPtr, nonce: u64, voting_purpose: u64,
+  metadata: &mut DataPtr, error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    let delegations = std::slice::from_raw_parts(delegations, delegations_len)
+      .iter()
+      .map(|delegation| Delegation {
+        vote_key: Vec::from(delegation.vote_key.rust_ref()),
+        weight: delegation.weight
+      })
+      .collect();
+    let registration = VoteRegistration {
+      delegations,
+      payment_address: payment_address.rust_ref().to_owned(),
+      nonce,
+      voting_purpose
+    };
+    keychain
+      .rust_ref()
+      .cardano_sign_vote_registration(&registration, &stake.derivation_path())
+      .map(|data| DataPtr::from(data))
+  })
+  .response(metadata, error)
+}
@@ -17,7 +17,8 @@ pub enum ErrorType {
   NetworkIsNotSupported = 8,
   KeyError = 9,
   KeyPathError = 10,
-  MnemonicError = 11
+  MnemonicError = 11,
+  WrongPassphrase = 12
 }
 
 #[repr(C)]
@@ -44,6 +45,7 @@ impl ErrorPtr {
   fn error_type(err: &RError) -> ErrorType {
     match err {
       &RError::WrongPassword => ErrorType::WrongPassword,
+      &RError::WrongPassphrase => ErrorType::WrongPassphrase,
       &RError::NotEnoughData => ErrorType::NotEnoughData,
       &RError::SeedIsNotSaved => ErrorType::SeedIsNotSaved,
       &RError::CantCalculateSeedSize(_, _) => ErrorType::CantCalculateSeedSize,
@@ -1,14 +1,37 @@
 use error::ErrorPtr;
 use key_path::KeyPath;
-use keychain::networks::ethereum::KeyPath as RKeyPath;
+use keychain::networks::ethereum::{
+  recover_personal_message_signer, recover_typed_data_signer, KeyPath as RKeyPath,
+  RecoveryIdFormat, Transaction
+};
 use keychain::KeyPath as IKeyPath;
-use keychain::Network as RNetwork;
+use keychain::{Error as RError, Network as RNetwork};
+use keychain_c::KeychainPtr;
 use network::Network;
+use utils::data::DataPtr;
 use utils::panic::handle_exception_result;
+use utils::ptr::Ptr;
 use utils::result::CResult;
+use utils::string::{CharPtr, ToCString};
 
 static ETHEREUM: Network = Network(RNetwork::ETHEREUM.0);
 
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub enum EthereumRecoveryIdFormat {
+  Raw = 0,
+  Legacy = 1
+}
+
+impl From<EthereumRecoveryIdFormat> for RecoveryIdFormat {
+  fn from(format: EthereumRecoveryIdFormat) -> Self {
+    match format {
+      EthereumRecoveryIdFormat::Raw => RecoveryIdFormat::Raw,
+      EthereumRecoveryIdFormat::Legacy => RecoveryIdFormat::Legacy
+    }
+  }
+}
+
 #[no_mangle]
 pub extern "C" fn NETWORK_ETHEREUM() -> Network {
   ETHEREUM
@@ -35,3 +58,134 @@ pub unsafe extern "C" fn keypath_ethereum_new_metamask(
   })
   .response(path, error)
 }
+
+#[no_mangle]
+pub unsafe extern "C" fn keychain_ethereum_address(
+  keychain: &KeychainPtr, path: KeyPath, address: &mut CharPtr, error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    keychain
+      .rust_ref()
+      .ethereum_address(&path.derivation_path(), None)
+      .map(|address| address.to_cstr())
+  })
+  .response(address, error)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn keychain_ethereum_address_with_chain_id(
+  keychain: &KeychainPtr, path: KeyPath, chain_id: u64, address: &mut CharPtr, error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    keychain
+      .rust_ref()
+      .ethereum_address(&path.derivation_path(), Some(chain_id))
+      .map(|address| address.to_cstr())
+  })
+  .response(address, error)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn keychain_ethereum_sign_personal_message(
+  keychain: &KeychainPtr, message: *const u8, message_len: usize, path: KeyPath,
+  format: EthereumRecoveryIdFormat, signature: &mut DataPtr, error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    let message_slice = std::slice::from_raw_parts(message, message_len);
+    keychain
+      .rust_ref()
+      .ethereum_sign_personal_message(message_slice, &path.derivation_path(), format.into())
+      .map(|data| DataPtr::from(data))
+  })
+  .response(signature, error)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn keychain_ethereum_verify_personal_message(
+  keychain: &KeychainPtr, message: *const u8, message_len: usize, signature: *const u8,
+  signature_len: usize, path: KeyPath, result: &mut bool, error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    let message_slice = std::slice::from_raw_parts(message, message_len);
+    let signature_slice = std::slice::from_raw_parts(signature, signature_len);
+    keychain.rust_ref().ethereum_verify_personal_message(
+      message_slice,
+      signature_slice,
+      &path.derivation_path()
+    )
+  })
+  .response(result, error)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn keychain_ethereum_sign_typed_data(
+  keychain: &KeychainPtr, typed_data: CharPtr, path: KeyPath, format: EthereumRecoveryIdFormat,
+  signature: &mut DataPtr, error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    keychain
+      .rust_ref()
+      .ethereum_sign_typed_data(typed_data.rust_ref(), &path.derivation_path(), format.into())
+      .map(|data| DataPtr::from(data))
+  })
+  .response(signature, error)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn keychain_ethereum_verify_typed_data(
+  keychain: &KeychainPtr, typed_data: CharPtr, signature: *const u8, signature_len: usize,
+  path: KeyPath, result: &mut bool, error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    let signature_slice = std::slice::from_raw_parts(signature, signature_len);
+    keychain.rust_ref().ethereum_verify_typed_data(
+      typed_data.rust_ref(),
+      signature_slice,
+      &path.derivation_path()
+    )
+  })
+  .response(result, error)
+}
+
+// Transaction is eth_signTransaction JSON object. Returns signed raw transaction.
+#[no_mangle]
+pub unsafe extern "C" fn keychain_ethereum_sign_transaction(
+  keychain: &KeychainPtr, transaction: CharPtr, path: KeyPath, signed: &mut DataPtr,
+  error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    let transaction = Transaction::from_json(transaction.rust_ref())
+      .map_err(|err| RError::from_key_error(&RNetwork::ETHEREUM, err.into()))?;
+    keychain
+      .rust_ref()
+      .ethereum_sign_transaction(&transaction, &path.derivation_path())
+      .map(|data| DataPtr::from(data))
+  })
+  .response(signed, error)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ethereum_recover_personal_message_signer(
+  message: *const u8, message_len: usize, signature: *const u8, signature_len: usize,
+  address: &mut CharPtr, error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    let message_slice = std::slice::from_raw_parts(message, message_len);
+    let signature_slice = std::slice::from_raw_parts(signature, signature_len);
+    recover_personal_message_signer(message_slice, signature_slice).map(|address| address.to_cstr())
+  })
+  .response(address, error)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ethereum_recover_typed_data_signer(
+  typed_data: CharPtr, signature: *const u8, signature_len: usize, address: &mut CharPtr,
+  error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    let signature_slice = std::slice::from_raw_parts(signature, signature_len);
+    recover_typed_data_signer(typed_data.rust_ref(), signature_slice)
+      .map(|address| address.to_cstr())
+  })
+  .response(address, error)
+}
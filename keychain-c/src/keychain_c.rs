@@ -1,5 +1,6 @@
 use error::ErrorPtr;
 use key_path::KeyPath;
+use keychain::KeyPath as IKeyPath;
 use keychain::Keychain as RKeychain;
 use network::{Network, NetworksPtr};
 use std::ffi::c_void;
@@ -7,6 +8,7 @@ use utils::data::DataPtr;
 use utils::panic::{handle_exception, handle_exception_result};
 use utils::ptr::Ptr;
 use utils::result::CResult;
+use utils::string::{CharPtr, ToCString};
 
 #[repr(C)]
 #[derive(Copy, Clone)]
@@ -43,11 +45,41 @@ pub unsafe extern "C" fn keychain_pub_key(
   keychain: &KeychainPtr, network: Network, path: KeyPath, key: &mut DataPtr, error: &mut ErrorPtr
 ) -> bool {
   handle_exception_result(|| {
-    keychain.rust_ref().pub_key(&network.into(), &path).map(|data| DataPtr::from(data))
+    keychain
+      .rust_ref()
+      .pub_key(&network.into(), &path.derivation_path())
+      .map(|data| DataPtr::from(data))
   })
   .response(key, error)
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn keychain_account_xpub(
+  keychain: &KeychainPtr, network: Network, path: KeyPath, xpub: &mut CharPtr, error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    keychain
+      .rust_ref()
+      .account_xpub(&network.into(), &path.derivation_path())
+      .map(|xpub| xpub.to_cstr())
+  })
+  .response(xpub, error)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn keychain_address(
+  keychain: &KeychainPtr, network: Network, path: KeyPath, address: &mut CharPtr,
+  error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    keychain
+      .rust_ref()
+      .address(&network.into(), &path.derivation_path())
+      .map(|address| address.to_cstr())
+  })
+  .response(address, error)
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn keychain_sign(
   keychain: &KeychainPtr, network: Network, data: *const u8, data_len: usize, path: KeyPath,
@@ -55,7 +87,10 @@ pub unsafe extern "C" fn keychain_sign(
 ) -> bool {
   handle_exception_result(|| {
     let data_slice = std::slice::from_raw_parts(data, data_len);
-    keychain.rust_ref().sign(&network.into(), data_slice, &path).map(|data| DataPtr::from(data))
+    keychain
+      .rust_ref()
+      .sign(&network.into(), data_slice, &path.derivation_path())
+      .map(|data| DataPtr::from(data))
   })
   .response(signature, error)
 }
@@ -69,7 +104,12 @@ pub unsafe extern "C" fn keychain_verify(
     let data_slice = std::slice::from_raw_parts(data, data_len);
     let signature_slice = std::slice::from_raw_parts(signature, signature_len);
 
-    keychain.rust_ref().verify(&network.into(), data_slice, signature_slice, &path)
+    keychain.rust_ref().verify(
+      &network.into(),
+      data_slice,
+      signature_slice,
+      &path.derivation_path()
+    )
   })
   .response(result, error)
 }
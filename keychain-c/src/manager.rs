@@ -1,5 +1,11 @@
+#[cfg(feature = "cardano")]
+use cardano::CardanoMasterKeyScheme;
 use error::ErrorPtr;
-use keychain::{KeychainManager as RKeychainManager, Language as RLanguage};
+use key_path::KeyPath;
+use keychain::{
+  DerivationPath, KeyPath as IKeyPath, KeychainManager as RKeychainManager, Language as RLanguage,
+  Network as RNetwork
+};
 use keychain_c::KeychainPtr;
 use network::Network;
 use num_traits::FromPrimitive;
@@ -8,7 +14,7 @@ use utils::data::DataPtr;
 use utils::panic::{handle_exception, handle_exception_result};
 use utils::ptr::Ptr;
 use utils::result::CResult;
-use utils::string::{CharPtr, ToCString};
+use utils::string::{option_str, CharPtr, ToCString};
 
 #[repr(C)]
 #[derive(Copy, Clone)]
@@ -59,6 +65,14 @@ impl From<RLanguage> for Language {
   }
 }
 
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct AccountXPub {
+  network: Network,
+  path: KeyPath,
+  xpub: CharPtr
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn keychain_manager_new(
   manager: &mut KeychainManagerPtr, error: &mut ErrorPtr
@@ -69,6 +83,19 @@ pub unsafe extern "C" fn keychain_manager_new(
   .response(manager, error)
 }
 
+// New Cardano keys are generated with the scheme instead of Icarus
+#[cfg(feature = "cardano")]
+#[no_mangle]
+pub unsafe extern "C" fn keychain_manager_new_with_cardano_scheme(
+  scheme: CardanoMasterKeyScheme, manager: &mut KeychainManagerPtr, error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    RKeychainManager::with_cardano_scheme(scheme.into())
+      .map(|manager| KeychainManagerPtr::new(manager))
+  })
+  .response(manager, error)
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn keychain_manager_has_network(
   manager: &KeychainManagerPtr, network: Network, has: &mut bool, error: &mut ErrorPtr
@@ -104,12 +131,17 @@ pub unsafe extern "C" fn keychain_manager_keychain_data_from_seed(
 #[no_mangle]
 pub unsafe extern "C" fn keychain_manager_keychain_data_from_mnemonic(
   manager: &KeychainManagerPtr, mnemonic: CharPtr, password: CharPtr, lang: Language,
-  data: &mut DataPtr, error: &mut ErrorPtr
+  passphrase: CharPtr, data: &mut DataPtr, error: &mut ErrorPtr
 ) -> bool {
   handle_exception_result(|| {
     manager
       .rust_ref()
-      .keychain_data_from_mnemonic(mnemonic.rust_ref(), password.rust_ref(), lang.rust())
+      .keychain_data_from_mnemonic(
+        mnemonic.rust_ref(),
+        password.rust_ref(),
+        lang.rust(),
+        option_str(passphrase)
+      )
       .map(|data| DataPtr::from(data))
   })
   .response(data, error)
@@ -130,16 +162,38 @@ pub unsafe extern "C" fn keychain_manager_keychain_from_data(
   .response(keychain, error)
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn keychain_manager_keychain_from_account_xpubs(
+  manager: &KeychainManagerPtr, xpubs: *const AccountXPub, xpubs_len: usize,
+  keychain: &mut KeychainPtr, error: &mut ErrorPtr
+) -> bool {
+  handle_exception_result(|| {
+    let xpubs_slice = std::slice::from_raw_parts(xpubs, xpubs_len);
+    let paths: Vec<DerivationPath> =
+      xpubs_slice.iter().map(|account| account.path.derivation_path()).collect();
+    let accounts: Vec<(RNetwork, &DerivationPath, &str)> = xpubs_slice
+      .iter()
+      .zip(paths.iter())
+      .map(|(account, path)| (account.network.into(), path, account.xpub.rust_ref()))
+      .collect();
+    manager
+      .rust_ref()
+      .keychain_from_account_xpubs(&accounts)
+      .map(|keychain| KeychainPtr::new(keychain))
+  })
+  .response(keychain, error)
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn keychain_manager_add_network(
   manager: &KeychainManagerPtr, data: *const u8, data_len: usize, password: CharPtr,
-  network: Network, response: &mut DataPtr, error: &mut ErrorPtr
+  network: Network, passphrase: CharPtr, response: &mut DataPtr, error: &mut ErrorPtr
 ) -> bool {
   handle_exception_result(|| {
     let data_slice = std::slice::from_raw_parts(data, data_len);
     manager
       .rust_ref()
-      .add_network(data_slice, password.rust_ref(), network.into())
+      .add_network(data_slice, password.rust_ref(), network.into(), option_str(passphrase))
       .map(|data| DataPtr::from(data))
   })
   .response(response, error)
@@ -20,6 +20,15 @@ pub unsafe extern "C" fn delete_string(mut ptr: CharPtr) {
   (&mut ptr).free();
 }
 
+// Null pointer is treated as absent value
+pub unsafe fn option_str<'a>(ptr: CharPtr) -> Option<&'a str> {
+  if ptr.is_null() {
+    None
+  } else {
+    Some(CStr::from_ptr(ptr).to_str().unwrap())
+  }
+}
+
 pub trait ToCString {
   fn to_cstr(&self) -> CharPtr;
 }
@@ -0,0 +1,156 @@
+use key_path::{Error, BIP44_SOFT_UPPER_BOUND};
+use serde::de::{Deserialize, Deserializer, Error as DeError};
+use serde::ser::{Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+// BIP32 derivation path of any depth. Hardened indexes have the highest bit set.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DerivationPath {
+  path: Vec<u32>
+}
+
+impl DerivationPath {
+  pub fn new(path: Vec<u32>) -> Self {
+    Self { path }
+  }
+
+  pub fn hardened(index: u32) -> u32 {
+    index | BIP44_SOFT_UPPER_BOUND
+  }
+
+  pub fn is_hardened(index: u32) -> bool {
+    index >= BIP44_SOFT_UPPER_BOUND
+  }
+
+  pub fn path(&self) -> &[u32] {
+    &self.path
+  }
+
+  pub fn depth(&self) -> usize {
+    self.path.len()
+  }
+
+  pub fn child(&self, index: u32) -> Self {
+    let mut path = self.path.clone();
+    path.push(index);
+    Self { path }
+  }
+
+  // First `depth` indexes of the path
+  pub fn prefix(&self, depth: usize) -> Self {
+    Self { path: self.path.iter().take(depth).cloned().collect() }
+  }
+
+  pub fn starts_with(&self, other: &DerivationPath) -> bool {
+    self.path.starts_with(&other.path)
+  }
+
+  pub fn from(path: &str) -> Result<Self, Error> {
+    let parts: Vec<&str> = path.split('/').map(|s| s.trim()).collect();
+    if parts[0] != "m" {
+      return Err(Error::InvalidPathMarker(parts[0].to_owned()));
+    }
+    let indexes: Result<Vec<u32>, Error> =
+      parts.iter().enumerate().skip(1).map(|(index, s)| Self::parse_index(index, s)).collect();
+    indexes.map(|path| Self { path })
+  }
+
+  // Hardened index can be marked with ', h or H
+  fn parse_index(index: usize, s: &str) -> Result<u32, Error> {
+    if s.is_empty() {
+      return Err(Error::EmptyValueAtIndex(index));
+    }
+    let (number, hardened) = match s.chars().last() {
+      Some('\'') | Some('h') | Some('H') => (&s[..s.len() - 1], true),
+      _ => (s, false)
+    };
+    if number.is_empty() {
+      return Err(Error::EmptyValueAtIndex(index));
+    }
+    let value = str::parse::<u32>(number).map_err(|err| Error::ParseErrorAtIndex(index, err))?;
+    if Self::is_hardened(value) {
+      return Err(Error::InvalidIndex(index, value));
+    }
+    Ok(if hardened { Self::hardened(value) } else { value })
+  }
+}
+
+impl fmt::Display for DerivationPath {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "m")?;
+    for &index in self.path.iter() {
+      if Self::is_hardened(index) {
+        write!(f, "/{}'", index - BIP44_SOFT_UPPER_BOUND)?;
+      } else {
+        write!(f, "/{}", index)?;
+      }
+    }
+    Ok(())
+  }
+}
+
+impl FromStr for DerivationPath {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Self::from(s)
+  }
+}
+
+impl Serialize for DerivationPath {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(self)
+  }
+}
+
+impl<'de> Deserialize<'de> for DerivationPath {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let string = String::deserialize(deserializer)?;
+    Self::from(&string).map_err(D::Error::custom)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::DerivationPath;
+  use key_path::{GenericKeyPath, KeyPath};
+  use serde_json;
+
+  #[test]
+  fn parse_and_print() {
+    let path: DerivationPath = "m/1852'/1815h/0H/2/0".parse().unwrap();
+    assert_eq!(path.path(), &[0x8000073c, 0x80000717, 0x80000000, 2, 0]);
+    assert_eq!(path.to_string(), "m/1852'/1815'/0'/2/0");
+    assert_eq!(DerivationPath::from("m").unwrap().depth(), 0);
+    assert_eq!(DerivationPath::from("m/83696968'/39'/0'/12'/0'").unwrap().depth(), 5);
+  }
+
+  #[test]
+  fn invalid_paths() {
+    assert!(DerivationPath::from("").is_err());
+    assert!(DerivationPath::from("44'/0'").is_err());
+    assert!(DerivationPath::from("m/44'/").is_err());
+    assert!(DerivationPath::from("m/'").is_err());
+    assert!(DerivationPath::from("m/2147483648").is_err());
+    assert!(DerivationPath::from("m/0x").is_err());
+    assert!(DerivationPath::from("m/0é").is_err());
+  }
+
+  #[test]
+  fn from_key_path() {
+    let key_path = GenericKeyPath::from("m/44'/60'/0'/0/1").unwrap();
+    let path: DerivationPath = (&key_path as &dyn KeyPath).into();
+    assert_eq!(path, DerivationPath::from("m/44'/60'/0'/0/1").unwrap());
+    let concrete: DerivationPath = (&key_path).into();
+    assert_eq!(concrete, path);
+  }
+
+  #[test]
+  fn serde() {
+    let path = DerivationPath::from("m/44'/60'/0'").unwrap();
+    let json = serde_json::to_string(&path).unwrap();
+    assert_eq!(json, "\"m/44'/60'/0'\"");
+    assert_eq!(serde_json::from_str::<DerivationPath>(&json).unwrap(), path);
+  }
+}
@@ -10,6 +10,7 @@ use std::fmt;
 #[derive(Debug)]
 pub enum Error {
   WrongPassword,
+  WrongPassphrase,
   NotEnoughData,
   SeedIsNotSaved,
   CantCalculateSeedSize(usize, usize),
@@ -27,6 +28,7 @@ impl fmt::Display for Error {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
     match self {
       &Error::WrongPassword => write!(f, "Wrong password"),
+      &Error::WrongPassphrase => write!(f, "Passphrase doesn't match keychain keys"),
       &Error::NotEnoughData => write!(f, "Not enough data to load keychain"),
       &Error::SeedIsNotSaved => write!(f, "Seed is not saved"),
       &Error::CantCalculateSeedSize(min, max) => {
@@ -1,6 +1,8 @@
-use key_path::{Error as KeyPathError, KeyPath};
+use derivation_path::DerivationPath;
+use key_path::Error as KeyPathError;
 use mnemonic::Error as MnemonicError;
 use network::Network;
+use std::any::Any;
 use std::fmt;
 
 #[cfg(any(feature = "ethereum", feature = "bitcoin"))]
@@ -12,7 +14,11 @@ pub enum Error {
   InvalidMnemonic(MnemonicError),
   InvalidKeySize(usize, usize),
   InvalidKeyData(Box<dyn std::error::Error>),
+  InvalidData(Box<dyn std::error::Error>),
   InvalidSignatureSize(usize, usize),
+  InvalidSignature(Box<dyn std::error::Error>),
+  PrivateKeyIsNotAvailable,
+  OperationIsNotSupported(&'static str),
   SignError(Box<dyn std::error::Error>)
 }
 
@@ -25,9 +31,17 @@ impl fmt::Display for Error {
         write!(f, "Invalid key size {}, accepts {}", size, good)
       }
       &Error::InvalidKeyData(ref err) => write!(f, "Invalid key data: {}", err),
+      &Error::InvalidData(ref err) => write!(f, "Invalid data: {}", err),
       &Error::InvalidSignatureSize(size, good) => {
         write!(f, "Invalid signature size {}, accepts {}", size, good)
       }
+      &Error::InvalidSignature(ref err) => write!(f, "Invalid signature: {}", err),
+      &Error::OperationIsNotSupported(operation) => {
+        write!(f, "Operation {} is not supported", operation)
+      }
+      &Error::PrivateKeyIsNotAvailable => {
+        write!(f, "Private key is not available in watch-only key")
+      }
       &Error::SignError(ref err) => write!(f, "Sign error: {}", err)
     }
   }
@@ -57,6 +71,9 @@ impl From<SecpKeyError> for Error {
   fn from(err: SecpKeyError) -> Self {
     match err {
       SecpKeyError::InvalidSignature(bad, good) => Error::InvalidSignatureSize(bad, good),
+      SecpKeyError::InvalidDerSignature | SecpKeyError::InvalidRecoveryId => {
+        Error::InvalidSignature(Box::new(err))
+      }
       _ => Error::InvalidKeyData(Box::new(err))
     }
   }
@@ -67,11 +84,23 @@ impl std::error::Error for Error {}
 pub trait Key {
   fn network(&self) -> Network;
 
-  fn pub_key(&self, path: &dyn KeyPath) -> Result<Vec<u8>, Error>;
+  fn pub_key(&self, path: &DerivationPath) -> Result<Vec<u8>, Error>;
+
+  // Default address format of the network
+  fn address(&self, path: &DerivationPath) -> Result<String, Error>;
 
-  fn sign(&self, data: &[u8], path: &dyn KeyPath) -> Result<Vec<u8>, Error>;
+  // Extended public key of the path account. Path parts below the account are ignored.
+  fn account_xpub(&self, path: &DerivationPath) -> Result<String, Error>;
 
-  fn verify(&self, data: &[u8], signature: &[u8], path: &dyn KeyPath) -> Result<bool, Error>;
+  fn sign(&self, data: &[u8], path: &DerivationPath) -> Result<Vec<u8>, Error>;
+
+  fn verify(&self, data: &[u8], signature: &[u8], path: &DerivationPath) -> Result<bool, Error>;
+
+  // Concrete key for network specific Keychain methods. Keys of other crates keep the
+  // default, so these methods report their network as not supported.
+  fn as_any(&self) -> Option<&dyn Any> {
+    None
+  }
 
   fn boxed(self) -> Box<Self>
   where
@@ -1,3 +1,4 @@
+use derivation_path::DerivationPath;
 use key::{Error as KeyError, Key};
 use network::Network;
 
@@ -17,6 +18,14 @@ impl SeedSize {
   }
 }
 
+// BIP39 seed with the mnemonic entropy and passphrase it's calculated from.
+// Entropy is missing for keychains created from a raw seed.
+pub struct KeySeed {
+  pub seed: Vec<u8>,
+  pub entropy: Option<Vec<u8>>,
+  pub passphrase: String
+}
+
 pub trait KeyFactory {
   fn network(&self) -> Network;
 
@@ -26,6 +35,21 @@ pub trait KeyFactory {
 
   fn key_data_from_seed(&self, seed: &[u8]) -> Result<Vec<u8>, KeyError>;
 
+  // Networks which generate keys from mnemonic entropy instead of BIP39 seed override it
+  fn key_data(&self, seed: &KeySeed) -> Result<Vec<u8>, KeyError> {
+    self.key_data_from_seed(&seed.seed)
+  }
+
+  // Checks that stored key data was generated from the seed. Used to detect wrong passphrase.
+  fn check_key_data(&self, data: &[u8], seed: &KeySeed) -> Result<bool, KeyError> {
+    self.key_data(seed).map(|generated| generated == data)
+  }
+
+  // Watch-only key for the path account. Path parts below the account are ignored.
+  fn key_from_account_xpub(
+    &self, path: &DerivationPath, xpub: &str
+  ) -> Result<Box<dyn Key>, KeyError>;
+
   fn boxed(self) -> Box<Self>
   where
     Self: Sized
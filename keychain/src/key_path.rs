@@ -1,3 +1,4 @@
+use derivation_path::DerivationPath;
 use std::fmt;
 
 // special number for BIP44 purpose
@@ -9,6 +10,12 @@ pub const BIP44_SOFT_UPPER_BOUND: u32 = 0x80000000;
 // length of bip44
 pub const KEY_PATH_PARTS_COUNT: usize = 6;
 
+// depth of purpose / coin / account path
+pub const ACCOUNT_DEPTH: usize = 3;
+
+// depth of purpose / coin / account / change / address path
+pub const ADDRESS_DEPTH: usize = 5;
+
 #[derive(Debug, Clone)]
 pub enum Error {
   InvalidPartsCount(usize),
@@ -18,6 +25,8 @@ pub enum Error {
   InvalidAccount(u32),
   InvalidChange(u32),
   InvalidAddress(u32),
+  InvalidDepth(usize),
+  InvalidIndex(usize, u32),
   EmptyValueAtIndex(usize),
   ParseErrorAtIndex(usize, std::num::ParseIntError)
 }
@@ -40,6 +49,8 @@ impl fmt::Display for Error {
       &Error::InvalidAccount(account) => write!(f, "Invalid account {}", account),
       &Error::InvalidChange(change) => write!(f, "Invalid change {}", change),
       &Error::InvalidAddress(addr) => write!(f, "Invalid address {}", addr),
+      &Error::InvalidDepth(depth) => write!(f, "Invalid path depth {}", depth),
+      &Error::InvalidIndex(index, value) => write!(f, "Invalid value {} at index {}", value, index),
       &Error::EmptyValueAtIndex(index) => write!(f, "Found empty value at index: {}", index),
       &Error::ParseErrorAtIndex(index, ref err) => {
         write!(f, "Can't parse number at index {}, error: {}", index, err)
@@ -56,6 +67,16 @@ pub trait KeyPath {
   fn account(&self) -> u32;
   fn change(&self) -> u32;
   fn address(&self) -> u32;
+
+  fn derivation_path(&self) -> DerivationPath {
+    DerivationPath::new(vec![
+      self.purpose(),
+      self.coin(),
+      self.account(),
+      self.change(),
+      self.address(),
+    ])
+  }
 }
 
 pub struct GenericKeyPath {
@@ -67,55 +88,19 @@ pub struct GenericKeyPath {
 }
 
 impl GenericKeyPath {
-  fn hard_int(index: usize, s: &str) -> Result<u32, Error> {
-    if s.len() == 0 {
-      return Err(Error::EmptyValueAtIndex(index));
-    }
-    Self::soft_int(index, &s[..s.len() - 1]).map(|val| val + BIP44_SOFT_UPPER_BOUND)
-  }
-
-  fn soft_int(index: usize, s: &str) -> Result<u32, Error> {
-    if s.len() == 0 {
-      return Err(Error::EmptyValueAtIndex(index));
-    }
-    str::parse::<u32>(s).map_err(|err| Error::ParseErrorAtIndex(index, err))
-  }
-
-  fn parse_int(index: usize, s: &str) -> Result<u32, Error> {
-    if s.len() == 0 {
-      return Err(Error::EmptyValueAtIndex(index));
-    }
-    if &s[s.len() - 1..] == "'" {
-      Self::hard_int(index, s)
-    } else {
-      Self::soft_int(index, s)
-    }
-  }
-
-  fn print_int(val: u32) -> String {
-    if val >= BIP44_SOFT_UPPER_BOUND {
-      format!("{}'", (val - BIP44_SOFT_UPPER_BOUND))
-    } else {
-      val.to_string()
-    }
-  }
-
   pub fn from(path: &str) -> Result<Self, Error> {
-    let parts: Vec<&str> = path.split("/").map(|s| s.trim()).collect();
-    if parts.len() != KEY_PATH_PARTS_COUNT {
-      return Err(Error::InvalidPartsCount(parts.len()));
-    }
-    if parts[0] != "m" {
-      return Err(Error::InvalidPathMarker(parts[0].to_owned()));
+    let parsed = DerivationPath::from(path)?;
+    if parsed.depth() != ADDRESS_DEPTH {
+      return Err(Error::InvalidPartsCount(parsed.depth() + 1));
     }
-
-    let purpose = Self::parse_int(1, parts[1])?;
-    let coin = Self::parse_int(2, parts[2])?;
-    let account = Self::parse_int(3, parts[3])?;
-    let change = Self::parse_int(4, parts[4])?;
-    let address = Self::parse_int(5, parts[5])?;
-
-    Ok(Self { purpose, coin, account, change, address })
+    let indexes = parsed.path();
+    Ok(Self {
+      purpose: indexes[0],
+      coin: indexes[1],
+      account: indexes[2],
+      change: indexes[3],
+      address: indexes[4]
+    })
   }
 
   pub fn to_string(&self) -> String {
@@ -145,15 +130,15 @@ impl KeyPath for GenericKeyPath {
   }
 }
 
+// Keychain methods take DerivationPath. BIP44 key paths convert with `&path.into()`.
+impl<'a, P: KeyPath + ?Sized> From<&'a P> for DerivationPath {
+  fn from(path: &'a P) -> Self {
+    path.derivation_path()
+  }
+}
+
 impl<'a> From<&'a GenericKeyPath> for String {
   fn from(path: &'a GenericKeyPath) -> Self {
-    format!(
-      "m/{}/{}/{}/{}/{}",
-      GenericKeyPath::print_int(path.purpose()),
-      GenericKeyPath::print_int(path.coin()),
-      GenericKeyPath::print_int(path.account()),
-      GenericKeyPath::print_int(path.change()),
-      GenericKeyPath::print_int(path.address())
-    )
+    path.derivation_path().to_string()
   }
 }
@@ -1,6 +1,6 @@
+use derivation_path::DerivationPath;
 use error::Error;
 use key::Key;
-use key_path::KeyPath;
 use network::Network;
 use std::collections::HashMap;
 
@@ -23,16 +23,26 @@ impl Keychain {
     self.keys.keys().cloned().collect()
   }
 
-  pub fn pub_key(&self, network: &Network, path: &dyn KeyPath) -> Result<Vec<u8>, Error> {
+  pub fn pub_key(&self, network: &Network, path: &DerivationPath) -> Result<Vec<u8>, Error> {
     self._pk(network)?.pub_key(path).map_err(|err| Error::from_key_error(network, err))
   }
 
-  pub fn sign(&self, network: &Network, data: &[u8], path: &dyn KeyPath) -> Result<Vec<u8>, Error> {
+  pub fn address(&self, network: &Network, path: &DerivationPath) -> Result<String, Error> {
+    self._pk(network)?.address(path).map_err(|err| Error::from_key_error(network, err))
+  }
+
+  pub fn account_xpub(&self, network: &Network, path: &DerivationPath) -> Result<String, Error> {
+    self._pk(network)?.account_xpub(path).map_err(|err| Error::from_key_error(network, err))
+  }
+
+  pub fn sign(
+    &self, network: &Network, data: &[u8], path: &DerivationPath
+  ) -> Result<Vec<u8>, Error> {
     self._pk(network)?.sign(data, path).map_err(|err| Error::from_key_error(network, err))
   }
 
   pub fn verify(
-    &self, network: &Network, data: &[u8], signature: &[u8], path: &dyn KeyPath
+    &self, network: &Network, data: &[u8], signature: &[u8], path: &DerivationPath
   ) -> Result<bool, Error> {
     self
       ._pk(network)?
@@ -42,6 +52,16 @@ impl Keychain {
 }
 
 impl Keychain {
+  pub(crate) fn network_key<'a, K: Key + 'static>(
+    &'a self, network: &Network
+  ) -> Result<&'a K, Error> {
+    self
+      ._pk(network)?
+      .as_any()
+      .and_then(|key| key.downcast_ref::<K>())
+      .ok_or_else(|| Error::NetworkIsNotSupported(network.clone()))
+  }
+
   fn _pk<'a>(&'a self, network: &Network) -> Result<&'a dyn Key, Error> {
     self
       .keys
@@ -19,17 +19,27 @@ extern crate rand_os;
 extern crate enum_primitive_derive;
 extern crate num_traits;
 
+// BIP39 passphrase normalization
+extern crate unicode_normalization;
+
 // our bip39 lib
 pub extern crate bip39;
 
 #[cfg(feature = "cardano")]
 extern crate ed25519_bip32;
 
+#[cfg(feature = "cardano")]
+extern crate bs58;
+
 #[cfg(any(feature = "ethereum", feature = "bitcoin"))]
 extern crate secp256k1_bip32;
 
+#[cfg(any(feature = "bitcoin", feature = "cardano"))]
+extern crate bech32;
+
 // Internal modules
 mod data;
+mod derivation_path;
 mod entropy;
 mod error;
 mod key;
@@ -45,6 +55,7 @@ pub mod crypt;
 pub mod networks;
 
 //Exports
+pub use derivation_path::DerivationPath;
 pub use error::Error;
 pub use key_path::GenericKeyPath;
 pub use key_path::KeyPath;
@@ -2,14 +2,20 @@ use std::collections::HashMap;
 
 use crypt;
 use data::{VersionedData, WalletDataV2};
+use derivation_path::DerivationPath;
 use entropy::{Entropy, OsEntropy};
 use error::Error;
 use key::Key;
-use key_factory::KeyFactory;
+use key_factory::{KeyFactory, KeySeed};
 use keychain::Keychain;
-use mnemonic::{generate_entropy, mnemonic_from_entropy, seed_from_mnemonic, Language, SEED_SIZE};
+use mnemonic::{
+  entropy_from_mnemonic, generate_entropy, mnemonic_from_entropy, seed_from_mnemonic, Language,
+  SEED_SIZE
+};
 use network::Network;
 use networks::all_networks;
+#[cfg(feature = "cardano")]
+use networks::cardano::{self, MasterKeyScheme};
 
 pub struct KeychainManager {
   factories: HashMap<Network, Box<dyn KeyFactory>>,
@@ -33,6 +39,22 @@ impl KeychainManager {
     Self::with_factory_objs(filtered)
   }
 
+  // New Cardano keys are generated with the scheme instead of Icarus. Stored keys keep their own.
+  #[cfg(feature = "cardano")]
+  pub fn with_cardano_scheme(scheme: MasterKeyScheme) -> Result<Self, Error> {
+    let factories: Vec<Box<dyn KeyFactory>> = all_networks()
+      .into_iter()
+      .map(|factory| -> Box<dyn KeyFactory> {
+        if factory.network() == Network::CARDANO {
+          cardano::KeyFactory::with_scheme(scheme).boxed()
+        } else {
+          factory
+        }
+      })
+      .collect();
+    Self::with_factory_objs(factories)
+  }
+
   #[cfg(feature = "custom-networks")]
   pub fn with_factories(factories: Vec<Box<KeyFactory>>) -> Result<Self, Error> {
     Self::with_factory_objs(factories)
@@ -53,14 +75,15 @@ impl KeychainManager {
   }
 
   pub fn keychain_data_from_seed(&self, seed: &[u8], password: &str) -> Result<Vec<u8>, Error> {
-    self.new_keychain_data(Some(seed), password, None, None)
+    self.new_keychain_data(Some(seed), password, None, None, None)
   }
 
+  // BIP39 passphrase isn't saved in keychain data. It should be provided to `add_network` again.
   pub fn keychain_data_from_mnemonic(
-    &self, mnemonic: &str, password: &str, language: Option<Language>
+    &self, mnemonic: &str, password: &str, language: Option<Language>, passphrase: Option<&str>
   ) -> Result<Vec<u8>, Error> {
     let lang = language.unwrap_or_default();
-    self.new_keychain_data(None, password, Some(mnemonic), Some(lang))
+    self.new_keychain_data(None, password, Some(mnemonic), Some(lang), passphrase)
   }
 
   pub fn keychain_from_data(&self, data: &[u8], password: &str) -> Result<Keychain, Error> {
@@ -77,6 +100,26 @@ impl KeychainManager {
     keys_result.map(|keys| Keychain::new(keys))
   }
 
+  // Watch-only keychain. Can derive public keys and verify signatures, but can't sign.
+  pub fn keychain_from_account_xpubs(
+    &self, xpubs: &[(Network, &DerivationPath, &str)]
+  ) -> Result<Keychain, Error> {
+    let mut networks: Vec<Network> = Vec::with_capacity(xpubs.len());
+    let mut keys: Vec<Box<dyn Key>> = Vec::with_capacity(xpubs.len());
+    for &(network, path, xpub) in xpubs {
+      let factory = self.factories.get(&network).ok_or(Error::NetworkIsNotSupported(network))?;
+      if networks.contains(&network) {
+        return Err(Error::KeyAlreadyExist(network));
+      }
+      let key = factory
+        .key_from_account_xpub(path, xpub)
+        .map_err(|err| Error::from_key_error(&network, err))?;
+      networks.push(network);
+      keys.push(key);
+    }
+    Ok(Keychain::new(keys))
+  }
+
   pub fn change_password(
     &self, encrypted: &[u8], old_password: &str, new_password: &str
   ) -> Result<Vec<u8>, Error> {
@@ -85,8 +128,9 @@ impl KeychainManager {
       .map(|decrypted| crypt::encrypt(&decrypted, new_password, self.random.as_ref()))
   }
 
+  // Passphrase is used only for keychains created from mnemonic. It's checked against stored keys.
   pub fn add_network(
-    &self, encrypted: &[u8], password: &str, network: Network
+    &self, encrypted: &[u8], password: &str, network: Network, passphrase: Option<&str>
   ) -> Result<Vec<u8>, Error> {
     let factory = self.factories.get(&network).ok_or(Error::NetworkIsNotSupported(network))?;
     let mut data = Self::keychain_data_from_bytes(encrypted, password)?;
@@ -97,11 +141,12 @@ impl KeychainManager {
     let seed = self.seed_from_data(
       data.seed.as_ref().map(|s| s.as_ref()),
       data.mnemonic.as_ref().map(|m| m.as_ref()),
-      data.dictionary
+      data.dictionary,
+      passphrase
     )?;
+    self.check_seed(&seed, &data.keys)?;
 
-    let key_data =
-      factory.key_data_from_seed(&seed).map_err(|err| Error::from_key_error(&network, err))?;
+    let key_data = factory.key_data(&seed).map_err(|err| Error::from_key_error(&network, err))?;
 
     data.keys.insert(network, key_data);
 
@@ -153,17 +198,21 @@ impl KeychainManager {
   }
 
   fn seed_from_data(
-    &self, seed: Option<&[u8]>, mnemonic: Option<&str>, lang: Option<Language>
-  ) -> Result<Vec<u8>, Error> {
+    &self, seed: Option<&[u8]>, mnemonic: Option<&str>, lang: Option<Language>,
+    passphrase: Option<&str>
+  ) -> Result<KeySeed, Error> {
     seed.map_or_else(
       || {
         let mnem = mnemonic.ok_or(Error::SeedIsNotSaved)?;
         let lang = lang.ok_or(Error::SeedIsNotSaved)?;
-        seed_from_mnemonic(mnem, "", self.seed_size, lang).map_err(|err| Error::from(err))
+        let passphrase = passphrase.unwrap_or("");
+        let seed = seed_from_mnemonic(mnem, passphrase, self.seed_size, lang)?;
+        let entropy = entropy_from_mnemonic(mnem, lang)?;
+        Ok(KeySeed { seed, entropy: Some(entropy), passphrase: passphrase.to_owned() })
       },
       |seed| {
         if seed.len() == SEED_SIZE {
-          Ok(Vec::from(seed))
+          Ok(KeySeed { seed: Vec::from(seed), entropy: None, passphrase: String::new() })
         } else {
           Err(Error::InvalidSeedSize(seed.len()))
         }
@@ -172,9 +221,10 @@ impl KeychainManager {
   }
 
   fn new_keychain_data(
-    &self, seed: Option<&[u8]>, password: &str, mnemonic: Option<&str>, lang: Option<Language>
+    &self, seed: Option<&[u8]>, password: &str, mnemonic: Option<&str>, lang: Option<Language>,
+    passphrase: Option<&str>
   ) -> Result<Vec<u8>, Error> {
-    let calculated_seed = self.seed_from_data(seed, mnemonic, lang)?;
+    let calculated_seed = self.seed_from_data(seed, mnemonic, lang, passphrase)?;
 
     let pkeys: Result<HashMap<Network, Vec<u8>>, Error> = self
       .factories
@@ -182,7 +232,7 @@ impl KeychainManager {
       .into_iter()
       .map(|fact| {
         fact
-          .key_data_from_seed(&calculated_seed)
+          .key_data(&calculated_seed)
           .map(|data| (fact.network(), data))
           .map_err(|err| Error::from_key_error(&fact.network(), err))
       })
@@ -201,6 +251,23 @@ impl KeychainManager {
       .map_err(|err| Error::from(err))
   }
 
+  // Keys are derived from the same seed, so a wrong passphrase gives different key data
+  fn check_seed(&self, seed: &KeySeed, keys: &HashMap<Network, Vec<u8>>) -> Result<(), Error> {
+    let stored = keys.iter().find(|&(network, _)| self.factories.contains_key(network));
+    match stored {
+      Some((network, key)) => {
+        let matches = self.factories[network]
+          .check_key_data(key, seed)
+          .map_err(|err| Error::from_key_error(network, err))?;
+        if !matches {
+          return Err(Error::WrongPassphrase);
+        }
+        Ok(())
+      }
+      None => Ok(())
+    }
+  }
+
   fn keychain_data_from_bytes(bytes: &[u8], password: &str) -> Result<WalletDataV2, Error> {
     let decrypted = crypt::decrypt(bytes, password).map_err(|err| Error::from(err))?;
     VersionedData::from_bytes(&decrypted)
@@ -208,3 +275,127 @@ impl KeychainManager {
       .map_err(|err| Error::from(err))
   }
 }
+
+#[cfg(all(test, feature = "ethereum", feature = "cardano"))]
+mod tests {
+  use super::KeychainManager;
+  use bip39::util::hex;
+  use derivation_path::DerivationPath;
+  use ed25519_bip32::{DerivationScheme, XPrv, XPRV_SIZE};
+  use error::Error;
+  use mnemonic::{seed_from_mnemonic, Language};
+  use network::Network;
+  use networks::cardano::{MasterKeyScheme, NetworkId};
+
+  const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon \
+                          abandon abandon about";
+
+  #[test]
+  fn bip39_passphrase() {
+    let ethereum = KeychainManager::with_networks(&[Network::ETHEREUM]).unwrap();
+    let path = DerivationPath::from("m/44'/60'/0'/0/0").unwrap();
+
+    let plain = ethereum.keychain_data_from_mnemonic(MNEMONIC, "pwd", None, None).unwrap();
+    let data = ethereum.keychain_data_from_mnemonic(MNEMONIC, "pwd", None, Some("TREZOR")).unwrap();
+    let plain_key =
+      ethereum.keychain_from_data(&plain, "pwd").unwrap().pub_key(&Network::ETHEREUM, &path);
+    let key = ethereum.keychain_from_data(&data, "pwd").unwrap().pub_key(&Network::ETHEREUM, &path);
+    assert_ne!(plain_key.unwrap(), key.unwrap());
+
+    let manager = KeychainManager::with_networks(&[Network::ETHEREUM, Network::CARDANO]).unwrap();
+    match manager.add_network(&data, "pwd", Network::CARDANO, Some("trezor")) {
+      Err(Error::WrongPassphrase) => {}
+      _ => panic!("wrong passphrase should be rejected")
+    }
+    match manager.add_network(&data, "pwd", Network::CARDANO, None) {
+      Err(Error::WrongPassphrase) => {}
+      _ => panic!("missing passphrase should be rejected")
+    }
+    assert!(manager.add_network(&data, "pwd", Network::CARDANO, Some("TREZOR")).is_ok());
+  }
+
+  #[test]
+  fn cardano_master_key_scheme() {
+    let manager = KeychainManager::with_networks(&[Network::ETHEREUM, Network::CARDANO]).unwrap();
+    let path = DerivationPath::from("m/1852'/1815'/0'/0/0").unwrap();
+
+    // Icarus keys need mnemonic entropy, raw seed needs Legacy scheme selected
+    let seed = seed_from_mnemonic(MNEMONIC, "", 128, Language::English).unwrap();
+    assert!(manager.keychain_data_from_seed(&seed, "pwd").is_err());
+    let seed_manager = KeychainManager::with_cardano_scheme(MasterKeyScheme::Legacy).unwrap();
+    let legacy = seed_manager.keychain_data_from_seed(&seed, "pwd").unwrap();
+    let legacy = seed_manager.keychain_from_data(&legacy, "pwd").unwrap();
+    assert_eq!(legacy.cardano_master_key_scheme().unwrap(), MasterKeyScheme::Legacy);
+
+    let data = manager.keychain_data_from_mnemonic(MNEMONIC, "pwd", None, Some("foo")).unwrap();
+    let icarus = manager.keychain_from_data(&data, "pwd").unwrap();
+    assert_eq!(icarus.cardano_master_key_scheme().unwrap(), MasterKeyScheme::Icarus);
+    assert_ne!(
+      icarus.pub_key(&Network::CARDANO, &path).unwrap(),
+      legacy.pub_key(&Network::CARDANO, &path).unwrap()
+    );
+
+    let ethereum = KeychainManager::with_networks(&[Network::ETHEREUM]).unwrap();
+    let data = ethereum.keychain_data_from_mnemonic(MNEMONIC, "pwd", None, Some("foo")).unwrap();
+    let added = manager.add_network(&data, "pwd", Network::CARDANO, Some("foo")).unwrap();
+    let added = manager.keychain_from_data(&added, "pwd").unwrap();
+    assert_eq!(
+      added.pub_key(&Network::CARDANO, &path).unwrap(),
+      icarus.pub_key(&Network::CARDANO, &path).unwrap()
+    );
+  }
+
+  #[test]
+  fn cardano_ledger_scheme() {
+    let manager = KeychainManager::with_cardano_scheme(MasterKeyScheme::Ledger).unwrap();
+    let data = manager.keychain_data_from_mnemonic(MNEMONIC, "pwd", None, None).unwrap();
+    let keychain = manager.keychain_from_data(&data, "pwd").unwrap();
+    assert_eq!(keychain.cardano_master_key_scheme().unwrap(), MasterKeyScheme::Ledger);
+    let ethereum = KeychainManager::with_networks(&[Network::ETHEREUM]).unwrap();
+    let data = ethereum.keychain_data_from_mnemonic(MNEMONIC, "pwd", None, None).unwrap();
+    let added = manager.add_network(&data, "pwd", Network::CARDANO, None).unwrap();
+    let added = manager.keychain_from_data(&added, "pwd").unwrap();
+    assert_eq!(added.cardano_master_key_scheme().unwrap(), MasterKeyScheme::Ledger);
+
+    // CIP-3 Ledger test vector. Its mnemonic is longer than manager accepts, so seed is used.
+    let mnemonic = "recall grace sport punch exhibit mad harbor stand obey short width stem \
+                    awkward used stairs wool ugly trap season stove worth toward congress jaguar";
+    let seed = seed_from_mnemonic(mnemonic, "", 256, Language::English).unwrap();
+    let data = manager.keychain_data_from_seed(&seed, "pwd").unwrap();
+    let keychain = manager.keychain_from_data(&data, "pwd").unwrap();
+    let root = hex::decode(
+      "a08cf85b564ecf3b947d8d4321fb96d70ee7bb760877e371899b14e2ccf88658\
+       104b884682b57efd97decbb318a45c05a527b9cc5c2f64f7352935a049ceea60\
+       680d52308194ccef2a18e6812b452a5815fbd7f5babc083856919aaf668fe7e4"
+    );
+    let mut bytes = [0u8; XPRV_SIZE];
+    bytes.copy_from_slice(&root.unwrap());
+    let account = DerivationPath::from("m/1852'/1815'/0'").unwrap();
+    let expected =
+      account.path().iter().fold(XPrv::from_bytes_verified(bytes).unwrap(), |key, &index| {
+        key.derive(DerivationScheme::V2, index)
+      });
+    assert_eq!(keychain.cardano_account_pub_key(&account).unwrap(), expected.public().as_ref());
+  }
+
+  #[test]
+  fn cardano_daedalus_scheme() {
+    let manager = KeychainManager::with_cardano_scheme(MasterKeyScheme::Daedalus).unwrap();
+    let data = manager.keychain_data_from_mnemonic(MNEMONIC, "pwd", None, None).unwrap();
+    let keychain = manager.keychain_from_data(&data, "pwd").unwrap();
+    assert_eq!(keychain.cardano_master_key_scheme().unwrap(), MasterKeyScheme::Daedalus);
+
+    let path = DerivationPath::from("m/0'/1285827418'").unwrap();
+    let address = keychain.cardano_byron_address(&path, NetworkId::Mainnet).unwrap();
+    assert_eq!(
+      address,
+      "DdzFFzCqrhsrXwMwRbJvWyFXaxfB5CiuV9NQJBVwLagXofQ616bDaHJRePsy6tovqNGyHATn3TxQN9ffj6b4EiNGjs83\
+       9gEPQwNeXMrN"
+    );
+    assert_eq!(keychain.cardano_byron_address_path(&address).unwrap(), Some(path));
+
+    // Daedalus keys need mnemonic entropy
+    let seed = seed_from_mnemonic(MNEMONIC, "", 128, Language::English).unwrap();
+    assert!(manager.keychain_data_from_seed(&seed, "pwd").is_err());
+  }
+}
@@ -1,6 +1,7 @@
 use bip39;
 use entropy::Entropy;
 use std::fmt;
+use unicode_normalization::UnicodeNormalization;
 
 pub const SEED_SIZE: usize = bip39::SEED_SIZE;
 
@@ -105,5 +106,14 @@ pub fn seed_from_mnemonic(
     return Err(Error::MnemonicToLong(words_count, size_words));
   }
   let mnemonic_string = mnemonics.to_string(language.to_dict());
-  Ok(Vec::from(bip39::Seed::from_mnemonic_string(&mnemonic_string, unique.as_bytes()).as_ref()))
+  // BIP39 requires passphrase to be in NFKD form
+  let passphrase: String = unique.nfkd().collect();
+  Ok(Vec::from(bip39::Seed::from_mnemonic_string(&mnemonic_string, passphrase.as_bytes()).as_ref()))
+}
+
+pub fn entropy_from_mnemonic(mnemonic: &str, language: Language) -> Result<Vec<u8>, Error> {
+  bip39::Mnemonics::from_string(language.to_dict(), mnemonic)
+    .and_then(|mnemonics| bip39::Entropy::from_mnemonics(&mnemonics))
+    .map(|entropy| Vec::from(entropy.as_ref()))
+    .map_err(|err| err.into())
 }
@@ -0,0 +1,302 @@
+use super::key_path::{BIP49_PURPOSE, BIP84_PURPOSE, BIP86_PURPOSE};
+use super::network_type::NetworkType;
+use bech32::{u5, FromBase32, ToBase32, Variant};
+use key::Error;
+use key_path::{Error as KPError, BIP44_PURPOSE};
+use secp256k1_bip32::base58;
+use secp256k1_bip32::hash::hash160;
+use secp256k1_bip32::{Version, XPrv, XPub};
+use std::fmt;
+
+// Witness version 0 program with 20-byte key hash
+const P2WPKH_SCRIPT_PREFIX: [u8; 2] = [0x00, 0x14];
+
+// Script opcodes used by standard output scripts
+pub(super) const OP_0: u8 = 0x00;
+pub(super) const OP_1: u8 = 0x51;
+pub(super) const OP_RETURN: u8 = 0x6a;
+const OP_DUP: u8 = 0x76;
+const OP_EQUAL: u8 = 0x87;
+const OP_EQUALVERIFY: u8 = 0x88;
+const OP_HASH160: u8 = 0xa9;
+const OP_CHECKSIG: u8 = 0xac;
+
+const HASH160_PUSH: u8 = 0x14;
+const TAPROOT_PUSH: u8 = 0x20;
+
+// WIF suffix for keys which have compressed public key
+const WIF_COMPRESSED_FLAG: u8 = 0x01;
+
+#[derive(Debug)]
+pub enum AddressError {
+  UnknownNetwork,
+  InvalidWitnessProgram,
+  InvalidLength(usize)
+}
+
+impl fmt::Display for AddressError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      &AddressError::UnknownNetwork => write!(f, "Unknown address network"),
+      &AddressError::InvalidWitnessProgram => write!(f, "Invalid witness program"),
+      &AddressError::InvalidLength(len) => write!(f, "Invalid address length {}", len)
+    }
+  }
+}
+
+impl std::error::Error for AddressError {}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum AddressType {
+  // BIP44 legacy address
+  P2PKH,
+  // BIP49 segwit address nested in P2SH
+  P2SHP2WPKH,
+  // BIP84 native segwit address
+  P2WPKH,
+  // BIP86 taproot address with key path spending only
+  P2TR
+}
+
+impl AddressType {
+  pub fn from_purpose(purpose: u32) -> Result<Self, KPError> {
+    match purpose {
+      BIP44_PURPOSE => Ok(AddressType::P2PKH),
+      BIP49_PURPOSE => Ok(AddressType::P2SHP2WPKH),
+      BIP84_PURPOSE => Ok(AddressType::P2WPKH),
+      BIP86_PURPOSE => Ok(AddressType::P2TR),
+      _ => Err(KPError::InvalidPurpose(purpose, BIP44_PURPOSE))
+    }
+  }
+
+  // BIP86 has no SLIP-132 version and uses plain xpub and tpub
+  pub fn xpub_version(&self, network: NetworkType) -> Version {
+    match (self, network.is_testnet()) {
+      (&AddressType::P2PKH, false) | (&AddressType::P2TR, false) => Version::Xpub,
+      (&AddressType::P2SHP2WPKH, false) => Version::Ypub,
+      (&AddressType::P2WPKH, false) => Version::Zpub,
+      (&AddressType::P2PKH, true) | (&AddressType::P2TR, true) => Version::Tpub,
+      (&AddressType::P2SHP2WPKH, true) => Version::Upub,
+      (&AddressType::P2WPKH, true) => Version::Vpub
+    }
+  }
+}
+
+fn base58check(version: u8, payload: &[u8]) -> String {
+  let mut data = Vec::with_capacity(1 + payload.len());
+  data.push(version);
+  data.extend_from_slice(payload);
+  base58::encode_check(&data)
+}
+
+// Witness version 0 uses bech32, later versions use bech32m (BIP350)
+fn segwit(hrp: &str, version: u8, program: &[u8]) -> Result<String, Error> {
+  let mut data =
+    vec![u5::try_from_u8(version).map_err(|err| Error::InvalidKeyData(Box::new(err)))?];
+  data.extend_from_slice(&program.to_base32());
+  let variant = if version == 0 { Variant::Bech32 } else { Variant::Bech32m };
+  bech32::encode(hrp, data, variant).map_err(|err| Error::InvalidKeyData(Box::new(err)))
+}
+
+pub fn address(
+  pub_key: &XPub, address_type: AddressType, network: NetworkType
+) -> Result<String, Error> {
+  let key_hash = hash160(&pub_key.serialize_compressed());
+  match address_type {
+    AddressType::P2PKH => Ok(base58check(network.p2pkh_prefix(), &key_hash)),
+    AddressType::P2SHP2WPKH => {
+      Ok(base58check(network.p2sh_prefix(), &hash160(&p2wpkh_script(&key_hash))))
+    }
+    AddressType::P2WPKH => segwit(network.bech32_hrp(), 0, &key_hash),
+    AddressType::P2TR => segwit(network.bech32_hrp(), 1, &pub_key.taproot_output_key()?)
+  }
+}
+
+pub(super) fn script_pubkey(pub_key: &XPub, address_type: AddressType) -> Result<Vec<u8>, Error> {
+  match address_type {
+    AddressType::P2TR => {
+      let mut script = vec![OP_1, TAPROOT_PUSH];
+      script.extend_from_slice(&pub_key.taproot_output_key()?);
+      Ok(script)
+    }
+    _ => Ok(key_hash_script(&hash160(&pub_key.serialize_compressed()), address_type))
+  }
+}
+
+// Output script of compressed or uncompressed public key hash. Taproot has no key hash.
+pub(super) fn key_hash_script(key_hash: &[u8], address_type: AddressType) -> Vec<u8> {
+  match address_type {
+    AddressType::P2PKH => p2pkh_script(key_hash),
+    AddressType::P2SHP2WPKH => p2sh_script(&hash160(&p2wpkh_script(key_hash))),
+    AddressType::P2WPKH | AddressType::P2TR => p2wpkh_script(key_hash)
+  }
+}
+
+pub(super) fn p2pkh_script(key_hash: &[u8]) -> Vec<u8> {
+  let mut script = vec![OP_DUP, OP_HASH160, HASH160_PUSH];
+  script.extend_from_slice(key_hash);
+  script.extend_from_slice(&[OP_EQUALVERIFY, OP_CHECKSIG]);
+  script
+}
+
+pub(super) fn p2wpkh_script(key_hash: &[u8]) -> Vec<u8> {
+  let mut script = Vec::from(&P2WPKH_SCRIPT_PREFIX[..]);
+  script.extend_from_slice(key_hash);
+  script
+}
+
+pub(super) fn p2sh_script(script_hash: &[u8]) -> Vec<u8> {
+  let mut script = vec![OP_HASH160, HASH160_PUSH];
+  script.extend_from_slice(script_hash);
+  script.push(OP_EQUAL);
+  script
+}
+
+// Output script of the address. Mainnet, testnet and regtest addresses are accepted.
+pub fn address_script_pubkey(address: &str) -> Result<Vec<u8>, Error> {
+  let networks = [NetworkType::Mainnet, NetworkType::Testnet, NetworkType::Regtest];
+  if let Ok((hrp, data, variant)) = bech32::decode(address) {
+    if !networks.iter().any(|network| network.bech32_hrp() == hrp) || data.is_empty() {
+      return Err(Error::InvalidData(Box::new(AddressError::UnknownNetwork)));
+    }
+    let version = data[0].to_u8();
+    let program =
+      Vec::<u8>::from_base32(&data[1..]).map_err(|err| Error::InvalidData(Box::new(err)))?;
+    let expected = if version == 0 { Variant::Bech32 } else { Variant::Bech32m };
+    if variant != expected || version > 16 || program.len() < 2 || program.len() > 40 {
+      return Err(Error::InvalidData(Box::new(AddressError::InvalidWitnessProgram)));
+    }
+    if version == 0 && program.len() != 20 && program.len() != 32 {
+      return Err(Error::InvalidData(Box::new(AddressError::InvalidWitnessProgram)));
+    }
+    let mut script = vec![if version == 0 { OP_0 } else { OP_1 + version - 1 }];
+    script.push(program.len() as u8);
+    script.extend_from_slice(&program);
+    return Ok(script);
+  }
+  let data = base58::decode_check(address)?;
+  if data.len() != 21 {
+    return Err(Error::InvalidData(Box::new(AddressError::InvalidLength(data.len()))));
+  }
+  if networks.iter().any(|network| network.p2pkh_prefix() == data[0]) {
+    Ok(p2pkh_script(&data[1..]))
+  } else if networks.iter().any(|network| network.p2sh_prefix() == data[0]) {
+    Ok(p2sh_script(&data[1..]))
+  } else {
+    Err(Error::InvalidData(Box::new(AddressError::UnknownNetwork)))
+  }
+}
+
+pub fn wif(key: &XPrv, network: NetworkType) -> String {
+  let mut payload = Vec::from(&key.secret()[..]);
+  payload.push(WIF_COMPRESSED_FLAG);
+  base58check(network.wif_prefix(), &payload)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::fixture::key;
+  use super::super::key::PublicKey;
+  use super::super::key_path::KeyPath;
+  use super::super::network_type::NetworkType;
+  use super::address_script_pubkey;
+  use bip39::util::hex;
+  use key::Key as IKey;
+  use key_path::KeyPath as IKeyPath;
+
+  #[test]
+  fn bip44_address() {
+    let path = KeyPath::bip44(false, 0, 0, 0).unwrap().derivation_path();
+    assert_eq!(key().address(&path).unwrap(), "1LqBGSKuX5yYUonjxT5qGfpUsXKYYWeabA");
+  }
+
+  #[test]
+  fn bip49_address() {
+    let path = KeyPath::bip49(false, 0, 0, 0).unwrap().derivation_path();
+    assert_eq!(key().address(&path).unwrap(), "37VucYSaXLCAsxYyAPfbSi9eh4iEcbShgf");
+  }
+
+  #[test]
+  fn bip84_address() {
+    let path = KeyPath::bip84(false, 0, 0, 0).unwrap().derivation_path();
+    assert_eq!(key().address(&path).unwrap(), "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu");
+  }
+
+  #[test]
+  fn bip86_address() {
+    let path = KeyPath::bip86(false, 0, 0, 0).unwrap().derivation_path();
+    assert_eq!(
+      key().address(&path).unwrap(),
+      "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr"
+    );
+  }
+
+  #[test]
+  fn script_pubkeys() {
+    let script = |address: &str| hex::encode(&address_script_pubkey(address).unwrap());
+    assert_eq!(
+      script("1LqBGSKuX5yYUonjxT5qGfpUsXKYYWeabA"),
+      "76a914d986ed01b7a22225a70edbf2ba7cfb63a15cb3aa88ac"
+    );
+    assert_eq!(
+      script("37VucYSaXLCAsxYyAPfbSi9eh4iEcbShgf"),
+      "a9143fb6e95812e57bb4691f9a4a628862a61a4f769b87"
+    );
+    assert_eq!(
+      script("bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"),
+      "0014c0cebcd6c3d3ca8c75dc5ec62ebe55330ef910e2"
+    );
+    assert_eq!(
+      script("bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr"),
+      "5120a60869f0dbcf1dc659c9cecbaf8050135ea9e8cdc487053f1dc6880949dc684c"
+    );
+    assert!(address_script_pubkey("ltc1qcr8te4kr609gcawutmrza0j4xv80jy8zrpf2qz").is_err());
+    assert!(address_script_pubkey("1LqBGSKuX5yYUonjxT5qGfpUsXKYYWeabB").is_err());
+  }
+
+  #[test]
+  fn testnet_addresses() {
+    let bip49 = KeyPath::bip49(true, 0, 0, 0).unwrap().derivation_path();
+    assert_eq!(key().address(&bip49).unwrap(), "2Mww8dCYPUpKHofjgcXcBCEGmniw9CoaiD2");
+    let bip84 = KeyPath::bip84(true, 0, 0, 0).unwrap().derivation_path();
+    assert_eq!(key().address(&bip84).unwrap(), "tb1q6rz28mcfaxtmd6v789l9rrlrusdprr9pqcpvkl");
+    let regtest = key().network_address(NetworkType::Regtest, &bip84).unwrap();
+    assert!(regtest.starts_with("bcrt1q6rz28mcfaxtmd6v789l9rrlrusdprr9p"));
+    assert!(key().network_address(NetworkType::Mainnet, &bip84).is_err());
+  }
+
+  #[test]
+  fn wif() {
+    let path = KeyPath::bip84(false, 0, 0, 0).unwrap().derivation_path();
+    assert_eq!(
+      key().wif(NetworkType::Mainnet, &path).unwrap(),
+      "KyZpNDKnfs94vbrwhJneDi77V6jF64PWPF8x5cdJb8ifgg2DUc9d"
+    );
+  }
+
+  #[test]
+  fn account_xpub() {
+    let bip44 = KeyPath::bip44(false, 0, 0, 0).unwrap().derivation_path();
+    assert_eq!(
+      key().account_xpub(&bip44).unwrap(),
+      "xpub6BosfCnifzxcFwrSzQiqu2DBVTshkCXacvNsWGYJVVhhawA7d4R5WSWGFNbi8Aw6ZRc1brxMyWMzG3DSSSSoekkudhUd9yLb6qx39T9nMdj"
+    );
+    let bip49 = KeyPath::bip49(false, 0, 1, 5).unwrap().derivation_path();
+    assert_eq!(
+      key().account_xpub(&bip49).unwrap(),
+      "ypub6Ww3ibxVfGzLrAH1PNcjyAWenMTbbAosGNB6VvmSEgytSER9azLDWCxoJwW7Ke7icmizBMXrzBx9979FfaHxHcrArf3zbeJJJUZPf663zsP"
+    );
+    let bip84 = KeyPath::bip84(false, 0, 0, 0).unwrap().derivation_path();
+    assert_eq!(
+      key().account_xpub(&bip84).unwrap(),
+      "zpub6rFR7y4Q2AijBEqTUquhVz398htDFrtymD9xYYfG1m4wAcvPhXNfE3EfH1r1ADqtfSdVCToUG868RvUUkgDKf31mGDtKsAYz2oz2AGutZYs"
+    );
+    let bip86 = KeyPath::bip86(false, 0, 0, 0).unwrap().derivation_path();
+    assert_eq!(
+      key().account_xpub(&bip86).unwrap(),
+      "xpub6BgBgsespWvERF3LHQu6CnqdvfEvtMcQjYrcRzx53QJjSxarj2afYWcLteoGVky7D3UKDP9QyrLprQ3VCECoY49yfdDEHGCtMMj92pReUsQ"
+    );
+    let testnet = KeyPath::bip84(true, 0, 0, 0).unwrap().derivation_path();
+    assert!(key().account_xpub(&testnet).unwrap().starts_with("vpub"));
+  }
+}
@@ -1,10 +1,20 @@
-use super::key_path::COIN_TYPE;
+use super::address::{address, wif, AddressType};
+use super::key_path::{BIP49_PURPOSE, BIP84_PURPOSE, BIP85_PURPOSE, BIP86_PURPOSE};
+use super::message::{sign_bip322, sign_compact, SignatureFormat};
+use super::network_type::NetworkType;
+use super::psbt::{sign_psbt, Psbt};
+use super::transaction::{sign_input, SigningInput, Transaction};
 use bip39;
+use derivation_path::DerivationPath;
 use key::{Error, Key as IKey};
-use key_path::{Error as KPError, KeyPath, BIP44_SOFT_UPPER_BOUND};
+use key_path::{Error as KPError, ACCOUNT_DEPTH, ADDRESS_DEPTH, BIP44_PURPOSE};
 use network::Network;
+use std::any::Any;
 
-use secp256k1_bip32::XPrv;
+use secp256k1_bip32::hash::sha256d;
+use secp256k1_bip32::{Version, XPrv, XPub};
+
+const ACCOUNT_PURPOSES: [u32; 4] = [BIP44_PURPOSE, BIP49_PURPOSE, BIP84_PURPOSE, BIP86_PURPOSE];
 
 pub struct Key {
   xprv: XPrv
@@ -21,28 +31,143 @@ impl Key {
     Ok(xprv.serialize())
   }
 
-  fn derive_private(&self, path: &dyn KeyPath) -> Result<XPrv, Error> {
-    if path.coin() != COIN_TYPE {
-      return Err(KPError::InvalidCoin(path.coin(), COIN_TYPE).into());
-    }
-    if path.account() < BIP44_SOFT_UPPER_BOUND {
-      return Err(KPError::InvalidAccount(path.account()).into());
-    }
-    if path.change() != 0 && path.change() != 1 {
-      return Err(KPError::InvalidChange(path.change()).into());
+  fn derive_account(&self, path: &DerivationPath) -> Result<XPrv, Error> {
+    check_account_path(path)?;
+    self.xprv.derive_path(&path.path()[..ACCOUNT_DEPTH]).map_err(|err| err.into())
+  }
+
+  fn derive_private(&self, path: &DerivationPath) -> Result<XPrv, Error> {
+    check_key_path(path)?;
+    self.xprv.derive_path(path.path()).map_err(|err| err.into())
+  }
+
+  pub fn wif(&self, network: NetworkType, path: &DerivationPath) -> Result<String, Error> {
+    check_network(network, path)?;
+    self.derive_private(path).map(|xprv| wif(&xprv, network))
+  }
+
+  pub fn sign_hash(&self, hash: &[u8], path: &DerivationPath) -> Result<Vec<u8>, Error> {
+    self.derive_private(path)?.sign_digest_der(hash).map_err(|err| Error::from_secp_sign_error(err))
+  }
+
+  // Signs inputs in order. Legacy sighash commits to the script sig of the signed input only,
+  // so previously signed inputs don't affect next ones.
+  pub fn sign_transaction(
+    &self, tx: &Transaction, inputs: &[SigningInput]
+  ) -> Result<Transaction, Error> {
+    let mut signed = tx.clone();
+    for input in inputs.iter() {
+      let xprv = self.derive_private(&input.path)?;
+      let address_type = AddressType::from_purpose(input.path.path()[0])?;
+      sign_input(&mut signed, &xprv, address_type, input.index, input.value, input.sighash_type)?;
     }
-    if path.address() >= BIP44_SOFT_UPPER_BOUND {
-      return Err(KPError::InvalidAddress(path.address()).into());
+    Ok(signed)
+  }
+
+  // Signs inputs with BIP32 derivations of this key
+  pub fn sign_psbt(&self, psbt: &Psbt) -> Result<Psbt, Error> {
+    sign_psbt(&self.xprv, psbt)
+  }
+
+  // BIP137 signature with header of the path address type
+  pub fn sign_message(&self, message: &[u8], path: &DerivationPath) -> Result<String, Error> {
+    let xprv = self.derive_private(path)?;
+    sign_compact(&xprv, AddressType::from_purpose(path.path()[0])?, message)
+  }
+
+  pub fn sign_message_bip322(
+    &self, message: &[u8], format: SignatureFormat, path: &DerivationPath
+  ) -> Result<String, Error> {
+    let xprv = self.derive_private(path)?;
+    sign_bip322(&xprv, AddressType::from_purpose(path.path()[0])?, message, format)
+  }
+}
+
+// Operations which need only public keys. Implemented by full and watch-only keys.
+// Address network is detected by coin type, so regtest addresses need `network_address`.
+pub(super) trait PublicKey {
+  fn derive_public(&self, path: &DerivationPath) -> Result<XPub, Error>;
+
+  fn network_address(&self, network: NetworkType, path: &DerivationPath) -> Result<String, Error> {
+    check_network(network, path)?;
+    let address_type = AddressType::from_purpose(path.path()[0])?;
+    address(&self.derive_public(path)?, address_type, network)
+  }
+
+  fn verify_hash(
+    &self, hash: &[u8], signature: &[u8], path: &DerivationPath
+  ) -> Result<bool, Error> {
+    self.derive_public(path)?.verify_digest_der(hash, signature).map_err(|err| err.into())
+  }
+}
+
+impl PublicKey for Key {
+  fn derive_public(&self, path: &DerivationPath) -> Result<XPub, Error> {
+    self.derive_private(path).map(|pk| pk.public())
+  }
+}
+
+// Purpose, coin and account part of BIP44, BIP49, BIP84 and BIP86 paths
+pub(super) fn check_account_path(path: &DerivationPath) -> Result<(), Error> {
+  if path.depth() < ACCOUNT_DEPTH {
+    return Err(KPError::InvalidDepth(path.depth()).into());
+  }
+  let indexes = path.path();
+  if !ACCOUNT_PURPOSES.contains(&indexes[0]) {
+    return Err(KPError::InvalidPurpose(indexes[0], BIP44_PURPOSE).into());
+  }
+  NetworkType::from_coin(indexes[1])?;
+  if !DerivationPath::is_hardened(indexes[2]) {
+    return Err(KPError::InvalidAccount(indexes[2]).into());
+  }
+  Ok(())
+}
+
+pub(super) fn check_address_path(path: &DerivationPath) -> Result<(), Error> {
+  check_account_path(path)?;
+  if path.depth() != ADDRESS_DEPTH {
+    return Err(KPError::InvalidDepth(path.depth()).into());
+  }
+  let indexes = path.path();
+  if indexes[3] != 0 && indexes[3] != 1 {
+    return Err(KPError::InvalidChange(indexes[3]).into());
+  }
+  if DerivationPath::is_hardened(indexes[4]) {
+    return Err(KPError::InvalidAddress(indexes[4]).into());
+  }
+  Ok(())
+}
+
+// Address paths or BIP85 paths, which are hardened at every level
+fn check_key_path(path: &DerivationPath) -> Result<(), Error> {
+  match path.path().first() {
+    Some(&BIP85_PURPOSE) => {
+      if path.depth() < 2 {
+        return Err(KPError::InvalidDepth(path.depth()).into());
+      }
+      match path.path().iter().position(|&index| !DerivationPath::is_hardened(index)) {
+        Some(position) => Err(KPError::InvalidIndex(position + 1, path.path()[position]).into()),
+        None => Ok(())
+      }
     }
-    self
-      .xprv
-      .derive(path.purpose())
-      .and_then(|pk| pk.derive(path.coin()))
-      .and_then(|pk| pk.derive(path.account()))
-      .and_then(|pk| pk.derive(path.change()))
-      .and_then(|pk| pk.derive(path.address()))
-      .map_err(|err| err.into())
+    _ => check_address_path(path)
+  }
+}
+
+// SLIP-132 version by path purpose. BIP86 accounts use plain xpub and tpub.
+pub(super) fn account_version(path: &DerivationPath) -> Result<Version, Error> {
+  check_account_path(path)?;
+  let network = NetworkType::from_coin(path.path()[1])?;
+  Ok(AddressType::from_purpose(path.path()[0])?.xpub_version(network))
+}
+
+fn check_network(network: NetworkType, path: &DerivationPath) -> Result<(), Error> {
+  check_address_path(path)?;
+  let coin = path.path()[1];
+  if coin != network.coin() {
+    return Err(KPError::InvalidCoin(coin, network.coin()).into());
   }
+  Ok(())
 }
 
 impl IKey for Key {
@@ -50,15 +175,30 @@ impl IKey for Key {
     Network::BITCOIN
   }
 
-  fn pub_key(&self, path: &dyn KeyPath) -> Result<Vec<u8>, Error> {
-    self.derive_private(path).map(|pk| pk.public().serialize())
+  fn pub_key(&self, path: &DerivationPath) -> Result<Vec<u8>, Error> {
+    self.derive_public(path).map(|pk| pk.serialize())
+  }
+
+  fn address(&self, path: &DerivationPath) -> Result<String, Error> {
+    check_address_path(path)?;
+    self.network_address(NetworkType::from_coin(path.path()[1])?, path)
+  }
+
+  // SLIP-132 encoded: xpub, ypub, zpub for mainnet and tpub, upub, vpub for testnet
+  fn account_xpub(&self, path: &DerivationPath) -> Result<String, Error> {
+    let version = account_version(path)?;
+    self.derive_account(path).map(|pk| pk.public().to_base58(version))
+  }
+
+  fn sign(&self, data: &[u8], path: &DerivationPath) -> Result<Vec<u8>, Error> {
+    self.sign_hash(&sha256d(data), path)
   }
 
-  fn sign(&self, data: &[u8], path: &dyn KeyPath) -> Result<Vec<u8>, Error> {
-    self.derive_private(path)?.sign(data).map_err(|err| Error::from_secp_sign_error(err))
+  fn verify(&self, data: &[u8], signature: &[u8], path: &DerivationPath) -> Result<bool, Error> {
+    self.verify_hash(&sha256d(data), signature, path)
   }
 
-  fn verify(&self, data: &[u8], signature: &[u8], path: &dyn KeyPath) -> Result<bool, Error> {
-    self.derive_private(path)?.public().verify(data, signature).map_err(|err| err.into())
+  fn as_any(&self) -> Option<&dyn Any> {
+    Some(self)
   }
 }
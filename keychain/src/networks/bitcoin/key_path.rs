@@ -12,6 +12,12 @@ pub const BIP49_PURPOSE: u32 = 0x80000031;
 /// BIP84 purpose
 pub const BIP84_PURPOSE: u32 = 0x80000054;
 
+/// BIP86 purpose
+pub const BIP86_PURPOSE: u32 = 0x80000056;
+
+/// BIP85 deterministic entropy purpose
+pub const BIP85_PURPOSE: u32 = 0x84fd1d48;
+
 #[derive(Debug, Copy, Clone)]
 pub struct KeyPath {
   purpose: u32,
@@ -77,6 +83,17 @@ impl KeyPath {
       address
     })
   }
+
+  pub fn bip86(testnet: bool, account: u32, change: u32, address: u32) -> Result<Self, Error> {
+    Self::is_valid(account, change, address)?;
+    Ok(KeyPath {
+      purpose: BIP86_PURPOSE,
+      coin: Self::coin(testnet),
+      account: account + BIP44_SOFT_UPPER_BOUND,
+      change,
+      address
+    })
+  }
 }
 
 impl IKeyPath for KeyPath {
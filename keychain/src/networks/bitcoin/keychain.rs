@@ -0,0 +1,108 @@
+use super::key::{Key, PublicKey};
+use super::message::SignatureFormat;
+use super::network_type::NetworkType;
+use super::psbt::Psbt;
+use super::transaction::{SigningInput, Transaction};
+use super::watch_only::WatchOnlyKey;
+use derivation_path::DerivationPath;
+use error::Error;
+use key::Error as KeyError;
+use keychain::Keychain;
+use network::Network;
+
+impl Keychain {
+  // Address type is selected by path purpose: BIP44 - P2PKH, BIP49 - P2SH-P2WPKH, BIP84 - P2WPKH,
+  // BIP86 - P2TR
+  pub fn bitcoin_address(&self, path: &DerivationPath) -> Result<String, Error> {
+    self.address(&Network::BITCOIN, path)
+  }
+
+  // Regtest addresses can't be inferred from the key path, as regtest shares testnet coin type
+  pub fn bitcoin_network_address(
+    &self, network: NetworkType, path: &DerivationPath
+  ) -> Result<String, Error> {
+    self
+      .bitcoin_public_key()?
+      .network_address(network, path)
+      .map_err(|err| Error::from_key_error(&Network::BITCOIN, err))
+  }
+
+  pub fn bitcoin_wif(&self, network: NetworkType, path: &DerivationPath) -> Result<String, Error> {
+    self
+      .bitcoin_private_key()?
+      .wif(network, path)
+      .map_err(|err| Error::from_key_error(&Network::BITCOIN, err))
+  }
+
+  // Signs precomputed sighash. Returns DER encoded signature without sighash type byte.
+  pub fn bitcoin_sign_hash(&self, hash: &[u8], path: &DerivationPath) -> Result<Vec<u8>, Error> {
+    self
+      .bitcoin_private_key()?
+      .sign_hash(hash, path)
+      .map_err(|err| Error::from_key_error(&Network::BITCOIN, err))
+  }
+
+  // Returns the transaction with script sigs and witnesses of signed inputs
+  pub fn bitcoin_sign_transaction(
+    &self, tx: &Transaction, inputs: &[SigningInput]
+  ) -> Result<Transaction, Error> {
+    self
+      .bitcoin_private_key()?
+      .sign_transaction(tx, inputs)
+      .map_err(|err| Error::from_key_error(&Network::BITCOIN, err))
+  }
+
+  // PSBT signer. Inputs are matched by master key fingerprint of their BIP32 derivations.
+  pub fn bitcoin_sign_psbt(&self, psbt: &Psbt) -> Result<Psbt, Error> {
+    self
+      .bitcoin_private_key()?
+      .sign_psbt(psbt)
+      .map_err(|err| Error::from_key_error(&Network::BITCOIN, err))
+  }
+
+  // Base64 "Bitcoin Signed Message" signature (BIP137). Taproot addresses need BIP322.
+  pub fn bitcoin_sign_message(
+    &self, message: &[u8], path: &DerivationPath
+  ) -> Result<String, Error> {
+    self
+      .bitcoin_private_key()?
+      .sign_message(message, path)
+      .map_err(|err| Error::from_key_error(&Network::BITCOIN, err))
+  }
+
+  // Base64 BIP322 signature for segwit and taproot addresses
+  pub fn bitcoin_sign_message_bip322(
+    &self, message: &[u8], format: SignatureFormat, path: &DerivationPath
+  ) -> Result<String, Error> {
+    self
+      .bitcoin_private_key()?
+      .sign_message_bip322(message, format, path)
+      .map_err(|err| Error::from_key_error(&Network::BITCOIN, err))
+  }
+
+  pub fn bitcoin_verify_hash(
+    &self, hash: &[u8], signature: &[u8], path: &DerivationPath
+  ) -> Result<bool, Error> {
+    self
+      .bitcoin_public_key()?
+      .verify_hash(hash, signature, path)
+      .map_err(|err| Error::from_key_error(&Network::BITCOIN, err))
+  }
+}
+
+impl Keychain {
+  fn bitcoin_public_key(&self) -> Result<&dyn PublicKey, Error> {
+    self.network_key::<Key>(&Network::BITCOIN).map(|key| key as &dyn PublicKey).or_else(|_| {
+      self.network_key::<WatchOnlyKey>(&Network::BITCOIN).map(|key| key as &dyn PublicKey)
+    })
+  }
+
+  fn bitcoin_private_key(&self) -> Result<&Key, Error> {
+    self.network_key::<Key>(&Network::BITCOIN).map_err(|err| {
+      match self.network_key::<WatchOnlyKey>(&Network::BITCOIN) {
+        Ok(_) => Error::from_key_error(&Network::BITCOIN, KeyError::PrivateKeyIsNotAvailable),
+        Err(_) => err
+      }
+    })
+  }
+}
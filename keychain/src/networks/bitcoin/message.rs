@@ -0,0 +1,369 @@
+// Signed messages for proof of address ownership. Legacy "Bitcoin Signed Message" compact
+// signatures (BIP137) and generic BIP322 signatures, which are witnesses of virtual transaction.
+
+use super::address::{
+  address_script_pubkey, key_hash_script, p2pkh_script, p2wpkh_script, script_pubkey, AddressType,
+  OP_0, OP_1, OP_RETURN
+};
+use super::transaction::{
+  sign_input, write_bytes, write_witness, OutPoint, Reader, SighashType, Transaction, TxIn, TxOut
+};
+use base64;
+use entropy::{Entropy, OsEntropy};
+use key::Error;
+use secp256k1_bip32::hash::{hash160, sha256d, tagged_hash, HASH_SIZE};
+use secp256k1_bip32::{
+  compressed_public_key, recover, verify_der, verify_schnorr, XPrv, SCHNORR_SIGNATURE_SIZE
+};
+use std::fmt;
+
+const MESSAGE_MAGIC: &[u8] = b"Bitcoin Signed Message:\n";
+const BIP322_TAG: &str = "BIP0322-signed-message";
+
+// BIP137 header is 27 + recovery id + address type offset
+const HEADER_BASE: u8 = 27;
+const COMPACT_SIGNATURE_SIZE: usize = 65;
+const UNCOMPRESSED_P2PKH: u8 = 0;
+const COMPRESSED_P2PKH: u8 = 4;
+const P2SH_P2WPKH: u8 = 8;
+const P2WPKH: u8 = 12;
+const MAX_HEADER: u8 = HEADER_BASE + P2WPKH + 3;
+
+const COMPRESSED_KEY_SIZE: usize = 33;
+// Push of P2WPKH redeem script
+const P2SH_P2WPKH_SCRIPT_SIG_SIZE: usize = 23;
+// Taproot signature followed by sighash type byte
+const TAPROOT_SIGNATURE_WITH_TYPE_SIZE: usize = 65;
+
+// BIP322 signature encoding. Simple is the witness stack only, full is the whole transaction,
+// which is required if signature has script sig, as P2SH-P2WPKH does.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SignatureFormat {
+  Simple,
+  Full
+}
+
+#[derive(Debug)]
+pub enum MessageError {
+  UnsupportedScript,
+  ScriptSigIsNotEmpty
+}
+
+impl fmt::Display for MessageError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      &MessageError::UnsupportedScript => write!(f, "Unsupported address script"),
+      &MessageError::ScriptSigIsNotEmpty => {
+        write!(f, "Signature has script sig and needs full format")
+      }
+    }
+  }
+}
+
+impl std::error::Error for MessageError {}
+
+impl From<MessageError> for Error {
+  fn from(err: MessageError) -> Self {
+    Error::InvalidData(Box::new(err))
+  }
+}
+
+// Double SHA256 of magic prefixed message
+pub fn message_hash(message: &[u8]) -> [u8; HASH_SIZE] {
+  let mut data = Vec::with_capacity(MESSAGE_MAGIC.len() + message.len() + 10);
+  write_bytes(MESSAGE_MAGIC, &mut data);
+  write_bytes(message, &mut data);
+  sha256d(&data)
+}
+
+// Base64 of header byte and recoverable signature
+pub(super) fn sign_compact(
+  key: &XPrv, address_type: AddressType, message: &[u8]
+) -> Result<String, Error> {
+  let offset = match address_type {
+    AddressType::P2PKH => COMPRESSED_P2PKH,
+    AddressType::P2SHP2WPKH => P2SH_P2WPKH,
+    AddressType::P2WPKH => P2WPKH,
+    AddressType::P2TR => return Err(Error::OperationIsNotSupported("BIP137 taproot signature"))
+  };
+  let signature = key.sign_digest(&message_hash(message)).map_err(Error::from_secp_sign_error)?;
+  let mut compact = vec![HEADER_BASE + offset + signature[COMPACT_SIGNATURE_SIZE - 1]];
+  compact.extend_from_slice(&signature[..COMPACT_SIGNATURE_SIZE - 1]);
+  Ok(base64::encode(&compact))
+}
+
+pub(super) fn sign_bip322(
+  key: &XPrv, address_type: AddressType, message: &[u8], format: SignatureFormat
+) -> Result<String, Error> {
+  let pub_key = key.public();
+  let to_spend = to_spend(&script_pubkey(&pub_key, address_type)?, message);
+  let mut to_sign = to_sign(&to_spend, Vec::new(), Vec::new());
+  match address_type {
+    AddressType::P2WPKH | AddressType::P2SHP2WPKH => {
+      sign_input(&mut to_sign, key, address_type, 0, 0, SighashType::ALL)?
+    }
+    AddressType::P2TR => {
+      let sighash = to_sign.taproot_sighash(0, &to_spend.outputs, SighashType::DEFAULT)?;
+      let mut aux = [0u8; HASH_SIZE];
+      OsEntropy::new().fill_bytes(&mut aux);
+      let signature = key.sign_taproot(&sighash, &aux).map_err(Error::from_secp_sign_error)?;
+      to_sign.inputs[0].witness = vec![signature];
+    }
+    // BIP322 uses BIP137 signatures for legacy addresses
+    AddressType::P2PKH => return Err(Error::OperationIsNotSupported("BIP322 legacy signature"))
+  }
+  match format {
+    SignatureFormat::Full => Ok(base64::encode(&to_sign.serialize())),
+    SignatureFormat::Simple if to_sign.inputs[0].script_sig.is_empty() => {
+      let mut witness = Vec::new();
+      write_witness(&to_sign.inputs[0].witness, &mut witness);
+      Ok(base64::encode(&witness))
+    }
+    SignatureFormat::Simple => Err(MessageError::ScriptSigIsNotEmpty.into())
+  }
+}
+
+// Verifies BIP137 or BIP322 simple and full signatures. BIP322 signatures are supported for
+// P2WPKH, P2SH-P2WPKH and taproot key path addresses.
+pub fn verify_message(address: &str, message: &[u8], signature: &str) -> Result<bool, Error> {
+  let script = address_script_pubkey(address)?;
+  let signature =
+    base64::decode(signature).map_err(|err| Error::InvalidSignature(Box::new(err)))?;
+  let is_compact = signature.len() == COMPACT_SIGNATURE_SIZE
+    && signature[0] >= HEADER_BASE
+    && signature[0] <= MAX_HEADER;
+  if is_compact {
+    verify_compact(&script, message, &signature)
+  } else {
+    verify_bip322(&script, message, &signature)
+  }
+}
+
+// Segwit address signatures are accepted with any compressed key header, as wallets disagree on
+// header of segwit signatures.
+fn verify_compact(script: &[u8], message: &[u8], signature: &[u8]) -> Result<bool, Error> {
+  let flags = signature[0] - HEADER_BASE;
+  let mut recoverable = Vec::from(&signature[1..]);
+  recoverable.push(flags & 0b11);
+  let pub_key = recover(&message_hash(message), &recoverable)?;
+  if flags & !0b11 == UNCOMPRESSED_P2PKH {
+    return Ok(script == &p2pkh_script(&hash160(&pub_key))[..]);
+  }
+  let key_hash = hash160(&compressed_public_key(&pub_key)?);
+  Ok(
+    [AddressType::P2PKH, AddressType::P2SHP2WPKH, AddressType::P2WPKH]
+      .iter()
+      .any(|&address_type| script == &key_hash_script(&key_hash, address_type)[..])
+  )
+}
+
+fn verify_bip322(script: &[u8], message: &[u8], signature: &[u8]) -> Result<bool, Error> {
+  let to_spend = to_spend(script, message);
+  // Full signature is a transaction with version 0
+  let to_sign = if signature.starts_with(&[0, 0, 0, 0]) {
+    let tx = Transaction::parse(signature)?;
+    let expected = to_sign(&to_spend, Vec::new(), Vec::new());
+    if tx.inputs.len() != 1
+      || tx.inputs[0].previous_output != expected.inputs[0].previous_output
+      || tx.outputs != expected.outputs
+    {
+      return Ok(false);
+    }
+    tx
+  } else {
+    let mut reader = Reader::new(signature);
+    let witness = reader.witness()?;
+    reader.finish()?;
+    to_sign(&to_spend, Vec::new(), witness)
+  };
+
+  let input = &to_sign.inputs[0];
+  match (script.len(), script[0]) {
+    // P2WPKH
+    (22, OP_0) => {
+      if !input.script_sig.is_empty() {
+        return Ok(false);
+      }
+      verify_p2wpkh(&to_sign, &script[2..])
+    }
+    // P2SH script sig should push P2WPKH redeem script
+    (23, _) => {
+      let script_sig = &input.script_sig;
+      if script_sig.len() != P2SH_P2WPKH_SCRIPT_SIG_SIZE || script_sig[0] != 22 {
+        return Ok(false);
+      }
+      let key_hash = &script_sig[3..];
+      if script_sig[1..] != p2wpkh_script(key_hash)[..]
+        || script != &key_hash_script(key_hash, AddressType::P2SHP2WPKH)[..]
+      {
+        return Ok(false);
+      }
+      verify_p2wpkh(&to_sign, key_hash)
+    }
+    // Taproot key path
+    (34, OP_1) => {
+      if !input.script_sig.is_empty() || input.witness.len() != 1 {
+        return Ok(false);
+      }
+      // Default sighash signature or one with explicit SIGHASH_ALL byte
+      let witness = &input.witness[0];
+      let sighash_type = match witness.len() {
+        SCHNORR_SIGNATURE_SIZE => SighashType::DEFAULT,
+        TAPROOT_SIGNATURE_WITH_TYPE_SIZE if witness[64] == SighashType::ALL.0 => SighashType::ALL,
+        _ => return Ok(false)
+      };
+      let sighash = to_sign.taproot_sighash(0, &to_spend.outputs, sighash_type)?;
+      verify_schnorr(&sighash, &script[2..], &witness[..SCHNORR_SIGNATURE_SIZE])
+        .map_err(|err| err.into())
+    }
+    _ => Err(MessageError::UnsupportedScript.into())
+  }
+}
+
+fn verify_p2wpkh(to_sign: &Transaction, key_hash: &[u8]) -> Result<bool, Error> {
+  let witness = &to_sign.inputs[0].witness;
+  if witness.len() != 2 || witness[1].len() != COMPRESSED_KEY_SIZE {
+    return Ok(false);
+  }
+  if hash160(&witness[1])[..] != key_hash[..] || witness[0].last() != Some(&SighashType::ALL.0) {
+    return Ok(false);
+  }
+  let sighash = to_sign.segwit_v0_sighash(0, &p2pkh_script(key_hash), 0, SighashType::ALL)?;
+  let signature = &witness[0][..witness[0].len() - 1];
+  verify_der(&sighash, &witness[1], signature).map_err(|err| err.into())
+}
+
+// Virtual transaction, which spends nothing and pays to the address
+fn to_spend(script_pubkey: &[u8], message: &[u8]) -> Transaction {
+  let mut script_sig = vec![OP_0];
+  write_bytes(&tagged_hash(BIP322_TAG, message), &mut script_sig);
+  Transaction {
+    version: 0,
+    inputs: vec![TxIn {
+      previous_output: OutPoint { txid: [0; HASH_SIZE], vout: 0xffff_ffff },
+      script_sig,
+      sequence: 0,
+      witness: Vec::new()
+    }],
+    outputs: vec![TxOut { value: 0, script_pubkey: Vec::from(script_pubkey) }],
+    lock_time: 0
+  }
+}
+
+// Virtual transaction, which spends to_spend output. Its witness is the signature.
+fn to_sign(to_spend: &Transaction, script_sig: Vec<u8>, witness: Vec<Vec<u8>>) -> Transaction {
+  Transaction {
+    version: 0,
+    inputs: vec![TxIn {
+      previous_output: OutPoint { txid: to_spend.txid(), vout: 0 },
+      script_sig,
+      sequence: 0,
+      witness
+    }],
+    outputs: vec![TxOut { value: 0, script_pubkey: vec![OP_RETURN] }],
+    lock_time: 0
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::address::address_script_pubkey;
+  use super::super::fixture::key;
+  use super::super::key_path::KeyPath;
+  use super::super::key_path::BIP49_PURPOSE;
+  use super::{to_sign, to_spend, verify_message, SignatureFormat, BIP322_TAG};
+  use bip39::util::hex;
+  use key::Key as IKey;
+  use key_path::KeyPath as IKeyPath;
+  use secp256k1_bip32::hash::tagged_hash;
+
+  // BIP322 test vectors
+  const ADDRESS: &str = "bc1q9vza2e8x573nczrlzms0wvx3gsqjx7vavgkx0l";
+  const EMPTY_SIGNATURE: &str = "AkcwRAIgM2gBAQqvZX15ZiysmKmQpDrG83avLIT492QBzLnQIxYCIBaTpOaD20qRl\
+                                 EylyxFSeEA2ba9YOixpX8z46TSDtS40ASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIV\
+                                 JqO4XCsMvViHI=";
+  const HELLO_SIGNATURE: &str =
+    "AkcwRAIgZRfIY3p7/DoVTty6YZbWS71bc5Vct9p9Fia83eRmw2QCICK/ENGfwLtptF\
+                                 luMGs2KsqoNSk89pO7F29zJLUx9a/sASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIVJ\
+                                 qO4XCsMvViHI=";
+
+  // BIP322 P2TR vector, signature has explicit SIGHASH_ALL byte
+  const TAPROOT_ADDRESS: &str = "bc1ppv609nr0vr25u07u95waq5lucwfm6tde4nydujnu8npg4q75mr5sxq8lt3";
+  const TAPROOT_SIGNATURE: &str =
+    "AUHd69PrJQEv+oKTfZ8l+WROBHuy9HKrbFCJu7U1iK2iiEy1vMU5EfMtjc+VSHM7aU0SDbak5IUZRVno2P5mjSafAQ==";
+
+  fn reversed(hash: [u8; 32]) -> String {
+    hex::encode(&hash.iter().rev().cloned().collect::<Vec<u8>>())
+  }
+
+  #[test]
+  fn bip322_vectors() {
+    assert_eq!(
+      hex::encode(&tagged_hash(BIP322_TAG, b"")),
+      "c90c269c4f8fcbe6880f72a721ddfbf1914268a794cbb21cfafee13770ae19f1"
+    );
+    let to_spend = to_spend(&address_script_pubkey(ADDRESS).unwrap(), b"");
+    assert_eq!(
+      reversed(to_spend.txid()),
+      "c5680aa69bb8d860bf82d4e9cd3504b55dde018de765a91bb566283c545a99a7"
+    );
+    assert_eq!(
+      reversed(to_sign(&to_spend, Vec::new(), Vec::new()).txid()),
+      "1e9654e951a5ba44c8604c4de6c67fd78a27e81dcadcfe1edf638ba3aaebaed6"
+    );
+
+    assert!(verify_message(ADDRESS, b"", EMPTY_SIGNATURE).unwrap());
+    assert!(verify_message(ADDRESS, b"Hello World", HELLO_SIGNATURE).unwrap());
+    assert!(!verify_message(ADDRESS, b"Hello World", EMPTY_SIGNATURE).unwrap());
+
+    assert!(verify_message(TAPROOT_ADDRESS, b"Hello World", TAPROOT_SIGNATURE).unwrap());
+    assert!(!verify_message(TAPROOT_ADDRESS, b"Hello", TAPROOT_SIGNATURE).unwrap());
+  }
+
+  #[test]
+  fn compact_signature() {
+    let key = key();
+    let paths = [
+      KeyPath::bip44(false, 0, 0, 0).unwrap(),
+      KeyPath::bip49(false, 0, 0, 0).unwrap(),
+      KeyPath::bip84(false, 0, 0, 0).unwrap()
+    ];
+    for path in paths.iter() {
+      let path = path.derivation_path();
+      let address = key.address(&path).unwrap();
+      let signature = key.sign_message(b"Hello World", &path).unwrap();
+      assert!(verify_message(&address, b"Hello World", &signature).unwrap());
+      assert!(!verify_message(&address, b"Hello", &signature).unwrap());
+    }
+    let taproot = KeyPath::bip86(false, 0, 0, 0).unwrap().derivation_path();
+    assert!(key.sign_message(b"Hello World", &taproot).is_err());
+  }
+
+  #[test]
+  fn bip322_signature() {
+    let key = key();
+    let paths = [
+      KeyPath::bip49(false, 0, 0, 0).unwrap(),
+      KeyPath::bip84(false, 0, 0, 0).unwrap(),
+      KeyPath::bip86(false, 0, 0, 0).unwrap()
+    ];
+    for path in paths.iter() {
+      let path = path.derivation_path();
+      let address = key.address(&path).unwrap();
+      for &format in [SignatureFormat::Simple, SignatureFormat::Full].iter() {
+        let signature = match key.sign_message_bip322(b"Hello World", format, &path) {
+          Ok(signature) => signature,
+          // P2SH-P2WPKH signature has script sig
+          Err(_) => {
+            assert_eq!((path.path()[0], format), (BIP49_PURPOSE, SignatureFormat::Simple));
+            continue;
+          }
+        };
+        assert!(verify_message(&address, b"Hello World", &signature).unwrap());
+        assert!(!verify_message(&address, b"Hello", &signature).unwrap());
+      }
+    }
+    let legacy = KeyPath::bip44(false, 0, 0, 0).unwrap().derivation_path();
+    assert!(key.sign_message_bip322(b"Hello World", SignatureFormat::Full, &legacy).is_err());
+  }
+}
@@ -1,6 +1,13 @@
+mod address;
 mod factory;
 mod key;
 mod key_path;
+mod keychain;
+mod message;
+mod network_type;
+mod psbt;
+mod transaction;
+mod watch_only;
 
 use network::Network;
 
@@ -8,5 +15,29 @@ impl Network {
   pub const BITCOIN: Network = Network(key_path::COIN_TYPE);
 }
 
+pub use self::address::address_script_pubkey;
 pub use self::factory::KeyFactory;
 pub use self::key_path::KeyPath;
+pub use self::message::{message_hash, verify_message, SignatureFormat};
+pub use self::network_type::NetworkType;
+pub use self::psbt::{Psbt, PsbtError};
+pub use self::transaction::{
+  OutPoint, SighashType, SigningInput, Transaction, TransactionError, TxIn, TxOut
+};
+
+#[cfg(test)]
+mod fixture {
+  use super::key::Key;
+  use bip39::Seed;
+  use mnemonic::{seed_from_mnemonic, Language};
+
+  const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon \
+                          abandon abandon about";
+
+  // Master key of the BIP84 and BIP86 test vectors mnemonic
+  pub fn key() -> Key {
+    let seed = seed_from_mnemonic(MNEMONIC, "", 128, Language::English).unwrap();
+    let data = Key::data_from_seed(&Seed::from_slice(&seed).unwrap()).unwrap();
+    Key::from_data(&data).unwrap()
+  }
+}
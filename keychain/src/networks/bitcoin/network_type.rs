@@ -0,0 +1,64 @@
+use super::key_path::{COIN_TYPE, COIN_TYPE_TESTNET};
+use key_path::Error;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum NetworkType {
+  Mainnet,
+  Testnet,
+  Regtest
+}
+
+impl NetworkType {
+  // Regtest shares testnet coin type, so it can't be detected from the key path
+  pub fn from_coin(coin: u32) -> Result<Self, Error> {
+    match coin {
+      COIN_TYPE => Ok(NetworkType::Mainnet),
+      COIN_TYPE_TESTNET => Ok(NetworkType::Testnet),
+      _ => Err(Error::InvalidCoin(coin, COIN_TYPE))
+    }
+  }
+
+  pub fn is_testnet(&self) -> bool {
+    *self != NetworkType::Mainnet
+  }
+
+  pub fn coin(&self) -> u32 {
+    if self.is_testnet() {
+      COIN_TYPE_TESTNET
+    } else {
+      COIN_TYPE
+    }
+  }
+
+  pub fn p2pkh_prefix(&self) -> u8 {
+    if self.is_testnet() {
+      0x6f
+    } else {
+      0x00
+    }
+  }
+
+  pub fn p2sh_prefix(&self) -> u8 {
+    if self.is_testnet() {
+      0xc4
+    } else {
+      0x05
+    }
+  }
+
+  pub fn wif_prefix(&self) -> u8 {
+    if self.is_testnet() {
+      0xef
+    } else {
+      0x80
+    }
+  }
+
+  pub fn bech32_hrp(&self) -> &'static str {
+    match self {
+      &NetworkType::Mainnet => "bc",
+      &NetworkType::Testnet => "tb",
+      &NetworkType::Regtest => "bcrt"
+    }
+  }
+}
@@ -0,0 +1,858 @@
+// Partially signed transactions (BIP174 version 0 and BIP370 version 2). Only the signer role is
+// implemented: inputs with BIP32 derivations of the master key get partial signatures, all other
+// data is kept as is.
+
+use super::address::{p2pkh_script, p2sh_script, OP_0, OP_1};
+use super::transaction::{
+  write_bytes, write_compact_size, OutPoint, Reader, SighashType, Transaction, TxIn, TxOut
+};
+use base64;
+use entropy::{Entropy, OsEntropy};
+use key::Error;
+use secp256k1_bip32::hash::{hash160, sha256, HASH_SIZE};
+use secp256k1_bip32::{XPrv, X_ONLY_KEY_SIZE};
+use std::fmt;
+
+const MAGIC: &[u8] = b"psbt\xff";
+
+// Global key types
+const GLOBAL_UNSIGNED_TX: u8 = 0x00;
+const GLOBAL_TX_VERSION: u8 = 0x02;
+const GLOBAL_FALLBACK_LOCKTIME: u8 = 0x03;
+const GLOBAL_INPUT_COUNT: u8 = 0x04;
+const GLOBAL_OUTPUT_COUNT: u8 = 0x05;
+const GLOBAL_VERSION: u8 = 0xfb;
+
+// Input key types
+const IN_NON_WITNESS_UTXO: u8 = 0x00;
+const IN_WITNESS_UTXO: u8 = 0x01;
+const IN_PARTIAL_SIG: u8 = 0x02;
+const IN_SIGHASH_TYPE: u8 = 0x03;
+const IN_REDEEM_SCRIPT: u8 = 0x04;
+const IN_WITNESS_SCRIPT: u8 = 0x05;
+const IN_BIP32_DERIVATION: u8 = 0x06;
+const IN_FINAL_SCRIPTSIG: u8 = 0x07;
+const IN_FINAL_SCRIPTWITNESS: u8 = 0x08;
+const IN_PREVIOUS_TXID: u8 = 0x0e;
+const IN_OUTPUT_INDEX: u8 = 0x0f;
+const IN_SEQUENCE: u8 = 0x10;
+const IN_REQUIRED_TIME_LOCKTIME: u8 = 0x11;
+const IN_REQUIRED_HEIGHT_LOCKTIME: u8 = 0x12;
+const IN_TAP_KEY_SIG: u8 = 0x13;
+const IN_TAP_BIP32_DERIVATION: u8 = 0x16;
+const IN_TAP_MERKLE_ROOT: u8 = 0x18;
+
+// Output key types
+const OUT_AMOUNT: u8 = 0x03;
+const OUT_SCRIPT: u8 = 0x04;
+
+const FINGERPRINT_SIZE: usize = 4;
+const COMPRESSED_KEY_SIZE: usize = 33;
+
+#[derive(Debug)]
+pub enum PsbtError {
+  InvalidMagic,
+  UnsupportedVersion(u32),
+  DuplicateKey(Vec<u8>),
+  MissingField(&'static str),
+  InvalidField(&'static str),
+  PrevoutMismatch(usize),
+  ConflictingLockTimes
+}
+
+impl fmt::Display for PsbtError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      &PsbtError::InvalidMagic => write!(f, "Invalid PSBT magic bytes"),
+      &PsbtError::UnsupportedVersion(version) => write!(f, "Unsupported PSBT version {}", version),
+      &PsbtError::DuplicateKey(ref key) => write!(f, "Duplicate PSBT key {:?}", key),
+      &PsbtError::MissingField(field) => write!(f, "PSBT field {} is missing", field),
+      &PsbtError::InvalidField(field) => write!(f, "Invalid PSBT field {}", field),
+      &PsbtError::PrevoutMismatch(input) => {
+        write!(f, "Previous transaction doesn't match input {}", input)
+      }
+      &PsbtError::ConflictingLockTimes => write!(f, "Inputs require conflicting lock times")
+    }
+  }
+}
+
+impl std::error::Error for PsbtError {}
+
+impl From<PsbtError> for Error {
+  fn from(err: PsbtError) -> Self {
+    Error::InvalidData(Box::new(err))
+  }
+}
+
+// Key-value pairs in serialization order. Key includes key type byte.
+type Map = Vec<(Vec<u8>, Vec<u8>)>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Psbt {
+  version: u32,
+  global: Map,
+  inputs: Vec<Map>,
+  outputs: Vec<Map>
+}
+
+impl Psbt {
+  pub fn parse(data: &[u8]) -> Result<Self, Error> {
+    if !data.starts_with(MAGIC) {
+      return Err(PsbtError::InvalidMagic.into());
+    }
+    let mut reader = Reader::new(&data[MAGIC.len()..]);
+    let global = read_map(&mut reader)?;
+    let version = match get(&global, GLOBAL_VERSION) {
+      Some(version) => u32_field(version, "version")?,
+      None => 0
+    };
+    let (inputs, outputs) = match version {
+      0 => {
+        let tx = Transaction::parse(required(&global, GLOBAL_UNSIGNED_TX, "unsigned tx")?)?;
+        (tx.inputs.len() as u64, tx.outputs.len() as u64)
+      }
+      2 => (
+        compact_size_field(required(&global, GLOBAL_INPUT_COUNT, "input count")?, "input count")?,
+        compact_size_field(
+          required(&global, GLOBAL_OUTPUT_COUNT, "output count")?,
+          "output count"
+        )?
+      ),
+      _ => return Err(PsbtError::UnsupportedVersion(version).into())
+    };
+    let inputs = (0..inputs).map(|_| read_map(&mut reader)).collect::<Result<Vec<Map>, Error>>()?;
+    let outputs =
+      (0..outputs).map(|_| read_map(&mut reader)).collect::<Result<Vec<Map>, Error>>()?;
+    reader.finish()?;
+    Ok(Psbt { version, global, inputs, outputs })
+  }
+
+  pub fn from_base64(psbt: &str) -> Result<Self, Error> {
+    Self::parse(&base64::decode(psbt).map_err(|err| Error::InvalidData(Box::new(err)))?)
+  }
+
+  pub fn serialize(&self) -> Vec<u8> {
+    let mut out = Vec::from(MAGIC);
+    write_map(&self.global, &mut out);
+    self.inputs.iter().chain(self.outputs.iter()).for_each(|map| write_map(map, &mut out));
+    out
+  }
+
+  pub fn to_base64(&self) -> String {
+    base64::encode(&self.serialize())
+  }
+
+  pub fn version(&self) -> u32 {
+    self.version
+  }
+
+  // Transaction without signatures. Version 2 transaction is built from per input and output
+  // fields, lock time is selected by BIP370 rules.
+  pub fn unsigned_tx(&self) -> Result<Transaction, Error> {
+    if self.version == 0 {
+      return Transaction::parse(required(&self.global, GLOBAL_UNSIGNED_TX, "unsigned tx")?);
+    }
+    let version =
+      u32_field(required(&self.global, GLOBAL_TX_VERSION, "tx version")?, "tx version")?;
+    let inputs = self
+      .inputs
+      .iter()
+      .map(|input| {
+        let txid = required(input, IN_PREVIOUS_TXID, "previous txid")?;
+        if txid.len() != HASH_SIZE {
+          return Err(PsbtError::InvalidField("previous txid").into());
+        }
+        let mut previous_output = OutPoint { txid: [0; HASH_SIZE], vout: 0 };
+        previous_output.txid.copy_from_slice(txid);
+        previous_output.vout =
+          u32_field(required(input, IN_OUTPUT_INDEX, "output index")?, "output index")?;
+        let sequence = match get(input, IN_SEQUENCE) {
+          Some(sequence) => u32_field(sequence, "sequence")?,
+          None => 0xffff_ffff
+        };
+        Ok(TxIn { previous_output, script_sig: Vec::new(), sequence, witness: Vec::new() })
+      })
+      .collect::<Result<Vec<TxIn>, Error>>()?;
+    let outputs = self
+      .outputs
+      .iter()
+      .map(|output| {
+        Ok(TxOut {
+          value: u64_field(required(output, OUT_AMOUNT, "amount")?, "amount")?,
+          script_pubkey: Vec::from(required(output, OUT_SCRIPT, "script")?)
+        })
+      })
+      .collect::<Result<Vec<TxOut>, Error>>()?;
+    Ok(Transaction { version: version as i32, inputs, outputs, lock_time: self.lock_time()? })
+  }
+
+  // Height lock time is preferred, if all inputs with requirements allow it
+  fn lock_time(&self) -> Result<u32, Error> {
+    let mut heights = Vec::new();
+    let mut times = Vec::new();
+    let (mut height_only, mut time_only) = (false, false);
+    for input in self.inputs.iter() {
+      let height = get(input, IN_REQUIRED_HEIGHT_LOCKTIME).map(|h| u32_field(h, "height lock"));
+      let time = get(input, IN_REQUIRED_TIME_LOCKTIME).map(|t| u32_field(t, "time lock"));
+      match (height, time) {
+        (Some(height), Some(time)) => {
+          heights.push(height?);
+          times.push(time?);
+        }
+        (Some(height), None) => {
+          heights.push(height?);
+          height_only = true;
+        }
+        (None, Some(time)) => {
+          times.push(time?);
+          time_only = true;
+        }
+        (None, None) => {}
+      }
+    }
+    if height_only && time_only {
+      return Err(PsbtError::ConflictingLockTimes.into());
+    }
+    match (heights.iter().max(), times.iter().max()) {
+      (Some(&height), _) if !time_only => Ok(height),
+      (_, Some(&time)) => Ok(time),
+      _ => match get(&self.global, GLOBAL_FALLBACK_LOCKTIME) {
+        Some(lock_time) => u32_field(lock_time, "fallback lock time"),
+        None => Ok(0)
+      }
+    }
+  }
+
+  // Spent output from non-witness UTXO, which should match the input, or from witness UTXO
+  fn prevout(&self, index: usize, tx: &Transaction) -> Result<Option<TxOut>, Error> {
+    let input = &self.inputs[index];
+    if let Some(prev_tx) = get(input, IN_NON_WITNESS_UTXO) {
+      let prev_tx = Transaction::parse(prev_tx)?;
+      let outpoint = &tx.inputs[index].previous_output;
+      if prev_tx.txid() != outpoint.txid {
+        return Err(PsbtError::PrevoutMismatch(index).into());
+      }
+      let output = prev_tx.outputs.get(outpoint.vout as usize);
+      return output.cloned().map(Some).ok_or_else(|| PsbtError::PrevoutMismatch(index).into());
+    }
+    match get(input, IN_WITNESS_UTXO) {
+      Some(utxo) => {
+        let mut reader = Reader::new(utxo);
+        let output = TxOut { value: reader.u64()?, script_pubkey: reader.bytes()? };
+        reader.finish()?;
+        Ok(Some(output))
+      }
+      None => Ok(None)
+    }
+  }
+}
+
+// Adds signatures of the master key descendants. Inputs, which are finalized or have unknown
+// scripts, are skipped.
+pub(super) fn sign_psbt(master: &XPrv, psbt: &Psbt) -> Result<Psbt, Error> {
+  let fingerprint = master.fingerprint();
+  let tx = psbt.unsigned_tx()?;
+  if tx.inputs.len() != psbt.inputs.len() {
+    return Err(PsbtError::InvalidField("input count").into());
+  }
+  let prevouts = (0..tx.inputs.len())
+    .map(|index| psbt.prevout(index, &tx))
+    .collect::<Result<Vec<Option<TxOut>>, Error>>()?;
+
+  let mut signed = psbt.clone();
+  for (index, input) in psbt.inputs.iter().enumerate() {
+    let prevout = match prevouts[index] {
+      Some(ref prevout) => prevout,
+      None => continue
+    };
+    if get(input, IN_FINAL_SCRIPTSIG).is_some() || get(input, IN_FINAL_SCRIPTWITNESS).is_some() {
+      continue;
+    }
+    for (pub_key, derivation) in entries(input, IN_BIP32_DERIVATION) {
+      let key = match derive(master, &fingerprint, derivation)? {
+        Some(key) => key,
+        None => continue
+      };
+      if key.public().serialize_compressed() != pub_key {
+        continue;
+      }
+      if let Some(signature) = sign_ecdsa(&tx, index, input, prevout, &key)? {
+        set(&mut signed.inputs[index], [&[IN_PARTIAL_SIG], pub_key].concat(), signature);
+      }
+    }
+    for (x_only, derivation) in entries(input, IN_TAP_BIP32_DERIVATION) {
+      let mut reader = Reader::new(derivation);
+      // Keys with leaf hashes are used in script path spending
+      if reader.compact_size()? != 0 {
+        continue;
+      }
+      let key = match derive(master, &fingerprint, &derivation[1..])? {
+        Some(key) => key,
+        None => continue
+      };
+      if &key.public().x_only()[..] != x_only {
+        continue;
+      }
+      let script = &prevout.script_pubkey;
+      if let Some(signature) = sign_taproot(&tx, index, input, script, &prevouts, &key)? {
+        set(&mut signed.inputs[index], vec![IN_TAP_KEY_SIG], signature);
+      }
+    }
+  }
+  Ok(signed)
+}
+
+// DER signature with sighash type byte for P2PKH, P2WPKH, P2WSH and P2SH wrapped scripts.
+// Script hash inputs are signed if their script contains the key.
+fn sign_ecdsa(
+  tx: &Transaction, index: usize, input: &Map, prevout: &TxOut, key: &XPrv
+) -> Result<Option<Vec<u8>>, Error> {
+  let pub_key = key.public().serialize_compressed();
+  let sighash_type = sighash_type(input)?.unwrap_or(SighashType::ALL).check()?;
+
+  let mut script = &prevout.script_pubkey[..];
+  let p2sh = script.len() == 23 && script == &p2sh_script(&script[2..22])[..];
+  if p2sh {
+    let redeem = match get(input, IN_REDEEM_SCRIPT) {
+      Some(redeem) => redeem,
+      None => return Ok(None)
+    };
+    if hash160(redeem)[..] != script[2..22] {
+      return Err(PsbtError::InvalidField("redeem script").into());
+    }
+    script = redeem;
+  }
+
+  let sighash = if is_witness_program(script, OP_0, 20) {
+    if hash160(&pub_key)[..] != script[2..] {
+      return Ok(None);
+    }
+    tx.segwit_v0_sighash(index, &p2pkh_script(&script[2..]), prevout.value, sighash_type)?
+  } else if is_witness_program(script, OP_0, HASH_SIZE) {
+    let witness_script = match get(input, IN_WITNESS_SCRIPT) {
+      Some(witness_script) => witness_script,
+      None => return Ok(None)
+    };
+    if sha256(witness_script)[..] != script[2..] {
+      return Err(PsbtError::InvalidField("witness script").into());
+    }
+    if !contains(witness_script, &pub_key) {
+      return Ok(None);
+    }
+    tx.segwit_v0_sighash(index, witness_script, prevout.value, sighash_type)?
+  } else if !p2sh && script.len() == 25 && script == &p2pkh_script(&script[3..23])[..] {
+    if hash160(&pub_key)[..] != script[3..23] {
+      return Ok(None);
+    }
+    legacy_sighash(tx, index, input, script, sighash_type)?
+  } else if p2sh && contains(script, &pub_key) {
+    legacy_sighash(tx, index, input, script, sighash_type)?
+  } else {
+    return Ok(None);
+  };
+
+  let mut signature = key.sign_digest_der(&sighash).map_err(Error::from_secp_sign_error)?;
+  signature.push(sighash_type.0);
+  Ok(Some(signature))
+}
+
+// Legacy signatures don't commit to the spent value, so it's proven by the previous transaction
+fn legacy_sighash(
+  tx: &Transaction, index: usize, input: &Map, script_code: &[u8], sighash_type: SighashType
+) -> Result<[u8; HASH_SIZE], Error> {
+  required(input, IN_NON_WITNESS_UTXO, "non-witness utxo")?;
+  tx.check_single_output(index, sighash_type)?;
+  tx.legacy_sighash(index, script_code, sighash_type)
+}
+
+// BIP86 key path signature with SIGHASH_DEFAULT or SIGHASH_ALL. Outputs with script tree and
+// other sighash types are skipped.
+fn sign_taproot(
+  tx: &Transaction, index: usize, input: &Map, script: &[u8], prevouts: &[Option<TxOut>],
+  key: &XPrv
+) -> Result<Option<Vec<u8>>, Error> {
+  if !is_witness_program(script, OP_1, X_ONLY_KEY_SIZE) || get(input, IN_TAP_MERKLE_ROOT).is_some()
+  {
+    return Ok(None);
+  }
+  let sighash_type = sighash_type(input)?.unwrap_or(SighashType::DEFAULT);
+  if sighash_type != SighashType::DEFAULT && sighash_type != SighashType::ALL {
+    return Ok(None);
+  }
+  if key.public().taproot_output_key()?[..] != script[2..] {
+    return Ok(None);
+  }
+  // Taproot signature commits to all spent outputs
+  let prevouts = match prevouts.iter().cloned().collect::<Option<Vec<TxOut>>>() {
+    Some(prevouts) => prevouts,
+    None => return Ok(None)
+  };
+  let sighash = tx.taproot_sighash(index, &prevouts, sighash_type)?;
+  let mut aux = [0u8; HASH_SIZE];
+  OsEntropy::new().fill_bytes(&mut aux);
+  let mut signature = key.sign_taproot(&sighash, &aux).map_err(Error::from_secp_sign_error)?;
+  if sighash_type != SighashType::DEFAULT {
+    signature.push(sighash_type.0);
+  }
+  Ok(Some(signature))
+}
+
+// Sighash type is a single byte value in 4 bytes field
+fn sighash_type(input: &Map) -> Result<Option<SighashType>, Error> {
+  match get(input, IN_SIGHASH_TYPE) {
+    Some(value) => match u32_field(value, "sighash type")? {
+      sighash_type if sighash_type <= 0xff => Ok(Some(SighashType(sighash_type as u8))),
+      _ => Err(PsbtError::InvalidField("sighash type").into())
+    },
+    None => Ok(None)
+  }
+}
+
+// Key of derivation with the fingerprint and path, if fingerprint is of the master key
+fn derive(master: &XPrv, fingerprint: &[u8], derivation: &[u8]) -> Result<Option<XPrv>, Error> {
+  if derivation.len() < FINGERPRINT_SIZE {
+    return Err(PsbtError::InvalidField("BIP32 derivation").into());
+  }
+  let indexes = derivation[FINGERPRINT_SIZE..].chunks_exact(4);
+  if !indexes.remainder().is_empty() {
+    return Err(PsbtError::InvalidField("BIP32 derivation").into());
+  }
+  if &derivation[..FINGERPRINT_SIZE] != fingerprint {
+    return Ok(None);
+  }
+  let path: Vec<u32> = indexes
+    .map(|index| index.iter().rev().fold(0u32, |value, &byte| value << 8 | byte as u32))
+    .collect();
+  master.derive_path(&path).map(Some).map_err(|err| err.into())
+}
+
+fn is_witness_program(script: &[u8], version: u8, size: usize) -> bool {
+  script.len() == size + 2 && script[0] == version && script[1] as usize == size
+}
+
+fn contains(script: &[u8], pub_key: &[u8]) -> bool {
+  pub_key.len() == COMPRESSED_KEY_SIZE && script.windows(pub_key.len()).any(|data| data == pub_key)
+}
+
+fn read_map(reader: &mut Reader) -> Result<Map, Error> {
+  let mut map = Map::new();
+  loop {
+    let key = reader.bytes()?;
+    if key.is_empty() {
+      return Ok(map);
+    }
+    if map.iter().any(|(existing, _)| existing == &key) {
+      return Err(PsbtError::DuplicateKey(key).into());
+    }
+    let value = reader.bytes()?;
+    map.push((key, value));
+  }
+}
+
+fn write_map(map: &Map, out: &mut Vec<u8>) {
+  for (key, value) in map.iter() {
+    write_bytes(key, out);
+    write_bytes(value, out);
+  }
+  write_compact_size(0, out);
+}
+
+// Value of the key without key data
+fn get(map: &Map, key_type: u8) -> Option<&[u8]> {
+  map.iter().find(|(key, _)| key[..] == [key_type]).map(|(_, value)| &value[..])
+}
+
+fn required<'a>(map: &'a Map, key_type: u8, name: &'static str) -> Result<&'a [u8], Error> {
+  get(map, key_type).ok_or_else(|| PsbtError::MissingField(name).into())
+}
+
+// Key data and values of the key type
+fn entries(map: &Map, key_type: u8) -> impl Iterator<Item = (&[u8], &[u8])> {
+  map
+    .iter()
+    .filter(move |(key, _)| key.len() > 1 && key[0] == key_type)
+    .map(|(key, value)| (&key[1..], &value[..]))
+}
+
+fn set(map: &mut Map, key: Vec<u8>, value: Vec<u8>) {
+  match map.iter_mut().find(|(existing, _)| existing == &key) {
+    Some(entry) => entry.1 = value,
+    None => map.push((key, value))
+  }
+}
+
+fn u32_field(value: &[u8], name: &'static str) -> Result<u32, Error> {
+  let mut reader = Reader::new(value);
+  let result = reader.u32().map_err(|_| PsbtError::InvalidField(name))?;
+  reader.finish().map_err(|_| PsbtError::InvalidField(name))?;
+  Ok(result)
+}
+
+fn u64_field(value: &[u8], name: &'static str) -> Result<u64, Error> {
+  let mut reader = Reader::new(value);
+  let result = reader.u64().map_err(|_| PsbtError::InvalidField(name))?;
+  reader.finish().map_err(|_| PsbtError::InvalidField(name))?;
+  Ok(result)
+}
+
+fn compact_size_field(value: &[u8], name: &'static str) -> Result<u64, Error> {
+  let mut reader = Reader::new(value);
+  let result = reader.compact_size().map_err(|_| PsbtError::InvalidField(name))?;
+  reader.finish().map_err(|_| PsbtError::InvalidField(name))?;
+  Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::address::{key_hash_script, script_pubkey, AddressType};
+  use super::super::fixture::key;
+  use super::super::key::{Key, PublicKey};
+  use super::super::key_path::KeyPath;
+  use super::super::transaction::{write_bytes, OutPoint, SighashType, Transaction, TxIn, TxOut};
+  use super::{sign_psbt, Map, Psbt, IN_PARTIAL_SIG, IN_TAP_KEY_SIG};
+  use bip39::util::hex;
+  use derivation_path::DerivationPath;
+  use key_path::KeyPath as IKeyPath;
+  use secp256k1_bip32::hash::{hash160, sha256};
+  use secp256k1_bip32::{verify_der, verify_schnorr, XPrv};
+
+  const FINGERPRINT: &str = "73c5da0a";
+
+  // BIP174 signer vector: 2-of-2 multisig in P2SH and P2SH-P2WSH inputs
+  const BIP174_MASTER: &str =
+    "tprv8ZgxMBicQKsPd9TeAdPADNnSyH9SSUUbTVeFszDE23Ki6TBB5nCefAdHkK8Fm3qMQR6\
+     sHwA56zqRmKmxnHk37JkiFzvncDqoKmPWubu7hDF";
+  const BIP174_PSBT: &str =
+    "cHNidP8BAJoCAAAAAljoeiG1ba8MI76OcHBFbDNvfLqlyHV5JPVFiHuyq911AAAAAAD///\
+     //g40EJ9DsZQpoqka7CwmK6kQiwHGyyng1Kgd5WdB86h0BAAAAAP////8CcKrwCAAAAAAW\
+     ABTYXCtx0AYLCcmIauuBXlCZHdoSTQDh9QUAAAAAFgAUAK6pouXw+HaliN9VRuh0LR2HAI\
+     8AAAAAAAEAuwIAAAABqtc5MQGL0l+ErkALaISL4J23BurCrBgpi6vucatlb4sAAAAASEcw\
+     RAIgWPb8fGoz4bMVSNSByCbAFb0wE1qtQs1neQ2rZtKtJDsCIEoc7SYExnNbY5PltBaR3X\
+     iwDwxZQvufdRhW+qk4FX26Af7///8CgPD6AgAAAAAXqRQPuUY0IWlrgsgzryQceMF9295J\
+     NIfQ8gonAQAAABepFCnKdPigj4GZlCgYXJe12FLkBj9hh2UAAAABBEdSIQKVg785rgpgl0\
+     etGZrd1jT6YQhVnWxc05tMIYPxq5bgfyEC2rYf9JoU22p9ArDNH7t4/EsYMStbTlTa5Nui\
+     +/71NtdSriIGApWDvzmuCmCXR60Zmt3WNPphCFWdbFzTm0whg/GrluB/ENkMak8AAACAAA\
+     AAgAAAAIAiBgLath/0mhTban0CsM0fu3j8SxgxK1tOVNrk26L7/vU21xDZDGpPAAAAgAAA\
+     AIABAACAAAEBIADC6wsAAAAAF6kUt/X69A49QKWkWbHbNTXyty+pIeiHAQQiACCMI1MXN0\
+     O1ld+0oHtyuo5C43l9p06H/n2ddJfjsgKJAwEFR1IhAwidwQx6xttU+RMpr2FzM9s4jOrQ\
+     wjH3IzedG5kDCwLcIQI63ZBPPW3PWd25BrDe4jUpt/+57VDl6GFRkmhgIh8Oc1KuIgYCOt\
+     2QTz1tz1nduQaw3uI1Kbf/ue1Q5ehhUZJoYCIfDnMQ2QxqTwAAAIAAAACAAwAAgCIGAwid\
+     wQx6xttU+RMpr2FzM9s4jOrQwjH3IzedG5kDCwLcENkMak8AAACAAAAAgAIAAIAAIgIDqa\
+     TDf1mW06ol26xrVwrwZQOUSSlCRgs1R1Ptnuylh3EQ2QxqTwAAAIAAAACABAAAgAAiAgJ/\
+     Y5l1fS7/VaE2rQLGhLGDi2VW5fG2s0KCqUtrUAUQlhDZDGpPAAAAgAAAAIAFAACAAA==";
+  // Partial signatures of both BIP174 signers: input, public key and signature
+  const BIP174_SIGNATURES: [(usize, &str, &str); 4] = [
+    (
+      0,
+      "029583bf39ae0a609747ad199addd634fa6108559d6c5cd39b4c2183f1ab96e07f",
+      "3044022074018ad4180097b873323c0015720b3684cc8123891048e7dbcd9b55ad679c99022073d369b740e3eb\
+       53dcefa33823c8070514ca55a7dd9544f157c167913261118c01"
+    ),
+    (
+      0,
+      "02dab61ff49a14db6a7d02b0cd1fbb78fc4b18312b5b4e54dae4dba2fbfef536d7",
+      "3045022100f61038b308dc1da865a34852746f015772934208c6d24454393cd99bdf2217770220056e675a675a\
+       6d0a02b85b14e5e29074d8a25a9b5760bea2816f661910a006ea01"
+    ),
+    (
+      1,
+      "023add904f3d6dcf59ddb906b0dee23529b7ffb9ed50e5e86151926860221f0e73",
+      "3044022065f45ba5998b59a27ffe1a7bed016af1f1f90d54b3aa8f7450aa5f56a25103bd02207f724703ad1edb\
+       96680b284b56d4ffcb88f7fb759eabbe08aa30f29b851383d201"
+    ),
+    (
+      1,
+      "03089dc10c7ac6db54f91329af617333db388cead0c231f723379d1b99030b02dc",
+      "3044022062eb7a556107a7c73f45ac4ab5a1dddf6f7075fb1275969a7f383efff784bcb202200c05dbb7470dbf\
+       2f08557dd356c7325c1ed30913e996cd3840945db12228da5f01"
+    )
+  ];
+
+  fn paths() -> Vec<DerivationPath> {
+    vec![
+      KeyPath::bip44(false, 0, 0, 0).unwrap().derivation_path(),
+      KeyPath::bip49(false, 0, 0, 0).unwrap().derivation_path(),
+      KeyPath::bip84(false, 0, 1, 2).unwrap().derivation_path(),
+      KeyPath::bip86(false, 0, 0, 0).unwrap().derivation_path(),
+    ]
+  }
+
+  fn derivation(fingerprint: &str, path: &DerivationPath) -> Vec<u8> {
+    let mut value = hex::decode(fingerprint).unwrap();
+    path.path().iter().for_each(|index| value.extend_from_slice(&index.to_le_bytes()));
+    value
+  }
+
+  fn address_type(index: usize) -> AddressType {
+    [AddressType::P2PKH, AddressType::P2SHP2WPKH, AddressType::P2WPKH, AddressType::P2TR][index]
+  }
+
+  // Previous transaction pays to the address of each path, unsigned transaction spends its outputs
+  fn transactions(key: &Key) -> (Transaction, Transaction) {
+    let outputs = paths()
+      .iter()
+      .enumerate()
+      .map(|(index, path)| TxOut {
+        value: 1000 * (index as u64 + 1),
+        script_pubkey: script_pubkey(&key.derive_public(path).unwrap(), address_type(index))
+          .unwrap()
+      })
+      .collect();
+    let prev_tx = Transaction { version: 2, inputs: Vec::new(), outputs, lock_time: 0 };
+    let inputs = (0..4)
+      .map(|vout| TxIn {
+        previous_output: OutPoint { txid: prev_tx.txid(), vout },
+        script_sig: Vec::new(),
+        sequence: 0xffff_fffd,
+        witness: Vec::new()
+      })
+      .collect();
+    let outputs = vec![TxOut { value: 9000, script_pubkey: vec![0x6a] }];
+    (prev_tx, Transaction { version: 2, inputs, outputs, lock_time: 0 })
+  }
+
+  fn input_maps(key: &Key, prev_tx: &Transaction) -> Vec<Map> {
+    let paths = paths();
+    let witness_utxo = |index: usize| {
+      let mut value = Vec::from(&prev_tx.outputs[index].value.to_le_bytes()[..]);
+      value.push(prev_tx.outputs[index].script_pubkey.len() as u8);
+      value.extend_from_slice(&prev_tx.outputs[index].script_pubkey);
+      value
+    };
+    let pub_key = |index: usize| key.derive_public(&paths[index]).unwrap();
+    let bip32 = |index: usize| {
+      let mut key = vec![0x06];
+      key.extend_from_slice(&pub_key(index).serialize_compressed());
+      (key, derivation(FINGERPRINT, &paths[index]))
+    };
+    let mut redeem = vec![0x00, 0x14];
+    redeem.extend_from_slice(&hash160(&pub_key(1).serialize_compressed()));
+    let mut foreign = bip32(2);
+    foreign.0[1] ^= 1;
+    foreign.1 = derivation("ffffffff", &paths[2]);
+    let mut tap_key = vec![0x16];
+    tap_key.extend_from_slice(&pub_key(3).x_only());
+    let tap_value = [&[0][..], &derivation(FINGERPRINT, &paths[3])].concat();
+    vec![
+      vec![(vec![0x00], prev_tx.serialize()), bip32(0)],
+      vec![(vec![0x01], witness_utxo(1)), (vec![0x04], redeem), bip32(1)],
+      vec![(vec![0x01], witness_utxo(2)), (vec![0x03], vec![0x83, 0, 0, 0]), foreign, bip32(2)],
+      vec![(vec![0x01], witness_utxo(3)), (tap_key, tap_value)],
+    ]
+  }
+
+  fn psbt_v0(key: &Key) -> Psbt {
+    let (prev_tx, tx) = transactions(key);
+    Psbt {
+      version: 0,
+      global: vec![(vec![0x00], tx.serialize())],
+      inputs: input_maps(key, &prev_tx),
+      outputs: vec![Vec::new()]
+    }
+  }
+
+  fn psbt_v2(key: &Key) -> Psbt {
+    let (prev_tx, tx) = transactions(key);
+    let mut inputs = input_maps(key, &prev_tx);
+    for (index, input) in inputs.iter_mut().enumerate() {
+      input.push((vec![0x0e], Vec::from(&prev_tx.txid()[..])));
+      input.push((vec![0x0f], Vec::from(&(index as u32).to_le_bytes()[..])));
+      input.push((vec![0x10], Vec::from(&tx.inputs[index].sequence.to_le_bytes()[..])));
+    }
+    let output = vec![
+      (vec![0x03], Vec::from(&tx.outputs[0].value.to_le_bytes()[..])),
+      (vec![0x04], tx.outputs[0].script_pubkey.clone()),
+    ];
+    Psbt {
+      version: 2,
+      global: vec![
+        (vec![0x02], vec![2, 0, 0, 0]),
+        (vec![0x04], vec![4]),
+        (vec![0x05], vec![1]),
+        (vec![0xfb], vec![2, 0, 0, 0]),
+      ],
+      inputs,
+      outputs: vec![output]
+    }
+  }
+
+  fn check_signatures(key: &Key, signed: &Psbt) {
+    let tx = signed.unsigned_tx().unwrap();
+    let (prev_tx, _) = transactions(key);
+    let flags = [SighashType::ALL, SighashType::ALL, SighashType::SINGLE_ANYONECANPAY];
+    for (index, path) in paths().iter().enumerate().take(3) {
+      let pub_key = key.derive_public(path).unwrap().serialize_compressed();
+      let signatures: Vec<&(Vec<u8>, Vec<u8>)> =
+        signed.inputs[index].iter().filter(|entry| entry.0[0] == IN_PARTIAL_SIG).collect();
+      assert_eq!(signatures.len(), 1);
+      assert_eq!(signatures[0].0[1..], pub_key[..]);
+      let signature = &signatures[0].1;
+      assert_eq!(signature.last(), Some(&flags[index].0));
+
+      let script_code = key_hash_script(&hash160(&pub_key), AddressType::P2PKH);
+      let sighash = if index == 0 {
+        tx.legacy_sighash(index, &script_code, flags[index])
+      } else {
+        tx.segwit_v0_sighash(index, &script_code, prev_tx.outputs[index].value, flags[index])
+      };
+      let signature = &signature[..signature.len() - 1];
+      assert!(verify_der(&sighash.unwrap(), &pub_key, signature).unwrap());
+    }
+
+    let signature = tap_key_sig(signed).unwrap();
+    let output_key = &prev_tx.outputs[3].script_pubkey[2..];
+    let sighash = tx.taproot_sighash(3, &prev_tx.outputs, SighashType::DEFAULT).unwrap();
+    assert!(verify_schnorr(&sighash, output_key, signature).unwrap());
+  }
+
+  fn tap_key_sig(psbt: &Psbt) -> Option<&Vec<u8>> {
+    psbt.inputs[3].iter().find(|entry| entry.0 == [IN_TAP_KEY_SIG]).map(|entry| &entry.1)
+  }
+
+  #[test]
+  fn sign_psbt_v0() {
+    let key = key();
+    let psbt = Psbt::from_base64(&psbt_v0(&key).to_base64()).unwrap();
+    assert_eq!(psbt, psbt_v0(&key));
+    let signed = key.sign_psbt(&psbt).unwrap();
+    check_signatures(&key, &signed);
+    assert_eq!(Psbt::parse(&signed.serialize()).unwrap(), signed);
+
+    // Finalized inputs aren't signed again
+    let mut finalized = psbt.clone();
+    finalized.inputs[2].push((vec![0x08], vec![0]));
+    assert_eq!(key.sign_psbt(&finalized).unwrap().inputs[2], finalized.inputs[2]);
+  }
+
+  #[test]
+  fn sign_psbt_v2() {
+    let key = key();
+    let psbt = Psbt::parse(&psbt_v2(&key).serialize()).unwrap();
+    assert_eq!(psbt.version(), 2);
+    assert_eq!(psbt.unsigned_tx().unwrap(), psbt_v0(&key).unsigned_tx().unwrap());
+    check_signatures(&key, &key.sign_psbt(&psbt).unwrap());
+  }
+
+  fn partial_signatures(psbt: &Psbt) -> Vec<(usize, String, String)> {
+    let mut signatures = Vec::new();
+    for (index, input) in psbt.inputs.iter().enumerate() {
+      for &(ref key, ref value) in input.iter().filter(|entry| entry.0[0] == IN_PARTIAL_SIG) {
+        signatures.push((index, hex::encode(&key[1..]), hex::encode(value)));
+      }
+    }
+    signatures.sort();
+    signatures
+  }
+
+  #[test]
+  fn bip174_signer() {
+    let (master, _) = XPrv::from_base58(BIP174_MASTER).unwrap();
+    let mut psbt = Psbt::from_base64(BIP174_PSBT).unwrap();
+    // Signer vector has explicit SIGHASH_ALL
+    psbt.inputs.iter_mut().for_each(|input| input.push((vec![0x03], vec![0x01, 0, 0, 0])));
+    let expected: Vec<(usize, String, String)> = BIP174_SIGNATURES
+      .iter()
+      .map(|&(index, pub_key, signature)| (index, pub_key.to_owned(), signature.to_owned()))
+      .collect();
+    assert_eq!(partial_signatures(&sign_psbt(&master, &psbt).unwrap()), expected);
+
+    // P2WSH commits to the same script code and value as P2SH-P2WSH
+    let value = |input: &Map, key_type: u8| {
+      input.iter().find(|entry| entry.0 == [key_type]).map(|entry| entry.1.clone()).unwrap()
+    };
+    let mut utxo = value(&psbt.inputs[1], 0x01)[..8].to_vec();
+    utxo.extend_from_slice(&[0x22, 0x00, 0x20]);
+    utxo.extend_from_slice(&sha256(&value(&psbt.inputs[1], 0x05)));
+    psbt.inputs[1].retain(|entry| entry.0 != [0x01] && entry.0 != [0x04]);
+    psbt.inputs[1].push((vec![0x01], utxo));
+    assert_eq!(partial_signatures(&sign_psbt(&master, &psbt).unwrap()), expected);
+
+    // Legacy input is refused without previous transaction, which proves the spent value
+    let prev_tx = Transaction::parse(&value(&psbt.inputs[0], 0x00)).unwrap();
+    let mut utxo = Vec::from(&prev_tx.outputs[0].value.to_le_bytes()[..]);
+    write_bytes(&prev_tx.outputs[0].script_pubkey, &mut utxo);
+    psbt.inputs[0].retain(|entry| entry.0 != [0x00]);
+    psbt.inputs[0].push((vec![0x01], utxo));
+    assert!(sign_psbt(&master, &psbt).is_err());
+  }
+
+  #[test]
+  fn lock_time() {
+    let mut psbt = psbt_v2(&key());
+    psbt.global.push((vec![0x03], vec![100, 0, 0, 0]));
+    assert_eq!(psbt.unsigned_tx().unwrap().lock_time, 100);
+    psbt.inputs[0].push((vec![0x12], vec![200, 0, 0, 0]));
+    psbt.inputs[1].push((vec![0x11], vec![0, 0, 0, 0x60]));
+    psbt.inputs[1].push((vec![0x12], vec![150, 0, 0, 0]));
+    assert_eq!(psbt.unsigned_tx().unwrap().lock_time, 200);
+    psbt.inputs[2].push((vec![0x11], vec![0, 0, 0, 0x61]));
+    assert!(psbt.unsigned_tx().is_err());
+    psbt.inputs[0].retain(|entry| entry.0 != [0x12]);
+    assert_eq!(psbt.unsigned_tx().unwrap().lock_time, 0x6100_0000);
+  }
+
+  #[test]
+  fn invalid_psbt() {
+    let key = key();
+    let data = psbt_v0(&key).serialize();
+    assert!(Psbt::parse(&data[1..]).is_err());
+    assert!(Psbt::parse(&data[..data.len() - 1]).is_err());
+
+    let mut duplicate = psbt_v0(&key);
+    let entry = duplicate.inputs[0][1].clone();
+    duplicate.inputs[0].push(entry);
+    assert!(Psbt::parse(&duplicate.serialize()).is_err());
+
+    let mut mismatch = psbt_v0(&key);
+    mismatch.inputs[0][0].1[0] ^= 1;
+    assert!(key.sign_psbt(&mismatch).is_err());
+
+    // P2PKH input with witness UTXO only
+    let (prev_tx, _) = transactions(&key);
+    let mut witness_utxo = psbt_v0(&key);
+    let mut utxo = Vec::from(&prev_tx.outputs[0].value.to_le_bytes()[..]);
+    write_bytes(&prev_tx.outputs[0].script_pubkey, &mut utxo);
+    witness_utxo.inputs[0][0] = (vec![0x01], utxo);
+    assert!(key.sign_psbt(&witness_utxo).is_err());
+  }
+
+  #[test]
+  fn sighash_types() {
+    let key = key();
+    let (prev_tx, _) = transactions(&key);
+    let with_sighash = |index: usize, value: Vec<u8>| {
+      let mut psbt = psbt_v2(&key);
+      psbt.inputs[index].push((vec![0x03], value));
+      psbt
+    };
+
+    // Taproot signature with explicit SIGHASH_ALL has sighash type byte
+    let signed = key.sign_psbt(&with_sighash(3, vec![0x01, 0, 0, 0])).unwrap();
+    let signature = tap_key_sig(&signed).unwrap();
+    assert_eq!(signature.len(), 65);
+    assert_eq!(signature[64], SighashType::ALL.0);
+    let tx = signed.unsigned_tx().unwrap();
+    let sighash = tx.taproot_sighash(3, &prev_tx.outputs, SighashType::ALL).unwrap();
+    let output_key = &prev_tx.outputs[3].script_pubkey[2..];
+    assert!(verify_schnorr(&sighash, output_key, &signature[..64]).unwrap());
+
+    // Unsupported taproot sighash types skip the input only
+    let signed = key.sign_psbt(&with_sighash(3, vec![0x83, 0, 0, 0])).unwrap();
+    assert!(tap_key_sig(&signed).is_none());
+    assert!(signed.inputs[0].iter().any(|entry| entry.0[0] == IN_PARTIAL_SIG));
+
+    // Sighash types are not truncated to a byte
+    assert!(key.sign_psbt(&with_sighash(0, vec![0x01, 0x01, 0, 0])).is_err());
+    assert!(key.sign_psbt(&with_sighash(0, vec![0x04, 0, 0, 0])).is_err());
+
+    // Legacy SIGHASH_SINGLE input without matching output is refused
+    assert!(key.sign_psbt(&with_sighash(0, vec![0x03, 0, 0, 0])).is_ok());
+    let mut psbt = psbt_v2(&key);
+    psbt.global.retain(|entry| entry.0 != [0x05]);
+    psbt.global.push((vec![0x05], vec![0]));
+    psbt.outputs.clear();
+    assert!(key.sign_psbt(&psbt).is_ok());
+    psbt.inputs[0].push((vec![0x03], vec![0x03, 0, 0, 0]));
+    assert!(key.sign_psbt(&psbt).is_err());
+  }
+}
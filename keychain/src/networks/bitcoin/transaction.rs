@@ -0,0 +1,765 @@
+use super::address::{p2pkh_script, p2wpkh_script, AddressType};
+use derivation_path::DerivationPath;
+use key::Error;
+use secp256k1_bip32::hash::{hash160, sha256, sha256d, tagged_hash, HASH_SIZE};
+use secp256k1_bip32::XPrv;
+use std::fmt;
+
+// Segwit serialization marker and flag (BIP144)
+const SEGWIT_MARKER: u8 = 0x00;
+const SEGWIT_FLAG: u8 = 0x01;
+
+const SIGHASH_ANYONECANPAY: u8 = 0x80;
+const SIGHASH_BASE_MASK: u8 = 0x1f;
+
+// Sighash of SIGHASH_SINGLE input without matching output. Used for verification only: the same
+// constant is signed for any transaction, so signature of it spends every P2PKH output of the key.
+const SIGHASH_SINGLE_BUG: [u8; HASH_SIZE] =
+  [1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+// Sighash type byte, which is appended to ECDSA signatures
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SighashType(pub u8);
+
+impl SighashType {
+  pub const ALL: SighashType = SighashType(0x01);
+  pub const NONE: SighashType = SighashType(0x02);
+  pub const SINGLE: SighashType = SighashType(0x03);
+  pub const ALL_ANYONECANPAY: SighashType = SighashType(0x81);
+  pub const NONE_ANYONECANPAY: SighashType = SighashType(0x82);
+  pub const SINGLE_ANYONECANPAY: SighashType = SighashType(0x83);
+  // Taproot only. Same as ALL, but without sighash type byte in signature.
+  pub(super) const DEFAULT: SighashType = SighashType(0x00);
+
+  pub(super) fn check(self) -> Result<Self, TransactionError> {
+    match self.0 & !SIGHASH_ANYONECANPAY {
+      0x01..=0x03 => Ok(self),
+      _ => Err(TransactionError::InvalidSighashType(self.0))
+    }
+  }
+
+  fn is_none(self) -> bool {
+    self.0 & SIGHASH_BASE_MASK == Self::NONE.0
+  }
+
+  fn is_single(self) -> bool {
+    self.0 & SIGHASH_BASE_MASK == Self::SINGLE.0
+  }
+
+  fn is_anyone_can_pay(self) -> bool {
+    self.0 & SIGHASH_ANYONECANPAY != 0
+  }
+}
+
+#[derive(Debug)]
+pub enum TransactionError {
+  UnexpectedEnd,
+  TrailingBytes(usize),
+  InvalidInputIndex(usize),
+  InvalidPrevoutsCount(usize, usize),
+  InvalidSighashType(u8),
+  MissingSingleOutput(usize)
+}
+
+impl fmt::Display for TransactionError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      &TransactionError::UnexpectedEnd => write!(f, "Unexpected end of transaction data"),
+      &TransactionError::TrailingBytes(count) => {
+        write!(f, "Found {} bytes after transaction", count)
+      }
+      &TransactionError::InvalidInputIndex(index) => write!(f, "Invalid input index {}", index),
+      &TransactionError::InvalidPrevoutsCount(count, inputs) => {
+        write!(f, "Invalid previous outputs count {}, expected: {}", count, inputs)
+      }
+      &TransactionError::InvalidSighashType(sighash_type) => {
+        write!(f, "Invalid sighash type {:#04x}", sighash_type)
+      }
+      &TransactionError::MissingSingleOutput(index) => {
+        write!(f, "No output for SIGHASH_SINGLE input {}", index)
+      }
+    }
+  }
+}
+
+impl std::error::Error for TransactionError {}
+
+impl From<TransactionError> for Error {
+  fn from(err: TransactionError) -> Self {
+    Error::InvalidData(Box::new(err))
+  }
+}
+
+// Transaction id is stored in internal byte order, which is reversed in block explorers
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct OutPoint {
+  pub txid: [u8; HASH_SIZE],
+  pub vout: u32
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxIn {
+  pub previous_output: OutPoint,
+  pub script_sig: Vec<u8>,
+  pub sequence: u32,
+  pub witness: Vec<Vec<u8>>
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxOut {
+  pub value: u64,
+  pub script_pubkey: Vec<u8>
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transaction {
+  pub version: i32,
+  pub inputs: Vec<TxIn>,
+  pub outputs: Vec<TxOut>,
+  pub lock_time: u32
+}
+
+// Input, which is signed by the key at the path. Address type is selected by path purpose.
+// Value of previous output is committed by segwit signatures and ignored by P2PKH inputs.
+#[derive(Debug, Clone)]
+pub struct SigningInput {
+  pub index: usize,
+  pub path: DerivationPath,
+  pub value: u64,
+  pub sighash_type: SighashType
+}
+
+impl TxOut {
+  fn serialize(&self, out: &mut Vec<u8>) {
+    out.extend_from_slice(&self.value.to_le_bytes());
+    write_bytes(&self.script_pubkey, out);
+  }
+}
+
+impl OutPoint {
+  fn serialize(&self, out: &mut Vec<u8>) {
+    out.extend_from_slice(&self.txid);
+    out.extend_from_slice(&self.vout.to_le_bytes());
+  }
+}
+
+impl Transaction {
+  // Segwit serialization is used if any input has witness
+  pub fn serialize(&self) -> Vec<u8> {
+    self.serialize_with(self.inputs.iter().any(|input| !input.witness.is_empty()))
+  }
+
+  pub fn txid(&self) -> [u8; HASH_SIZE] {
+    sha256d(&self.serialize_with(false))
+  }
+
+  pub fn parse(data: &[u8]) -> Result<Self, Error> {
+    let mut reader = Reader::new(data);
+    let version = reader.u32()? as i32;
+    let segwit = reader.peek(2)? == [SEGWIT_MARKER, SEGWIT_FLAG];
+    if segwit {
+      reader.take(2)?;
+    }
+    let mut inputs = Vec::new();
+    for _ in 0..reader.compact_size()? {
+      let mut txid = [0u8; HASH_SIZE];
+      txid.copy_from_slice(reader.take(HASH_SIZE)?);
+      inputs.push(TxIn {
+        previous_output: OutPoint { txid, vout: reader.u32()? },
+        script_sig: reader.bytes()?,
+        sequence: reader.u32()?,
+        witness: Vec::new()
+      });
+    }
+    let mut outputs = Vec::new();
+    for _ in 0..reader.compact_size()? {
+      outputs.push(TxOut { value: reader.u64()?, script_pubkey: reader.bytes()? });
+    }
+    if segwit {
+      for input in inputs.iter_mut() {
+        input.witness = reader.witness()?;
+      }
+    }
+    let lock_time = reader.u32()?;
+    reader.finish()?;
+    Ok(Transaction { version, inputs, outputs, lock_time })
+  }
+
+  // Original signature hash of pre-segwit inputs. Script code is the previous output script,
+  // scripts with OP_CODESEPARATOR aren't supported.
+  pub fn legacy_sighash(
+    &self, input: usize, script_code: &[u8], sighash_type: SighashType
+  ) -> Result<[u8; HASH_SIZE], Error> {
+    sighash_type.check()?;
+    self.legacy_sighash_with(input, script_code, sighash_type.0 as u32)
+  }
+
+  // Consensus rules hash any 32-bit hash type: its low byte selects signed parts of transaction
+  // and the whole value is appended to preimage
+  fn legacy_sighash_with(
+    &self, input: usize, script_code: &[u8], hash_type: u32
+  ) -> Result<[u8; HASH_SIZE], Error> {
+    let sighash_type = SighashType(hash_type as u8);
+    if input >= self.inputs.len() {
+      return Err(TransactionError::InvalidInputIndex(input).into());
+    }
+    if sighash_type.is_single() && input >= self.outputs.len() {
+      return Ok(SIGHASH_SINGLE_BUG);
+    }
+    let mut tx = self.clone();
+    for (index, txin) in tx.inputs.iter_mut().enumerate() {
+      txin.script_sig = if index == input { Vec::from(script_code) } else { Vec::new() };
+      txin.witness = Vec::new();
+      if index != input && (sighash_type.is_none() || sighash_type.is_single()) {
+        txin.sequence = 0;
+      }
+    }
+    if sighash_type.is_none() {
+      tx.outputs.clear();
+    } else if sighash_type.is_single() {
+      tx.outputs.truncate(input + 1);
+      for output in tx.outputs[..input].iter_mut() {
+        *output = TxOut { value: u64::MAX, script_pubkey: Vec::new() };
+      }
+    }
+    if sighash_type.is_anyone_can_pay() {
+      tx.inputs = vec![tx.inputs.swap_remove(input)];
+    }
+    let mut preimage = tx.serialize_with(false);
+    preimage.extend_from_slice(&hash_type.to_le_bytes());
+    Ok(sha256d(&preimage))
+  }
+
+  // BIP143 signature hash of segwit v0 input. Value is the amount of previous output.
+  pub fn segwit_v0_sighash(
+    &self, input: usize, script_code: &[u8], value: u64, sighash_type: SighashType
+  ) -> Result<[u8; HASH_SIZE], Error> {
+    sighash_type.check()?;
+    let txin = self.inputs.get(input).ok_or(TransactionError::InvalidInputIndex(input))?;
+    let mut prevouts = Vec::new();
+    let mut sequences = Vec::new();
+    for input in self.inputs.iter() {
+      input.previous_output.serialize(&mut prevouts);
+      sequences.extend_from_slice(&input.sequence.to_le_bytes());
+    }
+    let mut outputs = Vec::new();
+    if !sighash_type.is_single() && !sighash_type.is_none() {
+      self.outputs.iter().for_each(|output| output.serialize(&mut outputs));
+    } else if sighash_type.is_single() && input < self.outputs.len() {
+      self.outputs[input].serialize(&mut outputs);
+    }
+
+    let zero = [0u8; HASH_SIZE];
+    let hash_prevouts = if sighash_type.is_anyone_can_pay() { zero } else { sha256d(&prevouts) };
+    let hash_sequence =
+      if sighash_type.is_anyone_can_pay() || sighash_type.is_single() || sighash_type.is_none() {
+        zero
+      } else {
+        sha256d(&sequences)
+      };
+    let hash_outputs = if outputs.is_empty() { zero } else { sha256d(&outputs) };
+
+    let mut preimage = Vec::new();
+    preimage.extend_from_slice(&self.version.to_le_bytes());
+    preimage.extend_from_slice(&hash_prevouts);
+    preimage.extend_from_slice(&hash_sequence);
+    txin.previous_output.serialize(&mut preimage);
+    write_bytes(script_code, &mut preimage);
+    preimage.extend_from_slice(&value.to_le_bytes());
+    preimage.extend_from_slice(&txin.sequence.to_le_bytes());
+    preimage.extend_from_slice(&hash_outputs);
+    preimage.extend_from_slice(&self.lock_time.to_le_bytes());
+    preimage.extend_from_slice(&(sighash_type.0 as u32).to_le_bytes());
+    Ok(sha256d(&preimage))
+  }
+
+  // BIP341 signature hash of taproot key path spending with SIGHASH_DEFAULT or SIGHASH_ALL.
+  // Previous outputs of all inputs are committed.
+  pub(super) fn taproot_sighash(
+    &self, input: usize, prevouts: &[TxOut], sighash_type: SighashType
+  ) -> Result<[u8; HASH_SIZE], Error> {
+    if sighash_type != SighashType::DEFAULT && sighash_type != SighashType::ALL {
+      return Err(TransactionError::InvalidSighashType(sighash_type.0).into());
+    }
+    if input >= self.inputs.len() {
+      return Err(TransactionError::InvalidInputIndex(input).into());
+    }
+    if prevouts.len() != self.inputs.len() {
+      return Err(TransactionError::InvalidPrevoutsCount(prevouts.len(), self.inputs.len()).into());
+    }
+    let (mut outpoints, mut amounts, mut scripts, mut sequences) =
+      (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    for (input, prevout) in self.inputs.iter().zip(prevouts.iter()) {
+      input.previous_output.serialize(&mut outpoints);
+      amounts.extend_from_slice(&prevout.value.to_le_bytes());
+      write_bytes(&prevout.script_pubkey, &mut scripts);
+      sequences.extend_from_slice(&input.sequence.to_le_bytes());
+    }
+    let mut outputs = Vec::new();
+    self.outputs.iter().for_each(|output| output.serialize(&mut outputs));
+
+    // Epoch 0, then sighash type and transaction data
+    let mut preimage = vec![0x00, sighash_type.0];
+    preimage.extend_from_slice(&self.version.to_le_bytes());
+    preimage.extend_from_slice(&self.lock_time.to_le_bytes());
+    preimage.extend_from_slice(&sha256(&outpoints));
+    preimage.extend_from_slice(&sha256(&amounts));
+    preimage.extend_from_slice(&sha256(&scripts));
+    preimage.extend_from_slice(&sha256(&sequences));
+    preimage.extend_from_slice(&sha256(&outputs));
+    // Key path spending without annex
+    preimage.push(0x00);
+    preimage.extend_from_slice(&(input as u32).to_le_bytes());
+    Ok(tagged_hash("TapSighash", &preimage))
+  }
+
+  // Refuses SIGHASH_SINGLE input without matching output, which must never be signed
+  pub(super) fn check_single_output(
+    &self, input: usize, sighash_type: SighashType
+  ) -> Result<(), TransactionError> {
+    if sighash_type.is_single() && input >= self.outputs.len() {
+      return Err(TransactionError::MissingSingleOutput(input));
+    }
+    Ok(())
+  }
+
+  fn serialize_with(&self, witness: bool) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&self.version.to_le_bytes());
+    if witness {
+      out.extend_from_slice(&[SEGWIT_MARKER, SEGWIT_FLAG]);
+    }
+    write_compact_size(self.inputs.len() as u64, &mut out);
+    for input in self.inputs.iter() {
+      input.previous_output.serialize(&mut out);
+      write_bytes(&input.script_sig, &mut out);
+      out.extend_from_slice(&input.sequence.to_le_bytes());
+    }
+    write_compact_size(self.outputs.len() as u64, &mut out);
+    self.outputs.iter().for_each(|output| output.serialize(&mut out));
+    if witness {
+      self.inputs.iter().for_each(|input| write_witness(&input.witness, &mut out));
+    }
+    out.extend_from_slice(&self.lock_time.to_le_bytes());
+    out
+  }
+}
+
+// Signs P2PKH, P2SH-P2WPKH or P2WPKH input and sets its script sig and witness
+pub(super) fn sign_input(
+  tx: &mut Transaction, key: &XPrv, address_type: AddressType, input: usize, value: u64,
+  sighash_type: SighashType
+) -> Result<(), Error> {
+  tx.check_single_output(input, sighash_type)?;
+  let pub_key = key.public().serialize_compressed();
+  let key_hash = hash160(&pub_key);
+  // Segwit v0 script code of P2WPKH is P2PKH script
+  let script_code = p2pkh_script(&key_hash);
+  let sighash = match address_type {
+    AddressType::P2PKH => tx.legacy_sighash(input, &script_code, sighash_type)?,
+    AddressType::P2SHP2WPKH | AddressType::P2WPKH => {
+      tx.segwit_v0_sighash(input, &script_code, value, sighash_type)?
+    }
+    AddressType::P2TR => return Err(Error::OperationIsNotSupported("taproot input signing"))
+  };
+  let mut signature = key.sign_digest_der(&sighash).map_err(Error::from_secp_sign_error)?;
+  signature.push(sighash_type.0);
+
+  let txin = &mut tx.inputs[input];
+  match address_type {
+    AddressType::P2PKH => {
+      txin.script_sig = Vec::new();
+      write_bytes(&signature, &mut txin.script_sig);
+      write_bytes(&pub_key, &mut txin.script_sig);
+      txin.witness = Vec::new();
+    }
+    _ => {
+      txin.script_sig = Vec::new();
+      if address_type == AddressType::P2SHP2WPKH {
+        write_bytes(&p2wpkh_script(&key_hash), &mut txin.script_sig);
+      }
+      txin.witness = vec![signature, pub_key];
+    }
+  }
+  Ok(())
+}
+
+pub(super) fn write_compact_size(value: u64, out: &mut Vec<u8>) {
+  match value {
+    0..=0xfc => out.push(value as u8),
+    0xfd..=0xffff => {
+      out.push(0xfd);
+      out.extend_from_slice(&(value as u16).to_le_bytes());
+    }
+    0x10000..=0xffff_ffff => {
+      out.push(0xfe);
+      out.extend_from_slice(&(value as u32).to_le_bytes());
+    }
+    _ => {
+      out.push(0xff);
+      out.extend_from_slice(&value.to_le_bytes());
+    }
+  }
+}
+
+pub(super) fn write_bytes(bytes: &[u8], out: &mut Vec<u8>) {
+  write_compact_size(bytes.len() as u64, out);
+  out.extend_from_slice(bytes);
+}
+
+pub(super) fn write_witness(witness: &[Vec<u8>], out: &mut Vec<u8>) {
+  write_compact_size(witness.len() as u64, out);
+  witness.iter().for_each(|item| write_bytes(item, out));
+}
+
+// Little-endian reader of consensus encoded data
+pub(super) struct Reader<'a> {
+  data: &'a [u8],
+  position: usize
+}
+
+impl<'a> Reader<'a> {
+  pub fn new(data: &'a [u8]) -> Self {
+    Self { data, position: 0 }
+  }
+
+  pub fn take(&mut self, length: usize) -> Result<&'a [u8], TransactionError> {
+    let bytes = self.peek(length)?;
+    self.position += length;
+    Ok(bytes)
+  }
+
+  pub fn peek(&self, length: usize) -> Result<&'a [u8], TransactionError> {
+    if self.data.len() - self.position < length {
+      return Err(TransactionError::UnexpectedEnd);
+    }
+    Ok(&self.data[self.position..self.position + length])
+  }
+
+  pub fn u32(&mut self) -> Result<u32, TransactionError> {
+    Ok(self.take(4)?.iter().rev().fold(0u32, |value, &byte| value << 8 | byte as u32))
+  }
+
+  pub fn u64(&mut self) -> Result<u64, TransactionError> {
+    Ok(self.take(8)?.iter().rev().fold(0u64, |value, &byte| value << 8 | byte as u64))
+  }
+
+  pub fn compact_size(&mut self) -> Result<u64, TransactionError> {
+    let size = match self.take(1)?[0] {
+      0xfd => 2,
+      0xfe => 4,
+      0xff => 8,
+      value => return Ok(value as u64)
+    };
+    Ok(self.take(size)?.iter().rev().fold(0u64, |value, &byte| value << 8 | byte as u64))
+  }
+
+  pub fn bytes(&mut self) -> Result<Vec<u8>, TransactionError> {
+    let length = self.compact_size()? as usize;
+    self.take(length).map(Vec::from)
+  }
+
+  pub fn witness(&mut self) -> Result<Vec<Vec<u8>>, TransactionError> {
+    (0..self.compact_size()?).map(|_| self.bytes()).collect()
+  }
+
+  pub fn finish(&self) -> Result<(), TransactionError> {
+    match self.data.len() - self.position {
+      0 => Ok(()),
+      count => Err(TransactionError::TrailingBytes(count))
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::address::{key_hash_script, AddressType};
+  use super::super::fixture::key;
+  use super::super::key::PublicKey;
+  use super::super::key_path::KeyPath;
+  use super::{sign_input, write_bytes, OutPoint, SighashType, SigningInput, Transaction, TxIn};
+  use super::{TxOut, SIGHASH_SINGLE_BUG};
+  use bip39::util::hex;
+  use key_path::KeyPath as IKeyPath;
+  use secp256k1_bip32::hash::{hash160, sha256d};
+  use secp256k1_bip32::{verify_der, XPrv};
+
+  // BIP143 native P2WPKH example
+  const P2WPKH_TX: &str = "0100000002fff7f7881a8099afa6940d42d1e7f6362bec38171ea3edf433541db4e4ad\
+                           969f0000000000eeffffffef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9\
+                           b2b55d57b90ec68a0100000000ffffffff02202cb206000000001976a9148280b37df3\
+                           78db99f66f85c95a783a76ac7a6d5988ac9093510d000000001976a9143bde42dbee7e\
+                           4dbe6a21b2d50ce2f0167faa815988ac11000000";
+  // BIP143 P2SH-P2WPKH example
+  const P2SH_P2WPKH_TX: &str = "0100000001db6b1b20aa0fd7b23880be2ecbd4a98130974cf4748fb66092ac4d3c\
+                                eb1a54770100000000feffffff02b8b4eb0b000000001976a914a457b684d7f0d5\
+                                39a46a45bbc043f35b59d0d96388ac0008af2f000000001976a914fd270b1ee6ab\
+                                caea97fea7ad0402e8bd8ad6d77c88ac92040000";
+  // Signed BIP143 examples. Keys are published and ECDSA nonces are deterministic (RFC6979).
+  const P2WPKH_SIGNED_TX: &str = "01000000000102fff7f7881a8099afa6940d42d1e7f6362bec38171ea3edf43354\
+                                  1db4e4ad969f00000000494830450221008b9d1dc26ba6a9cb62127b02742fa9d7\
+                                  54cd3bebf337f7a55d114c8e5cdd30be022040529b194ba3f9281a99f2b1c0a19c\
+                                  0489bc22ede944ccf4ecbab4cc618ef3ed01eeffffffef51e1b804cc89d182d279\
+                                  655c3aa89e815b1b309fe287d9b2b55d57b90ec68a0100000000ffffffff02202c\
+                                  b206000000001976a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac90\
+                                  93510d000000001976a9143bde42dbee7e4dbe6a21b2d50ce2f0167faa815988ac\
+                                  000247304402203609e17b84f6a7d30c80bfa610b5b4542f32a8a0d5447a12fb13\
+                                  66d7f01cc44a0220573a954c4518331561406f90300e8f3358f51928d43c212a8c\
+                                  aed02de67eebee0121025476c2e83188368da1ff3e292e7acafcdb3566bb0ad253\
+                                  f62fc70f07aeee635711000000";
+  const P2SH_P2WPKH_SIGNED_TX: &str =
+    "01000000000101db6b1b20aa0fd7b23880be2ecbd4a98130974cf4748fb6\
+                                       6092ac4d3ceb1a5477010000001716001479091972186c449eb1ded22b78\
+                                       e40d009bdf0089feffffff02b8b4eb0b000000001976a914a457b684d7f0\
+                                       d539a46a45bbc043f35b59d0d96388ac0008af2f000000001976a914fd27\
+                                       0b1ee6abcaea97fea7ad0402e8bd8ad6d77c88ac02473044022047ac8e87\
+                                       8352d3ebbde1c94ce3a10d057c24175747116f8288e5d794d12d482f0220\
+                                       217f36a485cae903c713331d877c1f64677e3622ad4010726870540656fe\
+                                       9dcb012103ad1d8e89212f0b92c74d23bb710c00662ad1470198ac48c43f\
+                                       7d6f93a2a2687392040000";
+
+  // Bitcoin Core sighash.json cases: transaction, script code, input, hash type and sighash in
+  // reversed byte order
+  const SIGHASH_VECTORS: [(&str, &str, usize, i32, &str); 5] = [
+    (
+      "907c2bc503ade11cc3b04eb2918b6f547b0630ab569273824748c87ea14b0696526c66ba740200000004ab65ab\
+       abfd1f9bdd4ef073c7afc4ae00da8a66f429c917a0081ad1e1dabce28d373eab81d8628de802000000096aab52\
+       53ab52000052ad042b5f25efb33beec9f3364e8a9139e8439d9d7e26529c3c30b6c3fd89f8684cfd68ea020000\
+       0009ab53526500636a52ab599ac2fe02a526ed040000000008535300516352515164370e010000000003006300\
+       ab2ec229",
+      "",
+      2,
+      1864164639,
+      "31af167a6cf3f9d5f6875caa4d31704ceb0eba078d132b78dab52c3b8997317e"
+    ),
+    (
+      "73107cbd025c22ebc8c3e0a47b2a760739216a528de8d4dab5d45cbeb3051cebae73b01ca10200000007ab6353\
+       656a636affffffffe26816dffc670841e6a6c8c61c586da401df1261a330a6c6b3dd9f9a0789bc9e0000000008\
+       00ac6552ac6aac51ffffffff0174a8f0010000000004ac52515100000000",
+      "5163ac63635151ac",
+      1,
+      1190874345,
+      "06e328de263a87b09beabe222a21627a6ea5c7f560030da31610c4611f4a46bc"
+    ),
+    (
+      "50818f4c01b464538b1e7e7f5ae4ed96ad23c68c830e78da9a845bc19b5c3b0b20bb82e5e9030000000763526a\
+       63655352ffffffff023b3f9c040000000008630051516a6a5163a83caf01000000000553ab65510000000000",
+      "6aac",
+      0,
+      946795545,
+      "746306f322de2b4b58ffe7faae83f6a72433c22f88062cdde881d4dd8a5a4e2d"
+    ),
+    (
+      "d3b7421e011f4de0f1cea9ba7458bf3486bee722519efab711a963fa8c100970cf7488b7bb0200000003525352\
+       dcd61b300148be5d05000000000000000000",
+      "535251536aac536a",
+      0,
+      -1960128125,
+      "29aa6d2d752d3310eba20442770ad345b7f6a35f96161ede5f07b33e92053e2a"
+    ),
+    (
+      "04bac8c5033460235919a9c63c42b2db884c7c8f2ed8fcd69ff683a0a2cccd9796346a04050200000003655351\
+       fcad3a2c5a7cbadeb4ec7acc9836c3f5c3e776e5c566220f7f965cf194f8ef98efb5e3530200000007526a0065\
+       52526526a2f55ba5f69699ece76692552b399ba908301907c5763d28a15b08581b23179cb01eac030000000753\
+       63ab6a516351073942c2025aa98a05000000000765006aabac65abd7ffa6030000000004516a655200000000",
+      "53ac6365ac526a",
+      1,
+      764174870,
+      "bf5fdc314ded2372a0ad078568d76c5064bf2affbde0764c335009e56634481b"
+    )
+  ];
+
+  fn transaction() -> Transaction {
+    let input = |vout| TxIn {
+      previous_output: OutPoint { txid: [vout as u8; 32], vout },
+      script_sig: Vec::new(),
+      sequence: 0xffff_fffd,
+      witness: Vec::new()
+    };
+    let output = |value| TxOut { value, script_pubkey: vec![0x6a] };
+    Transaction {
+      version: 2,
+      inputs: vec![input(0), input(1), input(2)],
+      outputs: vec![output(1000), output(2000)],
+      lock_time: 0
+    }
+  }
+
+  #[test]
+  fn bip143_vectors() {
+    let tx = Transaction::parse(&hex::decode(P2WPKH_TX).unwrap()).unwrap();
+    assert_eq!(hex::encode(&tx.serialize()), P2WPKH_TX);
+    let script_code = hex::decode("76a9141d0f172a0ecb48aee1be1f2687d2963ae33f71a188ac").unwrap();
+    assert_eq!(
+      hex::encode(&tx.segwit_v0_sighash(1, &script_code, 600000000, SighashType::ALL).unwrap()),
+      "c37af31116d1b27caf68aae9e3ac82f1477929014d5b917657d0eb49478cb670"
+    );
+
+    let tx = Transaction::parse(&hex::decode(P2SH_P2WPKH_TX).unwrap()).unwrap();
+    let script_code = hex::decode("76a91479091972186c449eb1ded22b78e40d009bdf008988ac").unwrap();
+    assert_eq!(
+      hex::encode(&tx.segwit_v0_sighash(0, &script_code, 1000000000, SighashType::ALL).unwrap()),
+      "64f3b0f4dd2bb3aa1ce8566d220cc74dda9df97d8490cc81d89d735c92e59fb6"
+    );
+  }
+
+  // Key without chain code, which signs with published private key
+  fn secret_key(secret: &str) -> XPrv {
+    let mut data = vec![0u8; 41];
+    data.extend_from_slice(&hex::decode(secret).unwrap());
+    let checksum = sha256d(&data);
+    data.extend_from_slice(&checksum[..4]);
+    XPrv::from_data(&data).unwrap()
+  }
+
+  fn reversed(hash: [u8; 32]) -> String {
+    hex::encode(&hash.iter().rev().cloned().collect::<Vec<u8>>())
+  }
+
+  #[test]
+  fn bip143_signed_vectors() {
+    // Input 0 spends P2PK output, which is signed with legacy sighash
+    let mut tx = Transaction::parse(&hex::decode(P2WPKH_TX).unwrap()).unwrap();
+    let key = secret_key("bbc27228ddcb9209d7fd6f36b02f7dfa6252af40bb2f1cbc7a557da8027ff866");
+    let script_code =
+      hex::decode("2103c9f4836b9a4f77fc0d81f7bcb01b7f1b35916864b9476c241ce9fc198bd25432ac")
+        .unwrap();
+    let sighash = tx.legacy_sighash(0, &script_code, SighashType::ALL).unwrap();
+    let mut signature = key.sign_digest_der(&sighash).unwrap();
+    signature.push(SighashType::ALL.0);
+    tx.inputs[0].script_sig = Vec::new();
+    write_bytes(&signature, &mut tx.inputs[0].script_sig);
+    let key = secret_key("619c335025c7f4012e556c2a58b2506e30b8511b53ade95ea316fd8c3286feb9");
+    sign_input(&mut tx, &key, AddressType::P2WPKH, 1, 600000000, SighashType::ALL).unwrap();
+    assert_eq!(hex::encode(&tx.serialize()), P2WPKH_SIGNED_TX);
+
+    let mut tx = Transaction::parse(&hex::decode(P2SH_P2WPKH_TX).unwrap()).unwrap();
+    let key = secret_key("eb696a065ef48a2192da5b28b694f87544b30fae8327c4510137a922f32c6dcf");
+    sign_input(&mut tx, &key, AddressType::P2SHP2WPKH, 0, 1000000000, SighashType::ALL).unwrap();
+    assert_eq!(hex::encode(&tx.serialize()), P2SH_P2WPKH_SIGNED_TX);
+    assert_eq!(Transaction::parse(&tx.serialize()).unwrap(), tx);
+  }
+
+  #[test]
+  fn sighash_vectors() {
+    for &(tx, script_code, input, hash_type, sighash) in SIGHASH_VECTORS.iter() {
+      let tx = Transaction::parse(&hex::decode(tx).unwrap()).unwrap();
+      let script_code = hex::decode(script_code).unwrap();
+      let hash = tx.legacy_sighash_with(input, &script_code, hash_type as u32).unwrap();
+      assert_eq!(reversed(hash), sighash);
+    }
+
+    // sighash.json has no NONE and plain SINGLE cases without unsupported OP_CODESEPARATOR. These
+    // are computed by independent implementation, which reproduces the cases above.
+    let tx = Transaction::parse(&hex::decode(SIGHASH_VECTORS[4].0).unwrap()).unwrap();
+    let script_code = hex::decode(SIGHASH_VECTORS[4].1).unwrap();
+    let sighash =
+      |input, sighash_type| reversed(tx.legacy_sighash(input, &script_code, sighash_type).unwrap());
+    assert_eq!(
+      sighash(1, SighashType::NONE),
+      "3e14b22ee905635cf68b8d9e90c12abf24f611505ca6896d12fe49947d39007e"
+    );
+    assert_eq!(
+      sighash(1, SighashType::SINGLE),
+      "bf0b0b88610f882cd728f4887b00a6a8409169bacd4d406bb1a7ef9cf9275d94"
+    );
+    assert_eq!(
+      sighash(0, SighashType::NONE_ANYONECANPAY),
+      "ab050646aec5dd6ffc7c5dca2f576d463837d40aa3456f7409b9b1a33e6c7ab8"
+    );
+  }
+
+  #[test]
+  fn sighash_flags() {
+    let tx = transaction();
+    let mut changed = tx.clone();
+    changed.inputs[2].sequence = 0;
+    changed.outputs[1].value = 3000;
+
+    let legacy = |tx: &Transaction, input, flag| tx.legacy_sighash(input, &[0x51], flag).unwrap();
+    let segwit =
+      |tx: &Transaction, input, flag| tx.segwit_v0_sighash(input, &[0x51], 5000, flag).unwrap();
+    for sighash in [legacy, segwit].iter() {
+      assert_ne!(sighash(&tx, 0, SighashType::ALL), sighash(&changed, 0, SighashType::ALL));
+      assert_eq!(sighash(&tx, 0, SighashType::NONE), sighash(&changed, 0, SighashType::NONE));
+      assert_eq!(sighash(&tx, 0, SighashType::SINGLE), sighash(&changed, 0, SighashType::SINGLE));
+      assert_ne!(sighash(&tx, 1, SighashType::SINGLE), sighash(&changed, 1, SighashType::SINGLE));
+      assert_ne!(sighash(&tx, 0, SighashType::ALL), sighash(&tx, 0, SighashType::ALL_ANYONECANPAY));
+
+      // Other inputs aren't committed with ANYONECANPAY
+      let mut removed = tx.clone();
+      removed.inputs.truncate(1);
+      assert_eq!(
+        sighash(&tx, 0, SighashType::NONE_ANYONECANPAY),
+        sighash(&removed, 0, SighashType::NONE_ANYONECANPAY)
+      );
+    }
+    assert_eq!(legacy(&tx, 2, SighashType::SINGLE), SIGHASH_SINGLE_BUG);
+    assert_ne!(segwit(&tx, 2, SighashType::SINGLE), SIGHASH_SINGLE_BUG);
+
+    assert!(tx.legacy_sighash(3, &[], SighashType::ALL).is_err());
+    assert!(tx.segwit_v0_sighash(0, &[], 0, SighashType(0x04)).is_err());
+  }
+
+  #[test]
+  fn sign_inputs() {
+    let key = key();
+    let tx = transaction();
+    let paths = [
+      KeyPath::bip44(false, 0, 0, 0).unwrap().derivation_path(),
+      KeyPath::bip49(false, 0, 0, 1).unwrap().derivation_path(),
+      KeyPath::bip84(false, 0, 1, 0).unwrap().derivation_path()
+    ];
+    let flags = [SighashType::ALL, SighashType::SINGLE_ANYONECANPAY, SighashType::NONE];
+    let inputs: Vec<SigningInput> = (0..3)
+      .map(|index| SigningInput {
+        index,
+        path: paths[index].clone(),
+        value: 10000,
+        sighash_type: flags[index]
+      })
+      .collect();
+    let signed = key.sign_transaction(&tx, &inputs).unwrap();
+    assert_ne!(signed.txid(), tx.txid());
+    assert_eq!(Transaction::parse(&signed.serialize()).unwrap(), signed);
+
+    for (index, path) in paths.iter().enumerate() {
+      let pub_key = key.derive_public(path).unwrap().serialize_compressed();
+      let script_code = key_hash_script(&hash160(&pub_key), AddressType::P2PKH);
+      let input = &signed.inputs[index];
+      let (signature, sighash) = if index == 0 {
+        assert!(input.witness.is_empty());
+        let signature = input.script_sig[1..input.script_sig[0] as usize + 1].to_vec();
+        assert!(input.script_sig.ends_with(&pub_key));
+        (signature, signed.legacy_sighash(index, &script_code, flags[index]).unwrap())
+      } else {
+        assert_eq!(input.witness[1], pub_key);
+        assert_eq!(input.script_sig.is_empty(), index == 2);
+        let sighash = signed.segwit_v0_sighash(index, &script_code, 10000, flags[index]);
+        (input.witness[0].clone(), sighash.unwrap())
+      };
+      assert_eq!(signature.last(), Some(&flags[index].0));
+      assert!(verify_der(&sighash, &pub_key, &signature[..signature.len() - 1]).unwrap());
+    }
+
+    let mut invalid = inputs.clone();
+    invalid[0].path = KeyPath::bip86(false, 0, 0, 0).unwrap().derivation_path();
+    assert!(key.sign_transaction(&tx, &invalid).is_err());
+    invalid = inputs.clone();
+    invalid[0].index = 3;
+    assert!(key.sign_transaction(&tx, &invalid).is_err());
+
+    // SIGHASH_SINGLE inputs without matching output are refused for any address type
+    for path in paths.iter() {
+      invalid = vec![SigningInput {
+        index: 2,
+        path: path.clone(),
+        value: 10000,
+        sighash_type: SighashType::SINGLE
+      }];
+      assert!(key.sign_transaction(&tx, &invalid).is_err());
+      invalid[0].sighash_type = SighashType::SINGLE_ANYONECANPAY;
+      assert!(key.sign_transaction(&tx, &invalid).is_err());
+    }
+  }
+}
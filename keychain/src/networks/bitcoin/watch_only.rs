@@ -0,0 +1,137 @@
+use super::address::AddressType;
+use super::key::{account_version, check_account_path, check_address_path, PublicKey};
+use super::network_type::NetworkType;
+use derivation_path::DerivationPath;
+use key::{Error, Key as IKey};
+use key_path::{Error as KPError, ACCOUNT_DEPTH};
+use network::Network;
+use std::any::Any;
+
+use secp256k1_bip32::hash::sha256d;
+use secp256k1_bip32::{KeyError as SecpKeyError, Version, XPub};
+
+pub struct WatchOnlyKey {
+  xpub: XPub,
+  version: Version,
+  account: DerivationPath
+}
+
+impl WatchOnlyKey {
+  // Accepts SLIP-132 version matching the path purpose. Plain xpub and tpub are accepted for
+  // any purpose, as a lot of wallets export them regardless of the address type.
+  pub fn from_account_xpub(path: &DerivationPath, xpub: &str) -> Result<Self, Error> {
+    let expected = account_version(path)?;
+    let network = NetworkType::from_coin(path.path()[1])?;
+    let (xpub, version) = XPub::from_base58(xpub)?;
+    if version != expected && version != AddressType::P2PKH.xpub_version(network) {
+      return Err(SecpKeyError::UnknownVersion(version.public()).into());
+    }
+    if xpub.depth() as usize != ACCOUNT_DEPTH || xpub.index() != path.path()[2] {
+      return Err(KPError::InvalidAccount(path.path()[2]).into());
+    }
+    Ok(Self { xpub, version, account: path.prefix(ACCOUNT_DEPTH) })
+  }
+
+  fn check_account(&self, path: &DerivationPath) -> Result<(), Error> {
+    check_account_path(path)?;
+    let (indexes, account) = (path.path(), self.account.path());
+    if indexes[0] != account[0] {
+      return Err(KPError::InvalidPurpose(indexes[0], account[0]).into());
+    }
+    if indexes[1] != account[1] {
+      return Err(KPError::InvalidCoin(indexes[1], account[1]).into());
+    }
+    if indexes[2] != account[2] {
+      return Err(KPError::InvalidAccount(indexes[2]).into());
+    }
+    Ok(())
+  }
+}
+
+impl PublicKey for WatchOnlyKey {
+  fn derive_public(&self, path: &DerivationPath) -> Result<XPub, Error> {
+    check_address_path(path)?;
+    self.check_account(path)?;
+    self.xpub.derive_path(&path.path()[ACCOUNT_DEPTH..]).map_err(|err| err.into())
+  }
+}
+
+impl IKey for WatchOnlyKey {
+  fn network(&self) -> Network {
+    Network::BITCOIN
+  }
+
+  fn pub_key(&self, path: &DerivationPath) -> Result<Vec<u8>, Error> {
+    self.derive_public(path).map(|pk| pk.serialize())
+  }
+
+  fn address(&self, path: &DerivationPath) -> Result<String, Error> {
+    check_address_path(path)?;
+    self.network_address(NetworkType::from_coin(path.path()[1])?, path)
+  }
+
+  fn account_xpub(&self, path: &DerivationPath) -> Result<String, Error> {
+    self.check_account(path).map(|_| self.xpub.to_base58(self.version))
+  }
+
+  fn sign(&self, _: &[u8], _: &DerivationPath) -> Result<Vec<u8>, Error> {
+    Err(Error::PrivateKeyIsNotAvailable)
+  }
+
+  fn verify(&self, data: &[u8], signature: &[u8], path: &DerivationPath) -> Result<bool, Error> {
+    self.verify_hash(&sha256d(data), signature, path)
+  }
+
+  fn as_any(&self) -> Option<&dyn Any> {
+    Some(self)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::fixture::key;
+  use super::WatchOnlyKey;
+  use derivation_path::DerivationPath;
+  use key::{Error, Key as IKey};
+
+  fn path(path: &str) -> DerivationPath {
+    path.parse().unwrap()
+  }
+
+  #[test]
+  fn watch_only_matches_full_key() {
+    let key = key();
+    let account = path("m/84'/0'/0'");
+    let xpub = key.account_xpub(&account).unwrap();
+    let watch = WatchOnlyKey::from_account_xpub(&account, &xpub).unwrap();
+
+    let child = path("m/84'/0'/0'/1/7");
+    assert_eq!(watch.pub_key(&child).unwrap(), key.pub_key(&child).unwrap());
+    assert_eq!(
+      watch.address(&path("m/84'/0'/0'/0/0")).unwrap(),
+      "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"
+    );
+    assert_eq!(watch.account_xpub(&child).unwrap(), xpub);
+    assert!(watch.pub_key(&account).is_err());
+
+    let signature = key.sign(b"message", &child).unwrap();
+    assert!(watch.verify(b"message", &signature, &child).unwrap());
+    match watch.sign(b"message", &child) {
+      Err(Error::PrivateKeyIsNotAvailable) => {}
+      _ => panic!("watch-only key should not sign")
+    }
+  }
+
+  #[test]
+  fn watch_only_rejects_other_accounts() {
+    let key = key();
+    let account = path("m/84'/0'/0'");
+    let xpub = key.account_xpub(&account).unwrap();
+    let watch = WatchOnlyKey::from_account_xpub(&account, &xpub).unwrap();
+
+    assert!(watch.pub_key(&path("m/84'/0'/1'/0/0")).is_err());
+    assert!(watch.pub_key(&path("m/49'/0'/0'/0/0")).is_err());
+    assert!(WatchOnlyKey::from_account_xpub(&path("m/84'/0'/1'/0/0"), &xpub).is_err());
+    assert!(WatchOnlyKey::from_account_xpub(&path("m/49'/0'/0'/0/0"), &xpub).is_err());
+  }
+}
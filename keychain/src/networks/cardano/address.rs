@@ -0,0 +1,235 @@
+use super::key::check_address_path;
+use super::key_path::{Role, CIP1852_PURPOSE};
+use bech32::{self, FromBase32, ToBase32, Variant};
+use cryptoxide::blake2b::Blake2b;
+use derivation_path::DerivationPath;
+use ed25519_bip32::XPUB_SIZE;
+use key::Error;
+use key_path::Error as KPError;
+
+pub const KEY_HASH_SIZE: usize = 28;
+
+// ed25519 public key. Extended keys of XPUB_SIZE are accepted, chain code is ignored.
+const PUBLIC_KEY_SIZE: usize = 32;
+
+// CIP-19 header types for key hash credentials
+const BASE_HEADER: u8 = 0b0000;
+const POINTER_HEADER: u8 = 0b0100;
+const ENTERPRISE_HEADER: u8 = 0b0110;
+const REWARD_HEADER: u8 = 0b1110;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NetworkId {
+  Mainnet,
+  Preprod,
+  Preview
+}
+
+impl NetworkId {
+  fn tag(&self) -> u8 {
+    match self {
+      &NetworkId::Mainnet => 1,
+      &NetworkId::Preprod | &NetworkId::Preview => 0
+    }
+  }
+
+  fn address_hrp(&self) -> &'static str {
+    match self {
+      &NetworkId::Mainnet => "addr",
+      &NetworkId::Preprod | &NetworkId::Preview => "addr_test"
+    }
+  }
+
+  // Byron addresses of test networks carry protocol magic attribute
+  pub(super) fn protocol_magic(&self) -> Option<u32> {
+    match self {
+      &NetworkId::Mainnet => None,
+      &NetworkId::Preprod => Some(1),
+      &NetworkId::Preview => Some(2)
+    }
+  }
+
+  fn reward_hrp(&self) -> &'static str {
+    match self {
+      &NetworkId::Mainnet => "stake",
+      &NetworkId::Preprod | &NetworkId::Preview => "stake_test"
+    }
+  }
+}
+
+// Location of stake key registration certificate on chain
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Pointer {
+  pub slot: u64,
+  pub tx_index: u64,
+  pub cert_index: u64
+}
+
+impl Pointer {
+  fn encode(&self, out: &mut Vec<u8>) {
+    encode_natural(self.slot, out);
+    encode_natural(self.tx_index, out);
+    encode_natural(self.cert_index, out);
+  }
+}
+
+// Blake2b-224 hash of ed25519 public key
+pub fn key_hash(pub_key: &[u8]) -> Result<[u8; KEY_HASH_SIZE], Error> {
+  if pub_key.len() != PUBLIC_KEY_SIZE && pub_key.len() != XPUB_SIZE {
+    return Err(Error::InvalidKeySize(pub_key.len(), PUBLIC_KEY_SIZE));
+  }
+  let mut hash = [0u8; KEY_HASH_SIZE];
+  Blake2b::blake2b(&mut hash, &pub_key[..PUBLIC_KEY_SIZE], &[]);
+  Ok(hash)
+}
+
+pub fn base_address(
+  payment_key: &[u8], stake_key: &[u8], network: NetworkId
+) -> Result<String, Error> {
+  let mut payload = Vec::from(&key_hash(payment_key)?[..]);
+  payload.extend_from_slice(&key_hash(stake_key)?);
+  encode(network.address_hrp(), BASE_HEADER, network, &payload)
+}
+
+pub fn enterprise_address(payment_key: &[u8], network: NetworkId) -> Result<String, Error> {
+  encode(network.address_hrp(), ENTERPRISE_HEADER, network, &key_hash(payment_key)?)
+}
+
+pub fn pointer_address(
+  payment_key: &[u8], pointer: &Pointer, network: NetworkId
+) -> Result<String, Error> {
+  let mut payload = Vec::from(&key_hash(payment_key)?[..]);
+  pointer.encode(&mut payload);
+  encode(network.address_hrp(), POINTER_HEADER, network, &payload)
+}
+
+pub fn reward_address(stake_key: &[u8], network: NetworkId) -> Result<String, Error> {
+  encode(network.reward_hrp(), REWARD_HEADER, network, &key_hash(stake_key)?)
+}
+
+// Raw bytes of bech32 Shelley address
+pub(super) fn address_bytes(address: &str) -> Result<Vec<u8>, Error> {
+  let (_, data, _) = bech32::decode(address).map_err(|err| Error::InvalidData(Box::new(err)))?;
+  Vec::<u8>::from_base32(&data).map_err(|err| Error::InvalidData(Box::new(err)))
+}
+
+// Payment keys are CIP-1852 external or internal chain keys
+pub(super) fn check_payment_path(path: &DerivationPath) -> Result<(), Error> {
+  check_shelley_path(path)?;
+  let role = path.path()[3];
+  if role != Role::External as u32 && role != Role::Internal as u32 {
+    return Err(KPError::InvalidChange(role).into());
+  }
+  Ok(())
+}
+
+pub(super) fn check_stake_path(path: &DerivationPath) -> Result<(), Error> {
+  check_shelley_path(path)?;
+  let role = path.path()[3];
+  if role != Role::Staking as u32 {
+    return Err(KPError::InvalidChange(role).into());
+  }
+  Ok(())
+}
+
+fn check_shelley_path(path: &DerivationPath) -> Result<(), Error> {
+  check_address_path(path)?;
+  if path.path()[0] != CIP1852_PURPOSE {
+    return Err(KPError::InvalidPurpose(path.path()[0], CIP1852_PURPOSE).into());
+  }
+  Ok(())
+}
+
+fn encode(hrp: &str, header: u8, network: NetworkId, payload: &[u8]) -> Result<String, Error> {
+  let mut data = Vec::with_capacity(payload.len() + 1);
+  data.push(header << 4 | network.tag());
+  data.extend_from_slice(payload);
+  bech32::encode(hrp, data.to_base32(), Variant::Bech32)
+    .map_err(|err| Error::InvalidKeyData(Box::new(err)))
+}
+
+// Variable length big-endian encoding, 7 bits per byte with continuation flag
+fn encode_natural(value: u64, out: &mut Vec<u8>) {
+  let mut bytes = vec![(value & 0x7f) as u8];
+  let mut value = value >> 7;
+  while value > 0 {
+    bytes.push((value & 0x7f) as u8 | 0x80);
+    value >>= 7;
+  }
+  out.extend(bytes.iter().rev());
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    base_address, enterprise_address, key_hash, pointer_address, reward_address, NetworkId, Pointer
+  };
+  use bech32::{self, FromBase32};
+
+  // CIP-19 test vectors
+  const PAYMENT_KEY: &str = "addr_vk1w0l2sr2zgfm26ztc6nl9xy8ghsk5sh6ldwemlpmp9xylzy4dtf7st80zhd";
+  const STAKE_KEY: &str = "stake_vk1px4j0r2fk7ux5p23shz8f3y5y2qam7s954rgf3lg5merqcj6aetsft99wu";
+  const POINTER: Pointer = Pointer { slot: 2498243, tx_index: 27, cert_index: 3 };
+
+  fn key(bech: &str) -> Vec<u8> {
+    Vec::<u8>::from_base32(&bech32::decode(bech).unwrap().1).unwrap()
+  }
+
+  #[test]
+  fn mainnet() {
+    let (payment, stake) = (key(PAYMENT_KEY), key(STAKE_KEY));
+    assert_eq!(
+      base_address(&payment, &stake, NetworkId::Mainnet).unwrap(),
+      "addr1qx2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzer3n0d3vllmyqwsx5wktcd8cc3sq835lu7drv2xw\
+       l2wywfgse35a3x"
+    );
+    assert_eq!(
+      enterprise_address(&payment, NetworkId::Mainnet).unwrap(),
+      "addr1vx2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzers66hrl8"
+    );
+    assert_eq!(
+      pointer_address(&payment, &POINTER, NetworkId::Mainnet).unwrap(),
+      "addr1gx2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzer5pnz75xxcrzqf96k"
+    );
+    assert_eq!(
+      reward_address(&stake, NetworkId::Mainnet).unwrap(),
+      "stake1uyehkck0lajq8gr28t9uxnuvgcqrc6070x3k9r8048z8y5gh6ffgw"
+    );
+  }
+
+  #[test]
+  fn testnet() {
+    let (payment, stake) = (key(PAYMENT_KEY), key(STAKE_KEY));
+    for network in [NetworkId::Preprod, NetworkId::Preview].iter() {
+      assert_eq!(
+        base_address(&payment, &stake, *network).unwrap(),
+        "addr_test1qz2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzer3n0d3vllmyqwsx5wktcd8cc3sq835lu7d\
+         rv2xwl2wywfgs68faae"
+      );
+      assert_eq!(
+        enterprise_address(&payment, *network).unwrap(),
+        "addr_test1vz2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzerspjrlsz"
+      );
+      assert_eq!(
+        pointer_address(&payment, &POINTER, *network).unwrap(),
+        "addr_test1gz2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzer5pnz75xxcrdw5vky"
+      );
+      assert_eq!(
+        reward_address(&stake, *network).unwrap(),
+        "stake_test1uqehkck0lajq8gr28t9uxnuvgcqrc6070x3k9r8048z8y5gssrtvn"
+      );
+    }
+    assert!(enterprise_address(&payment[..31], NetworkId::Preview).is_err());
+  }
+
+  #[test]
+  fn key_sizes() {
+    let payment = key(PAYMENT_KEY);
+    let extended = [&payment[..], &[7u8; 32][..]].concat();
+    assert_eq!(key_hash(&extended).unwrap(), key_hash(&payment).unwrap());
+    for size in [0, 31, 33, 63, 65].iter() {
+      let bytes = [&extended[..], &[7u8; 1][..]].concat();
+      assert!(key_hash(&bytes[..*size]).is_err());
+    }
+  }
+}
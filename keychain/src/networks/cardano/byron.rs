@@ -0,0 +1,329 @@
+use super::address::NetworkId;
+use super::cbor::{self, Decoder};
+use super::key::check_address_path;
+use bs58;
+use cryptoxide::blake2b::Blake2b;
+use cryptoxide::chacha20poly1305::ChaCha20Poly1305;
+use cryptoxide::digest::Digest;
+use cryptoxide::hmac::Hmac;
+use cryptoxide::pbkdf2::pbkdf2;
+use cryptoxide::sha2::Sha512;
+use cryptoxide::sha3::Sha3;
+use derivation_path::DerivationPath;
+use ed25519_bip32::XPUB_SIZE;
+use key::Error;
+use key_path::{Error as KPError, BIP44_PURPOSE};
+use std::fmt;
+
+// Daedalus wallets use two hardened random indexes: account and address
+pub(super) const RANDOM_INDEX_DEPTH: usize = 2;
+
+const ADDRESS_ROOT_SIZE: usize = 28;
+const PUBKEY_ADDRESS_TYPE: u64 = 0;
+const PUBKEY_SPENDING_DATA: u64 = 0;
+const DERIVATION_PATH_ATTRIBUTE: u64 = 1;
+const PROTOCOL_MAGIC_ATTRIBUTE: u64 = 2;
+const EMBEDDED_CBOR_TAG: u64 = 24;
+
+const HD_PAYLOAD_SALT: &[u8] = b"address-hashing";
+const HD_PAYLOAD_NONCE: &[u8] = b"serokellfore";
+const HD_PAYLOAD_ITERATIONS: u32 = 500;
+const HD_PAYLOAD_KEY_SIZE: usize = 32;
+const HD_PAYLOAD_TAG_SIZE: usize = 16;
+
+#[derive(Debug)]
+pub enum AddressError {
+  InvalidChecksum(u32, u32),
+  UnsupportedType(u64)
+}
+
+impl fmt::Display for AddressError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      &AddressError::InvalidChecksum(checksum, expected) => {
+        write!(f, "Invalid address checksum {}, expected: {}", checksum, expected)
+      }
+      &AddressError::UnsupportedType(addr_type) => {
+        write!(f, "Unsupported Byron address type {}", addr_type)
+      }
+    }
+  }
+}
+
+impl std::error::Error for AddressError {}
+
+impl From<AddressError> for Error {
+  fn from(err: AddressError) -> Self {
+    Error::InvalidData(Box::new(err))
+  }
+}
+
+// Bootstrap address without derivation path attribute. Icarus, Yoroi and hardware wallets.
+pub fn icarus_address(xpub: &[u8], network: NetworkId) -> Result<String, Error> {
+  encode(xpub, &icarus_attributes(network))
+}
+
+pub(super) fn icarus_attributes(network: NetworkId) -> Vec<u8> {
+  attributes(None, network)
+}
+
+// Daedalus addresses carry derivation path encrypted by the root public key
+pub(super) fn daedalus_attributes(root_xpub: &[u8], path: &[u32], network: NetworkId) -> Vec<u8> {
+  attributes(Some(&hd_payload_encrypt(root_xpub, path)), network)
+}
+
+pub(super) fn check_icarus_path(path: &DerivationPath) -> Result<(), Error> {
+  check_address_path(path)?;
+  if path.path()[0] != BIP44_PURPOSE {
+    return Err(KPError::InvalidPurpose(path.path()[0], BIP44_PURPOSE).into());
+  }
+  Ok(())
+}
+
+pub(super) fn check_random_index_path(path: &DerivationPath) -> Result<(), Error> {
+  if path.depth() != RANDOM_INDEX_DEPTH {
+    return Err(KPError::InvalidDepth(path.depth()).into());
+  }
+  match path.path().iter().position(|&index| !DerivationPath::is_hardened(index)) {
+    Some(position) => Err(KPError::InvalidIndex(position, path.path()[position]).into()),
+    None => Ok(())
+  }
+}
+
+pub(super) struct ByronAddress {
+  root: Vec<u8>,
+  attributes: Vec<u8>,
+  hd_payload: Option<Vec<u8>>
+}
+
+impl ByronAddress {
+  pub(super) fn from_base58(address: &str) -> Result<Self, Error> {
+    let data = bs58::decode(address).into_vec().map_err(|err| Error::InvalidData(Box::new(err)))?;
+    let mut decoder = Decoder::new(&data);
+    decoder.array()?;
+    decoder.tag()?;
+    let content = decoder.bytes()?;
+    let checksum = decoder.uint()? as u32;
+    decoder.finish()?;
+    if crc32(content) != checksum {
+      return Err(AddressError::InvalidChecksum(checksum, crc32(content)).into());
+    }
+
+    let mut decoder = Decoder::new(content);
+    decoder.array()?;
+    let root = decoder.bytes()?;
+    let attributes = decoder.item()?;
+    let addr_type = decoder.uint()?;
+    if addr_type != PUBKEY_ADDRESS_TYPE {
+      return Err(AddressError::UnsupportedType(addr_type).into());
+    }
+
+    let mut hd_payload = None;
+    let mut decoder = Decoder::new(attributes);
+    for _ in 0..decoder.map()? {
+      let key = decoder.uint()?;
+      let value = decoder.bytes()?;
+      if key == DERIVATION_PATH_ATTRIBUTE {
+        hd_payload = Some(Vec::from(Decoder::new(value).bytes()?));
+      }
+    }
+    Ok(Self { root: Vec::from(root), attributes: Vec::from(attributes), hd_payload })
+  }
+
+  // Random index path if derivation path payload is encrypted with this root key
+  pub(super) fn derivation_path(&self, root_xpub: &[u8]) -> Result<Option<Vec<u32>>, Error> {
+    let payload = match self.hd_payload {
+      Some(ref payload) => payload,
+      None => return Ok(None)
+    };
+    let plain = match hd_payload_decrypt(root_xpub, payload) {
+      Some(plain) => plain,
+      None => return Ok(None)
+    };
+    // Path is CBOR array of indexes, Daedalus uses indefinite length encoding
+    let mut decoder = Decoder::new(&plain);
+    let mut path = Vec::new();
+    match decoder.array_len()? {
+      Some(count) => {
+        for _ in 0..count {
+          path.push(decoder.uint()? as u32);
+        }
+      }
+      None => {
+        while !decoder.is_break()? {
+          path.push(decoder.uint()? as u32);
+        }
+      }
+    }
+    decoder.finish()?;
+    Ok(Some(path))
+  }
+
+  pub(super) fn is_derived_from(&self, xpub: &[u8]) -> bool {
+    address_root(xpub, &self.attributes).map(|root| root[..] == self.root[..]).unwrap_or(false)
+  }
+}
+
+pub(super) fn encode(xpub: &[u8], attributes: &[u8]) -> Result<String, Error> {
+  let root = address_root(xpub, attributes)?;
+  let content = cbor::encode_array(&[
+    cbor::encode_bytes(&root),
+    Vec::from(attributes),
+    cbor::encode_uint(PUBKEY_ADDRESS_TYPE)
+  ]);
+  let address = cbor::encode_array(&[
+    cbor::encode_tag(EMBEDDED_CBOR_TAG, &cbor::encode_bytes(&content)),
+    cbor::encode_uint(crc32(&content) as u64)
+  ]);
+  Ok(bs58::encode(address).into_string())
+}
+
+// Blake2b-224 of SHA3-256 of address type, spending data and attributes
+fn address_root(xpub: &[u8], attributes: &[u8]) -> Result<[u8; ADDRESS_ROOT_SIZE], Error> {
+  if xpub.len() != XPUB_SIZE {
+    return Err(Error::InvalidKeySize(xpub.len(), XPUB_SIZE));
+  }
+  let spending_data =
+    cbor::encode_array(&[cbor::encode_uint(PUBKEY_SPENDING_DATA), cbor::encode_bytes(xpub)]);
+  let data = cbor::encode_array(&[
+    cbor::encode_uint(PUBKEY_ADDRESS_TYPE),
+    spending_data,
+    Vec::from(attributes)
+  ]);
+
+  let mut sha3 = [0u8; 32];
+  let mut hasher = Sha3::sha3_256();
+  hasher.input(&data);
+  hasher.result(&mut sha3);
+  let mut root = [0u8; ADDRESS_ROOT_SIZE];
+  Blake2b::blake2b(&mut root, &sha3, &[]);
+  Ok(root)
+}
+
+// Attribute values are CBOR encoded and wrapped into byte strings
+fn attributes(hd_payload: Option<&[u8]>, network: NetworkId) -> Vec<u8> {
+  let mut entries = Vec::new();
+  if let Some(payload) = hd_payload {
+    entries.push((
+      cbor::encode_uint(DERIVATION_PATH_ATTRIBUTE),
+      cbor::encode_bytes(&cbor::encode_bytes(payload))
+    ));
+  }
+  if let Some(magic) = network.protocol_magic() {
+    entries.push((
+      cbor::encode_uint(PROTOCOL_MAGIC_ATTRIBUTE),
+      cbor::encode_bytes(&cbor::encode_uint(magic as u64))
+    ));
+  }
+  cbor::encode_map(&entries)
+}
+
+fn hd_payload_key(root_xpub: &[u8]) -> [u8; HD_PAYLOAD_KEY_SIZE] {
+  let mut key = [0u8; HD_PAYLOAD_KEY_SIZE];
+  let mut mac = Hmac::new(Sha512::new(), root_xpub);
+  pbkdf2(&mut mac, HD_PAYLOAD_SALT, HD_PAYLOAD_ITERATIONS, &mut key);
+  key
+}
+
+// ChaCha20-Poly1305 encrypted path, followed by the tag
+fn hd_payload_encrypt(root_xpub: &[u8], path: &[u32]) -> Vec<u8> {
+  let indexes: Vec<Vec<u8>> = path.iter().map(|&index| cbor::encode_uint(index as u64)).collect();
+  let plain = cbor::encode_indefinite_array(&indexes);
+  let mut payload = vec![0u8; plain.len() + HD_PAYLOAD_TAG_SIZE];
+  let (encrypted, tag) = payload.split_at_mut(plain.len());
+  ChaCha20Poly1305::new(&hd_payload_key(root_xpub), HD_PAYLOAD_NONCE, &[])
+    .encrypt(&plain, encrypted, tag);
+  payload
+}
+
+fn hd_payload_decrypt(root_xpub: &[u8], payload: &[u8]) -> Option<Vec<u8>> {
+  if payload.len() < HD_PAYLOAD_TAG_SIZE {
+    return None;
+  }
+  let (encrypted, tag) = payload.split_at(payload.len() - HD_PAYLOAD_TAG_SIZE);
+  let mut plain = vec![0u8; encrypted.len()];
+  let mut cipher = ChaCha20Poly1305::new(&hd_payload_key(root_xpub), HD_PAYLOAD_NONCE, &[]);
+  if cipher.decrypt(encrypted, &mut plain, tag) {
+    Some(plain)
+  } else {
+    None
+  }
+}
+
+// CRC-32 (IEEE 802.3) of address content
+fn crc32(data: &[u8]) -> u32 {
+  !data.iter().fold(!0u32, |crc, &byte| {
+    (0..8).fold(
+      crc ^ byte as u32,
+      |crc, _| {
+        if crc & 1 == 1 {
+          crc >> 1 ^ 0xedb8_8320
+        } else {
+          crc >> 1
+        }
+      }
+    )
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::address::NetworkId;
+  use super::super::fixture::{mnemonic_key, MNEMONIC};
+  use super::super::master_key::MasterKeyScheme;
+  use super::ByronAddress;
+  use derivation_path::DerivationPath;
+
+  #[test]
+  fn parse() {
+    // CIP-19 Byron vectors, mainnet and testnet with derivation path payload
+    let mainnet = ByronAddress::from_base58(
+      "DdzFFzCqrht4PWfBGtmrQz4x1GkZHYLVGbK7aaBkjWxujxzz3L5GxCgPiTsks5RjUr3yX9KvwKjNJBt7ZzPCmS3fUQr\
+       GeRvo9Y1YBQKQ"
+    )
+    .unwrap();
+    assert_eq!(mainnet.hd_payload.unwrap().len(), 28);
+    let testnet = ByronAddress::from_base58(
+      "37btjrVyb4KDXBNC4haBVPCrro8AQPHwvCMp3RFhhSVWwfFmZ6wwzSK6JK1hY6wHNmtrpTf1kdbva8TCneM2YsiXT7m\
+       rzT21EacHnPpz5YyUdj64na"
+    )
+    .unwrap();
+    assert_eq!(testnet.hd_payload.unwrap().len(), 28);
+    assert!(ByronAddress::from_base58(
+      "DdzFFzCqrht4PWfBGtmrQz4x1GkZHYLVGbK7aaBkjWxujxzz3L5GxCgPiTsks5RjUr3yX9KvwKjNJBt7ZzPCmS3fUQr\
+       GeRvo9Y1YBQKR"
+    )
+    .is_err());
+  }
+
+  // Expected addresses were checked against an independent implementation of the Byron format
+  #[test]
+  fn daedalus() {
+    let key = mnemonic_key(MNEMONIC, MasterKeyScheme::Daedalus);
+    let path = DerivationPath::from("m/0'/1285827418'").unwrap();
+    assert_eq!(
+      key.byron_address(&path, NetworkId::Mainnet).unwrap(),
+      "DdzFFzCqrhsrXwMwRbJvWyFXaxfB5CiuV9NQJBVwLagXofQ616bDaHJRePsy6tovqNGyHATn3TxQN9ffj6b4EiNGjs83\
+       9gEPQwNeXMrN"
+    );
+    assert_eq!(
+      key.byron_address(&path, NetworkId::Preprod).unwrap(),
+      "KjgoiXJS2co7c2cg31uiDsyi2ykofrGr5AN426wYhXx69Mu7nKWc5FEpLFNZvQbu2hn91JJmVTwgTnvkeyzGXm9qZASW\
+       p6LUaUBPDyLdgm3o"
+    );
+    for network in [NetworkId::Mainnet, NetworkId::Preprod].iter() {
+      let address = key.byron_address(&path, *network).unwrap();
+      assert_eq!(key.random_index_path(&address).unwrap(), Some(path.clone()));
+    }
+    assert!(key
+      .byron_address(&DerivationPath::from("m/0'/0").unwrap(), NetworkId::Mainnet)
+      .is_err());
+
+    let address = key.byron_address(&path, NetworkId::Mainnet).unwrap();
+    let other = mnemonic_key(
+      "legal winner thank year wave sausage worth useful legal winner thank yellow",
+      MasterKeyScheme::Daedalus
+    );
+    assert_eq!(other.random_index_path(&address).unwrap(), None);
+  }
+}
@@ -0,0 +1,371 @@
+// Minimal CBOR (RFC 8949) encoding. Arrays and maps take already encoded items.
+
+use key::Error as KeyError;
+use std::fmt;
+
+const UNSIGNED: u8 = 0;
+const NEGATIVE: u8 = 1;
+const BYTES: u8 = 2;
+const TEXT: u8 = 3;
+const ARRAY: u8 = 4;
+const MAP: u8 = 5;
+const TAG: u8 = 6;
+const SIMPLE: u8 = 7;
+
+const FALSE: u8 = 20;
+const TRUE: u8 = 21;
+const NULL: u8 = 22;
+
+const INDEFINITE: u8 = 31;
+const BREAK: u8 = 0xff;
+
+// nesting limit of arrays, maps and tags skipped by the decoder
+const MAX_DEPTH: usize = 64;
+
+#[derive(Debug)]
+pub enum Error {
+  UnexpectedEnd,
+  UnexpectedType(u8, u8),
+  InvalidLength(u8),
+  InvalidCount(u64),
+  NestingTooDeep,
+  TrailingBytes(usize)
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      &Error::UnexpectedEnd => write!(f, "Unexpected end of CBOR data"),
+      &Error::UnexpectedType(found, expected) => {
+        write!(f, "Unexpected CBOR major type {}, expected: {}", found, expected)
+      }
+      &Error::InvalidLength(info) => write!(f, "Invalid CBOR length info {}", info),
+      &Error::InvalidCount(count) => write!(f, "Invalid CBOR items count {}", count),
+      &Error::NestingTooDeep => write!(f, "CBOR nesting is deeper than {}", MAX_DEPTH),
+      &Error::TrailingBytes(count) => write!(f, "Found {} bytes after CBOR item", count)
+    }
+  }
+}
+
+impl std::error::Error for Error {}
+
+impl From<Error> for KeyError {
+  fn from(err: Error) -> Self {
+    KeyError::InvalidData(Box::new(err))
+  }
+}
+
+pub fn encode_uint(value: u64) -> Vec<u8> {
+  encode_head(UNSIGNED, value)
+}
+
+pub fn encode_int(value: i64) -> Vec<u8> {
+  if value < 0 {
+    encode_head(NEGATIVE, !value as u64)
+  } else {
+    encode_head(UNSIGNED, value as u64)
+  }
+}
+
+pub fn encode_bytes(bytes: &[u8]) -> Vec<u8> {
+  let mut encoded = encode_head(BYTES, bytes.len() as u64);
+  encoded.extend_from_slice(bytes);
+  encoded
+}
+
+pub fn encode_text(text: &str) -> Vec<u8> {
+  let mut encoded = encode_head(TEXT, text.len() as u64);
+  encoded.extend_from_slice(text.as_bytes());
+  encoded
+}
+
+pub fn encode_bool(value: bool) -> Vec<u8> {
+  encode_head(SIMPLE, if value { TRUE } else { FALSE } as u64)
+}
+
+pub fn encode_null() -> Vec<u8> {
+  encode_head(SIMPLE, NULL as u64)
+}
+
+pub fn encode_array(items: &[Vec<u8>]) -> Vec<u8> {
+  let mut encoded = encode_head(ARRAY, items.len() as u64);
+  for item in items {
+    encoded.extend_from_slice(item);
+  }
+  encoded
+}
+
+pub fn encode_indefinite_array(items: &[Vec<u8>]) -> Vec<u8> {
+  let mut encoded = vec![ARRAY << 5 | INDEFINITE];
+  for item in items {
+    encoded.extend_from_slice(item);
+  }
+  encoded.push(BREAK);
+  encoded
+}
+
+// Entries should be already sorted if canonical encoding is required
+pub fn encode_map(entries: &[(Vec<u8>, Vec<u8>)]) -> Vec<u8> {
+  let mut encoded = encode_head(MAP, entries.len() as u64);
+  for (key, value) in entries {
+    encoded.extend_from_slice(key);
+    encoded.extend_from_slice(value);
+  }
+  encoded
+}
+
+pub fn encode_tag(tag: u64, item: &[u8]) -> Vec<u8> {
+  let mut encoded = encode_head(TAG, tag);
+  encoded.extend_from_slice(item);
+  encoded
+}
+
+// Shortest form of the item head
+fn encode_head(major: u8, value: u64) -> Vec<u8> {
+  let major = major << 5;
+  match value {
+    0..=23 => vec![major | value as u8],
+    24..=0xff => vec![major | 24, value as u8],
+    0x100..=0xffff => {
+      let mut head = vec![major | 25];
+      head.extend_from_slice(&(value as u16).to_be_bytes());
+      head
+    }
+    0x10000..=0xffff_ffff => {
+      let mut head = vec![major | 26];
+      head.extend_from_slice(&(value as u32).to_be_bytes());
+      head
+    }
+    _ => {
+      let mut head = vec![major | 27];
+      head.extend_from_slice(&value.to_be_bytes());
+      head
+    }
+  }
+}
+
+// Reads items one by one. Indefinite length items are supported by skip only.
+pub struct Decoder<'a> {
+  data: &'a [u8],
+  position: usize
+}
+
+impl<'a> Decoder<'a> {
+  pub fn new(data: &'a [u8]) -> Self {
+    Self { data, position: 0 }
+  }
+
+  pub fn uint(&mut self) -> Result<u64, Error> {
+    self.definite(UNSIGNED)
+  }
+
+  pub fn bytes(&mut self) -> Result<&'a [u8], Error> {
+    let length = self.definite(BYTES)? as usize;
+    self.take(length)
+  }
+
+  pub fn array(&mut self) -> Result<usize, Error> {
+    self.definite(ARRAY).map(|length| length as usize)
+  }
+
+  // None for indefinite length array, which ends with break
+  pub fn array_len(&mut self) -> Result<Option<usize>, Error> {
+    if self.peek()? == ARRAY << 5 | INDEFINITE {
+      self.position += 1;
+      return Ok(None);
+    }
+    self.array().map(Some)
+  }
+
+  pub fn is_break(&mut self) -> Result<bool, Error> {
+    let is_break = self.peek()? == BREAK;
+    if is_break {
+      self.position += 1;
+    }
+    Ok(is_break)
+  }
+
+  pub fn map(&mut self) -> Result<usize, Error> {
+    self.definite(MAP).map(|length| length as usize)
+  }
+
+  // None for indefinite length map, which ends with break
+  pub fn map_len(&mut self) -> Result<Option<usize>, Error> {
+    if self.peek()? == MAP << 5 | INDEFINITE {
+      self.position += 1;
+      return Ok(None);
+    }
+    self.map().map(Some)
+  }
+
+  pub fn tag(&mut self) -> Result<u64, Error> {
+    self.definite(TAG)
+  }
+
+  // Raw encoded bytes of the next item
+  pub fn item(&mut self) -> Result<&'a [u8], Error> {
+    let start = self.position;
+    self.skip(0)?;
+    Ok(&self.data[start..self.position])
+  }
+
+  pub fn finish(&self) -> Result<(), Error> {
+    match self.data.len() - self.position {
+      0 => Ok(()),
+      count => Err(Error::TrailingBytes(count))
+    }
+  }
+
+  fn skip(&mut self, depth: usize) -> Result<(), Error> {
+    let (major, info) = self.initial()?;
+    let inner = match major {
+      ARRAY | MAP | TAG if depth == MAX_DEPTH => return Err(Error::NestingTooDeep),
+      _ => depth + 1
+    };
+    if info == INDEFINITE {
+      return match major {
+        BYTES | TEXT | ARRAY | MAP => {
+          while self.peek()? != BREAK {
+            self.skip(inner)?;
+            if major == MAP {
+              self.skip(inner)?;
+            }
+          }
+          self.position += 1;
+          Ok(())
+        }
+        _ => Err(Error::InvalidLength(info))
+      };
+    }
+    let value = self.argument(info)?;
+    let count = match major {
+      BYTES | TEXT | ARRAY => value,
+      MAP => value.checked_mul(2).ok_or(Error::InvalidCount(value))?,
+      _ => 0
+    };
+    // every item takes at least one byte
+    if count > (self.data.len() - self.position) as u64 {
+      return Err(Error::InvalidCount(value));
+    }
+    match major {
+      BYTES | TEXT => self.take(count as usize).map(|_| ()),
+      ARRAY | MAP => (0..count).try_for_each(|_| self.skip(inner)),
+      TAG => self.skip(inner),
+      _ => Ok(())
+    }
+  }
+
+  fn definite(&mut self, expected: u8) -> Result<u64, Error> {
+    let (major, info) = self.initial()?;
+    if major != expected {
+      return Err(Error::UnexpectedType(major, expected));
+    }
+    self.argument(info)
+  }
+
+  fn initial(&mut self) -> Result<(u8, u8), Error> {
+    let byte = self.peek()?;
+    self.position += 1;
+    Ok((byte >> 5, byte & 0x1f))
+  }
+
+  fn argument(&mut self, info: u8) -> Result<u64, Error> {
+    let size = match info {
+      0..=23 => return Ok(info as u64),
+      24 => 1,
+      25 => 2,
+      26 => 4,
+      27 => 8,
+      _ => return Err(Error::InvalidLength(info))
+    };
+    Ok(self.take(size)?.iter().fold(0u64, |value, &byte| value << 8 | byte as u64))
+  }
+
+  fn peek(&self) -> Result<u8, Error> {
+    self.data.get(self.position).cloned().ok_or(Error::UnexpectedEnd)
+  }
+
+  fn take(&mut self, length: usize) -> Result<&'a [u8], Error> {
+    if self.data.len() - self.position < length {
+      return Err(Error::UnexpectedEnd);
+    }
+    self.position += length;
+    Ok(&self.data[self.position - length..self.position])
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    encode_array, encode_bool, encode_bytes, encode_indefinite_array, encode_int, encode_map,
+    encode_null, encode_tag, encode_text, encode_uint, Decoder, Error
+  };
+  use bip39::util::hex;
+
+  #[test]
+  fn encode() {
+    assert_eq!(hex::encode(&encode_uint(23)), "17");
+    assert_eq!(hex::encode(&encode_uint(1000)), "1903e8");
+    assert_eq!(hex::encode(&encode_uint(1000000000000)), "1b000000e8d4a51000");
+    assert_eq!(hex::encode(&encode_int(-8)), "27");
+    assert_eq!(hex::encode(&encode_int(-1000)), "3903e7");
+    assert_eq!(hex::encode(&encode_bytes(&[1, 2, 3, 4])), "4401020304");
+    assert_eq!(hex::encode(&encode_text("hashed")), "66686173686564");
+    assert_eq!(
+      hex::encode(&[encode_bool(false), encode_bool(true), encode_null()].concat()),
+      "f4f5f6"
+    );
+    let items = [encode_uint(1), encode_uint(2)];
+    assert_eq!(hex::encode(&encode_indefinite_array(&items)), "9f0102ff");
+    let map = encode_map(&[(encode_uint(1), encode_uint(2))]);
+    assert_eq!(hex::encode(&encode_tag(24, &encode_bytes(&map))), "d81843a10102");
+  }
+
+  #[test]
+  fn decode() {
+    let data = encode_array(&[
+      encode_uint(1000),
+      encode_indefinite_array(&[encode_uint(1)]),
+      encode_bytes(&[5, 6])
+    ]);
+    let mut decoder = Decoder::new(&data);
+    assert_eq!(decoder.array().unwrap(), 3);
+    assert_eq!(decoder.uint().unwrap(), 1000);
+    assert_eq!(hex::encode(decoder.item().unwrap()), "9f01ff");
+    assert!(decoder.uint().is_err());
+
+    let mut decoder = Decoder::new(&data[4..]);
+    decoder.item().unwrap();
+    assert_eq!(decoder.bytes().unwrap(), &[5, 6]);
+    assert!(decoder.finish().is_ok());
+    assert!(Decoder::new(&data[..3]).item().is_err());
+  }
+
+  #[test]
+  fn decode_limits() {
+    let mut nested = vec![0xa1, 0x00];
+    nested.extend(vec![0x81; 200000]);
+    nested.push(0x00);
+    match Decoder::new(&nested).item() {
+      Err(Error::NestingTooDeep) => {}
+      other => panic!("unexpected result {:?}", other)
+    }
+
+    let huge_map = hex::decode("bbffffffffffffffff").unwrap();
+    match Decoder::new(&huge_map).item() {
+      Err(Error::InvalidCount(count)) => assert_eq!(count, u64::MAX),
+      other => panic!("unexpected result {:?}", other)
+    }
+    let long_array = hex::decode("9a0000ffff0102").unwrap();
+    match Decoder::new(&long_array).item() {
+      Err(Error::InvalidCount(count)) => assert_eq!(count, 0xffff),
+      other => panic!("unexpected result {:?}", other)
+    }
+
+    let mut deepest = vec![0x81; 64];
+    deepest.push(0x00);
+    assert_eq!(Decoder::new(&deepest).item().unwrap().len(), 65);
+    deepest.insert(0, 0x81);
+    assert!(Decoder::new(&deepest).item().is_err());
+  }
+}
@@ -1,14 +1,28 @@
 use super::key::Key;
+use super::master_key::MasterKeyScheme;
+use super::watch_only::WatchOnlyKey;
 use bip39::Seed;
+use derivation_path::DerivationPath;
 use key::{Error as KeyError, Key as IKey};
-use key_factory::{KeyFactory as IKeyFactory, SeedSize};
+use key_factory::{KeyFactory as IKeyFactory, KeySeed, SeedSize};
 use network::Network;
 
-pub struct KeyFactory;
+pub struct KeyFactory {
+  scheme: MasterKeyScheme
+}
 
 impl KeyFactory {
+  // New keys are generated with CIP-3 Icarus scheme
   pub fn new() -> Self {
-    Self {}
+    Self::with_scheme(MasterKeyScheme::Icarus)
+  }
+
+  pub fn with_scheme(scheme: MasterKeyScheme) -> Self {
+    Self { scheme }
+  }
+
+  pub fn scheme(&self) -> MasterKeyScheme {
+    self.scheme
   }
 }
 
@@ -25,8 +39,29 @@ impl IKeyFactory for KeyFactory {
     Key::from_data(data).map(|pk| -> Box<dyn IKey> { pk.boxed() })
   }
 
+  // Raw seed has no mnemonic entropy. Keys from it need Legacy or Ledger scheme.
   fn key_data_from_seed(&self, seed: &[u8]) -> Result<Vec<u8>, KeyError> {
-    let seed = Seed::from_slice(seed).map_err(|err| KeyError::InvalidMnemonic(err.into()))?;
-    Key::data_from_seed(&seed)
+    Seed::from_slice(seed).map_err(|err| KeyError::InvalidMnemonic(err.into()))?;
+    let seed = KeySeed { seed: Vec::from(seed), entropy: None, passphrase: String::new() };
+    Key::data_from_seed(&seed, self.scheme)
+  }
+
+  fn key_data(&self, seed: &KeySeed) -> Result<Vec<u8>, KeyError> {
+    match seed.entropy {
+      Some(_) => Key::data_from_seed(seed, self.scheme),
+      None => self.key_data_from_seed(&seed.seed)
+    }
+  }
+
+  // Stored key is regenerated with its own scheme, so legacy keys stay valid
+  fn check_key_data(&self, data: &[u8], seed: &KeySeed) -> Result<bool, KeyError> {
+    let key = Key::from_data(data)?;
+    Key::data_from_seed(seed, key.scheme()).map(|generated| generated == key.data())
+  }
+
+  fn key_from_account_xpub(
+    &self, path: &DerivationPath, xpub: &str
+  ) -> Result<Box<dyn IKey>, KeyError> {
+    WatchOnlyKey::from_account_xpub(path, xpub).map(|pk| -> Box<dyn IKey> { pk.boxed() })
   }
 }
@@ -1,22 +1,35 @@
-use super::key_path::BIP44_COIN_TYPE;
-use bip39;
-use cryptoxide::digest::Digest;
-use cryptoxide::sha2::Sha512;
+use super::address::NetworkId;
+use super::byron::{
+  check_icarus_path, check_random_index_path, daedalus_attributes, encode as byron_address,
+  icarus_attributes, ByronAddress
+};
+use super::key_path::{Role, BIP44_COIN_TYPE, CIP1852_PURPOSE, CIP36_PURPOSE};
+use super::master_key::{generate, MasterKeyScheme};
+use bech32::{self, ToBase32, Variant};
+use derivation_path::DerivationPath;
 use ed25519_bip32::{
   DerivationScheme, PrivateKeyError, Signature, XPrv, SIGNATURE_SIZE, XPRV_SIZE
 };
 use key::{Error, Key as IKey};
-use key_path::{Error as KPError, KeyPath, BIP44_PURPOSE, BIP44_SOFT_UPPER_BOUND};
+use key_factory::KeySeed;
+use key_path::{Error as KPError, ACCOUNT_DEPTH, ADDRESS_DEPTH, BIP44_PURPOSE};
 use network::Network;
+use num_traits::FromPrimitive;
+use std::any::Any;
 use std::fmt;
 
-const D_SCHEME: DerivationScheme = DerivationScheme::V2;
+// CIP-5 prefix for account extended verification key
+pub(super) const ACCOUNT_XPUB_HRP: &str = "acct_xvk";
 
 #[derive(Debug)]
 pub enum KeyError {
   LengthInvalid(usize),
   HighestBitsInvalid,
-  LowestBitsInvalid
+  LowestBitsInvalid,
+  InvalidPrefix(String),
+  PublicDerivationFailed,
+  UnknownMasterKeyScheme(u8),
+  EntropyIsNotAvailable(MasterKeyScheme)
 }
 
 impl fmt::Display for KeyError {
@@ -24,7 +37,17 @@ impl fmt::Display for KeyError {
     match self {
       &KeyError::LengthInvalid(len) => write!(f, "Invalid data length {}", len),
       &KeyError::HighestBitsInvalid => write!(f, "Highest bits is invalid"),
-      &KeyError::LowestBitsInvalid => write!(f, "Lowest bits is invalid")
+      &KeyError::LowestBitsInvalid => write!(f, "Lowest bits is invalid"),
+      &KeyError::InvalidPrefix(ref prefix) => {
+        write!(f, "Invalid prefix {}, expected: {}", prefix, ACCOUNT_XPUB_HRP)
+      }
+      &KeyError::PublicDerivationFailed => write!(f, "Public key derivation failed"),
+      &KeyError::UnknownMasterKeyScheme(scheme) => {
+        write!(f, "Unknown master key scheme {}", scheme)
+      }
+      &KeyError::EntropyIsNotAvailable(scheme) => {
+        write!(f, "Master key scheme {:?} requires mnemonic entropy", scheme)
+      }
     }
   }
 }
@@ -42,98 +65,207 @@ impl From<PrivateKeyError> for KeyError {
 }
 
 pub struct Key {
-  xprv: XPrv
+  xprv: XPrv,
+  scheme: MasterKeyScheme
 }
 
 impl Key {
+  // Scheme byte followed by root xprv. Data without scheme byte is stored by older versions
+  // and always has legacy root key.
   pub fn from_data(data: &[u8]) -> Result<Self, Error> {
+    let (scheme, xprv) = match data.len() {
+      XPRV_SIZE => (MasterKeyScheme::Legacy, data),
+      len if len == XPRV_SIZE + 1 => {
+        let scheme = MasterKeyScheme::from_u8(data[0]).ok_or_else(|| {
+          Error::InvalidKeyData(Box::new(KeyError::UnknownMasterKeyScheme(data[0])))
+        })?;
+        (scheme, &data[1..])
+      }
+      len => return Err(Error::InvalidKeySize(len, XPRV_SIZE + 1))
+    };
     let mut arr: [u8; XPRV_SIZE] = [0; XPRV_SIZE];
-    if data.len() < XPRV_SIZE {
-      return Err(Error::InvalidKeySize(data.len(), XPRV_SIZE));
-    }
-    arr.copy_from_slice(data);
-    XPrv::from_bytes_verified(arr)
-      .map(|xprv| Self {
-        xprv: xprv.derive(D_SCHEME, BIP44_PURPOSE).derive(D_SCHEME, BIP44_COIN_TYPE)
-      })
-      .map_err(|err| {
-        let key_err: KeyError = err.into();
-        Error::InvalidKeyData(Box::new(key_err))
-      })
-  }
-
-  pub fn data_from_seed(seed: &bip39::Seed) -> Result<Vec<u8>, Error> {
-    let mut out = [0u8; XPRV_SIZE];
-
-    let mut hasher = Sha512::new();
-    hasher.input(&seed.as_ref()[0..32]);
-    hasher.result(&mut out[0..64]);
-    out[0] &= 248;
-    out[31] &= 63;
-    out[31] |= 64;
-    out[31] &= 0b1101_1111; // set 3rd highest bit to 0 as per the spec
-    out[64..96].clone_from_slice(&seed.as_ref()[32..64]);
-
-    XPrv::from_bytes_verified(out)
-      .map_err(|err| {
-        let key_err: KeyError = err.into();
-        Error::InvalidKeyData(Box::new(key_err))
-      })
-      .map(|xprv| Vec::from(xprv.as_ref()))
-  }
-
-  fn derive_private(&self, path: &dyn KeyPath) -> Result<XPrv, Error> {
-    if path.purpose() != BIP44_PURPOSE {
-      return Err(KPError::InvalidPurpose(path.purpose(), BIP44_PURPOSE).into());
+    arr.copy_from_slice(xprv);
+    XPrv::from_bytes_verified(arr).map(|xprv| Self { xprv, scheme }).map_err(|err| {
+      let key_err: KeyError = err.into();
+      Error::InvalidKeyData(Box::new(key_err))
+    })
+  }
+
+  pub fn data_from_seed(seed: &KeySeed, scheme: MasterKeyScheme) -> Result<Vec<u8>, Error> {
+    let xprv = XPrv::from_bytes_verified(generate(scheme, seed)?).map_err(|err| {
+      let key_err: KeyError = err.into();
+      Error::InvalidKeyData(Box::new(key_err))
+    })?;
+    Ok(Self { xprv, scheme }.data())
+  }
+
+  pub fn data(&self) -> Vec<u8> {
+    let mut data = Vec::with_capacity(XPRV_SIZE + 1);
+    data.push(self.scheme as u8);
+    data.extend_from_slice(self.xprv.as_ref());
+    data
+  }
+
+  pub fn scheme(&self) -> MasterKeyScheme {
+    self.scheme
+  }
+
+  // Root public key encrypts derivation paths of Daedalus addresses
+  pub(super) fn root_pub_key(&self) -> Vec<u8> {
+    Vec::from(self.xprv.public().as_ref())
+  }
+
+  pub(super) fn byron_address(
+    &self, path: &DerivationPath, network: NetworkId
+  ) -> Result<String, Error> {
+    let attributes = self.byron_attributes(path, network)?;
+    byron_address(&self.pub_key(path)?, &attributes)
+  }
+
+  // Daedalus keys use random index paths, others Icarus style BIP44 paths
+  pub(super) fn byron_attributes(
+    &self, path: &DerivationPath, network: NetworkId
+  ) -> Result<Vec<u8>, Error> {
+    match self.scheme {
+      MasterKeyScheme::Daedalus => {
+        check_random_index_path(path)?;
+        Ok(daedalus_attributes(&self.root_pub_key(), path.path(), network))
+      }
+      _ => {
+        check_icarus_path(path)?;
+        Ok(icarus_attributes(network))
+      }
     }
-    if path.coin() != BIP44_COIN_TYPE {
-      return Err(KPError::InvalidCoin(path.coin(), BIP44_COIN_TYPE).into());
+  }
+
+  // Payload decryption proves the path was encrypted by our root key, root hash check proves
+  // the address key is ours.
+  pub(super) fn random_index_path(&self, address: &str) -> Result<Option<DerivationPath>, Error> {
+    if self.scheme != MasterKeyScheme::Daedalus {
+      return Ok(None);
     }
-    if path.account() < BIP44_SOFT_UPPER_BOUND {
-      return Err(KPError::InvalidAccount(path.account()).into());
+    let address = ByronAddress::from_base58(address)?;
+    let path = match address.derivation_path(&self.root_pub_key())? {
+      Some(indexes) => DerivationPath::new(indexes),
+      None => return Ok(None)
+    };
+    if check_random_index_path(&path).is_err() {
+      return Ok(None);
     }
-    if path.change() != 0 && path.change() != 1 {
-      return Err(KPError::InvalidChange(path.change()).into());
+    Ok(Some(path).filter(|path| {
+      self.pub_key(path).map(|xpub| address.is_derived_from(&xpub)).unwrap_or(false)
+    }))
+  }
+
+  fn derivation_scheme(&self) -> DerivationScheme {
+    match self.scheme {
+      MasterKeyScheme::Daedalus => DerivationScheme::V1,
+      _ => DerivationScheme::V2
     }
-    if path.address() >= BIP44_SOFT_UPPER_BOUND {
-      return Err(KPError::InvalidAddress(path.address()).into());
+  }
+
+  // Daedalus wallets have no accounts, only random index paths
+  fn derive_account(&self, path: &DerivationPath) -> Result<XPrv, Error> {
+    if self.scheme == MasterKeyScheme::Daedalus {
+      return Err(Error::OperationIsNotSupported("account_pub_key"));
     }
-    Ok(
-      self
-        .xprv
-        .derive(D_SCHEME, path.account())
-        .derive(D_SCHEME, path.change())
-        .derive(D_SCHEME, path.address())
-    )
+    check_account_path(path)?;
+    Ok(derive_path(&self.xprv, &path.path()[..ACCOUNT_DEPTH], self.derivation_scheme()))
+  }
+
+  fn derive_private(&self, path: &DerivationPath) -> Result<XPrv, Error> {
+    match self.scheme {
+      MasterKeyScheme::Daedalus => check_random_index_path(path)?,
+      _ => check_address_path(path)?
+    };
+    Ok(derive_path(&self.xprv, path.path(), self.derivation_scheme()))
+  }
+
+  // 64 bytes of ed25519-bip32 account public key with chain code
+  pub fn account_pub_key(&self, path: &DerivationPath) -> Result<Vec<u8>, Error> {
+    self.derive_account(path).map(|pk| Vec::from(pk.public().as_ref()))
   }
 }
 
+fn derive_path(xprv: &XPrv, path: &[u32], scheme: DerivationScheme) -> XPrv {
+  path.iter().fold(xprv.clone(), |key, &index| key.derive(scheme, index))
+}
+
+// BIP44 (Byron), CIP-1852 (Shelley) and CIP-36 (vote keys) account paths
+pub(super) fn check_account_path(path: &DerivationPath) -> Result<(), Error> {
+  if path.depth() < ACCOUNT_DEPTH {
+    return Err(KPError::InvalidDepth(path.depth()).into());
+  }
+  let indexes = path.path();
+  if ![BIP44_PURPOSE, CIP1852_PURPOSE, CIP36_PURPOSE].contains(&indexes[0]) {
+    return Err(KPError::InvalidPurpose(indexes[0], CIP1852_PURPOSE).into());
+  }
+  if indexes[1] != BIP44_COIN_TYPE {
+    return Err(KPError::InvalidCoin(indexes[1], BIP44_COIN_TYPE).into());
+  }
+  if !DerivationPath::is_hardened(indexes[2]) {
+    return Err(KPError::InvalidAccount(indexes[2]).into());
+  }
+  Ok(())
+}
+
+// External (0) and internal (1) chains. CIP-1852 adds staking, DRep and committee roles.
+pub(super) fn check_address_path(path: &DerivationPath) -> Result<(), Error> {
+  check_account_path(path)?;
+  if path.depth() != ADDRESS_DEPTH {
+    return Err(KPError::InvalidDepth(path.depth()).into());
+  }
+  let indexes = path.path();
+  if indexes[3] > Role::max(indexes[0]) {
+    return Err(KPError::InvalidChange(indexes[3]).into());
+  }
+  if DerivationPath::is_hardened(indexes[4]) {
+    return Err(KPError::InvalidAddress(indexes[4]).into());
+  }
+  Ok(())
+}
+
+pub(super) fn signature_from_bytes(signature: &[u8]) -> Result<Signature<Vec<u8>>, Error> {
+  let mut sign: [u8; SIGNATURE_SIZE] = [0; SIGNATURE_SIZE];
+  if signature.len() != SIGNATURE_SIZE {
+    return Err(Error::InvalidSignatureSize(signature.len(), SIGNATURE_SIZE));
+  }
+  sign.copy_from_slice(signature);
+  Ok(Signature::from_bytes(sign))
+}
+
 impl IKey for Key {
   fn network(&self) -> Network {
     Network::CARDANO
   }
 
-  fn pub_key(&self, path: &dyn KeyPath) -> Result<Vec<u8>, Error> {
+  fn pub_key(&self, path: &DerivationPath) -> Result<Vec<u8>, Error> {
     self.derive_private(path).map(|pk| Vec::from(pk.public().as_ref()))
   }
 
-  fn sign(&self, data: &[u8], path: &dyn KeyPath) -> Result<Vec<u8>, Error> {
+  fn address(&self, _: &DerivationPath) -> Result<String, Error> {
+    Err(Error::OperationIsNotSupported("address"))
+  }
+
+  fn account_xpub(&self, path: &DerivationPath) -> Result<String, Error> {
+    let xpub = self.account_pub_key(path)?;
+    bech32::encode(ACCOUNT_XPUB_HRP, xpub.to_base32(), Variant::Bech32)
+      .map_err(|err| Error::InvalidKeyData(Box::new(err)))
+  }
+
+  fn sign(&self, data: &[u8], path: &DerivationPath) -> Result<Vec<u8>, Error> {
     self.derive_private(path).map(|pk| {
       let signature: Signature<Vec<u8>> = pk.sign(data);
       Vec::from(signature.as_ref())
     })
   }
 
-  fn verify(&self, data: &[u8], signature: &[u8], path: &dyn KeyPath) -> Result<bool, Error> {
-    let mut sign: [u8; SIGNATURE_SIZE] = [0; SIGNATURE_SIZE];
-    if signature.len() < SIGNATURE_SIZE {
-      return Err(Error::InvalidSignatureSize(signature.len(), SIGNATURE_SIZE));
-    }
-    sign.copy_from_slice(signature);
+  fn verify(&self, data: &[u8], signature: &[u8], path: &DerivationPath) -> Result<bool, Error> {
+    let signature = signature_from_bytes(signature)?;
+    self.derive_private(path).map(|pk| pk.verify(data, &signature))
+  }
 
-    self.derive_private(path).map(|pk| {
-      let native_signature: Signature<Vec<u8>> = Signature::from_bytes(sign);
-      pk.verify(data, &native_signature)
-    })
+  fn as_any(&self) -> Option<&dyn Any> {
+    Some(self)
   }
 }
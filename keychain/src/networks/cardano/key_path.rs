@@ -3,31 +3,94 @@ use key_path::{Error, KeyPath as IKeyPath, BIP44_PURPOSE, BIP44_SOFT_UPPER_BOUND
 /// the BIP44 coin type is set, by default, to cardano ada.
 pub const BIP44_COIN_TYPE: u32 = 0x80000717;
 
+/// CIP-1852 purpose for Shelley era keys
+pub const CIP1852_PURPOSE: u32 = 0x8000073c;
+
+/// CIP-36 purpose for vote keys
+pub const CIP36_PURPOSE: u32 = 0x8000069e;
+
+/// CIP-1852 chain roles. DRep and constitutional committee roles are defined in CIP-105.
+#[derive(Primitive, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Role {
+  External = 0,
+  Internal = 1,
+  Staking = 2,
+  DRep = 3,
+  CommitteeCold = 4,
+  CommitteeHot = 5
+}
+
+impl Role {
+  // Byron paths have external and internal chains only. Vote keys use chain 0.
+  pub(super) fn max(purpose: u32) -> u32 {
+    match purpose {
+      CIP1852_PURPOSE => Role::CommitteeHot as u32,
+      CIP36_PURPOSE => Role::External as u32,
+      _ => Role::Internal as u32
+    }
+  }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct KeyPath {
+  purpose: u32,
   account: u32,
   change: u32,
   address: u32
 }
 
 impl KeyPath {
-  pub fn new(account: u32, change: u32, address: u32) -> Result<Self, Error> {
+  #[inline]
+  fn is_valid(account: u32, address: u32) -> Result<(), Error> {
     if account >= BIP44_SOFT_UPPER_BOUND {
       return Err(Error::InvalidAccount(account));
     }
-    if change != 0 && change != 1 {
-      return Err(Error::InvalidChange(change));
-    }
     if address >= BIP44_SOFT_UPPER_BOUND {
-      return Err(Error::InvalidAddress(change));
+      return Err(Error::InvalidAddress(address));
+    }
+    Ok(())
+  }
+
+  // Byron era BIP44 path
+  pub fn new(account: u32, change: u32, address: u32) -> Result<Self, Error> {
+    Self::is_valid(account, address)?;
+    if change > Role::max(BIP44_PURPOSE) {
+      return Err(Error::InvalidChange(change));
     }
-    Ok(KeyPath { account: account + BIP44_SOFT_UPPER_BOUND, change, address })
+    Ok(KeyPath {
+      purpose: BIP44_PURPOSE,
+      account: account + BIP44_SOFT_UPPER_BOUND,
+      change,
+      address
+    })
+  }
+
+  // Shelley era CIP-1852 path
+  pub fn cip1852(account: u32, role: Role, index: u32) -> Result<Self, Error> {
+    Self::is_valid(account, index)?;
+    Ok(KeyPath {
+      purpose: CIP1852_PURPOSE,
+      account: account + BIP44_SOFT_UPPER_BOUND,
+      change: role as u32,
+      address: index
+    })
+  }
+
+  // CIP-36 vote key path
+  pub fn cip36(account: u32, index: u32) -> Result<Self, Error> {
+    Self::is_valid(account, index)?;
+    Ok(KeyPath {
+      purpose: CIP36_PURPOSE,
+      account: account + BIP44_SOFT_UPPER_BOUND,
+      change: 0,
+      address: index
+    })
   }
 }
 
 impl IKeyPath for KeyPath {
   fn purpose(&self) -> u32 {
-    BIP44_PURPOSE
+    self.purpose
   }
 
   fn coin(&self) -> u32 {
@@ -46,3 +109,44 @@ impl IKeyPath for KeyPath {
     self.address
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::super::fixture::key;
+  use super::{KeyPath, Role};
+  use derivation_path::DerivationPath;
+  use key::Key as IKey;
+  use key_path::KeyPath as IKeyPath;
+
+  #[test]
+  fn cip1852_roles() {
+    let key = key();
+    let drep = KeyPath::cip1852(0, Role::DRep, 0).unwrap().derivation_path();
+    assert_eq!(drep, DerivationPath::from("m/1852'/1815'/0'/3/0").unwrap());
+
+    for role in 0..=Role::CommitteeHot as u32 {
+      let path = DerivationPath::from(&format!("m/1852'/1815'/0'/{}/0", role)).unwrap();
+      assert!(key.pub_key(&path).is_ok());
+    }
+    assert!(key.pub_key(&DerivationPath::from("m/1852'/1815'/0'/6/0").unwrap()).is_err());
+  }
+
+  #[test]
+  fn cip36_paths() {
+    let key = key();
+    let path = KeyPath::cip36(1, 2).unwrap().derivation_path();
+    assert_eq!(path, DerivationPath::from("m/1694'/1815'/1'/0/2").unwrap());
+    assert!(key.pub_key(&path).is_ok());
+    assert!(key.pub_key(&DerivationPath::from("m/1694'/1815'/0'/1/0").unwrap()).is_err());
+  }
+
+  #[test]
+  fn byron_paths() {
+    let key = key();
+    let path = KeyPath::new(0, 1, 0).unwrap().derivation_path();
+    assert_eq!(path, DerivationPath::from("m/44'/1815'/0'/1/0").unwrap());
+    assert!(key.pub_key(&path).is_ok());
+    assert!(KeyPath::new(0, 2, 0).is_err());
+    assert!(key.pub_key(&DerivationPath::from("m/44'/1815'/0'/2/0").unwrap()).is_err());
+  }
+}
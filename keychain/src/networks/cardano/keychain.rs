@@ -0,0 +1,312 @@
+use super::address::{
+  base_address, check_payment_path, check_stake_path, enterprise_address, pointer_address,
+  reward_address, NetworkId, Pointer
+};
+use super::byron::{check_icarus_path, icarus_address};
+use super::key::Key;
+use super::master_key::MasterKeyScheme;
+use super::message::{sign_data, DataSignature};
+use super::transaction::{transaction_hash, BootstrapWitness, VKeyWitness};
+use super::vote::{check_vote_path, registration_hash, registration_metadata, VoteRegistration};
+use super::watch_only::WatchOnlyKey;
+use derivation_path::DerivationPath;
+use error::Error;
+use key::{Error as KeyError, Key as IKey};
+use keychain::Keychain;
+use network::Network;
+
+impl Keychain {
+  // Raw ed25519-bip32 account public key: 32 bytes of public key followed by 32 bytes of chain code
+  pub fn cardano_account_pub_key(&self, path: &DerivationPath) -> Result<Vec<u8>, Error> {
+    let pub_key = match self.network_key::<Key>(&Network::CARDANO) {
+      Ok(key) => key.account_pub_key(path),
+      Err(_) => self.network_key::<WatchOnlyKey>(&Network::CARDANO)?.account_pub_key(path)
+    };
+    pub_key.map_err(|err| Error::from_key_error(&Network::CARDANO, err))
+  }
+
+  // Keychains created before CIP-3 support keep legacy root key, so their addresses don't change
+  pub fn cardano_master_key_scheme(&self) -> Result<MasterKeyScheme, Error> {
+    self.cardano_private_key().map(|key| key.scheme())
+  }
+
+  // CIP-19 base address with payment and stake key hashes
+  pub fn cardano_base_address(
+    &self, payment: &DerivationPath, stake: &DerivationPath, network: NetworkId
+  ) -> Result<String, Error> {
+    let payment_key = self.cardano_payment_key(payment)?;
+    let stake_key = self.cardano_stake_key(stake)?;
+    base_address(&payment_key, &stake_key, network)
+      .map_err(|err| Error::from_key_error(&Network::CARDANO, err))
+  }
+
+  // Address without staking rights
+  pub fn cardano_enterprise_address(
+    &self, payment: &DerivationPath, network: NetworkId
+  ) -> Result<String, Error> {
+    let payment_key = self.cardano_payment_key(payment)?;
+    enterprise_address(&payment_key, network)
+      .map_err(|err| Error::from_key_error(&Network::CARDANO, err))
+  }
+
+  pub fn cardano_pointer_address(
+    &self, payment: &DerivationPath, pointer: &Pointer, network: NetworkId
+  ) -> Result<String, Error> {
+    let payment_key = self.cardano_payment_key(payment)?;
+    pointer_address(&payment_key, pointer, network)
+      .map_err(|err| Error::from_key_error(&Network::CARDANO, err))
+  }
+
+  pub fn cardano_reward_address(
+    &self, stake: &DerivationPath, network: NetworkId
+  ) -> Result<String, Error> {
+    let stake_key = self.cardano_stake_key(stake)?;
+    reward_address(&stake_key, network).map_err(|err| Error::from_key_error(&Network::CARDANO, err))
+  }
+
+  // Byron bootstrap address. Daedalus keys use random index paths m/account'/address' and put
+  // encrypted path into the address. Other keys use Icarus style BIP44 paths.
+  pub fn cardano_byron_address(
+    &self, path: &DerivationPath, network: NetworkId
+  ) -> Result<String, Error> {
+    if let Ok(key) = self.network_key::<Key>(&Network::CARDANO) {
+      return key
+        .byron_address(path, network)
+        .map_err(|err| Error::from_key_error(&Network::CARDANO, err));
+    }
+    check_icarus_path(path).map_err(|err| Error::from_key_error(&Network::CARDANO, err))?;
+    let xpub = self.pub_key(&Network::CARDANO, path)?;
+    icarus_address(&xpub, network).map_err(|err| Error::from_key_error(&Network::CARDANO, err))
+  }
+
+  // Random index path of Daedalus address, if the address belongs to this keychain.
+  // Used to find funds of legacy wallets, as their address indexes can't be enumerated.
+  pub fn cardano_byron_address_path(&self, address: &str) -> Result<Option<DerivationPath>, Error> {
+    self
+      .network_key::<Key>(&Network::CARDANO)?
+      .random_index_path(address)
+      .map_err(|err| Error::from_key_error(&Network::CARDANO, err))
+  }
+
+  // Signs Blake2b-256 hash of CBOR transaction body with each key
+  pub fn cardano_sign_transaction(
+    &self, body: &[u8], paths: &[DerivationPath]
+  ) -> Result<Vec<VKeyWitness>, Error> {
+    let key = self.cardano_private_key()?;
+    let witness = |hash: &[u8], path: &DerivationPath| -> Result<VKeyWitness, KeyError> {
+      Ok(VKeyWitness::new(&key.pub_key(path)?, key.sign(hash, path)?))
+    };
+    transaction_hash(body)
+      .and_then(|hash| paths.iter().map(|path| witness(&hash, path)).collect())
+      .map_err(|err| Error::from_key_error(&Network::CARDANO, err))
+  }
+
+  // Witnesses for Byron address inputs. Address attributes depend on network.
+  pub fn cardano_sign_bootstrap_transaction(
+    &self, body: &[u8], paths: &[DerivationPath], network: NetworkId
+  ) -> Result<Vec<BootstrapWitness>, Error> {
+    let key = self.cardano_private_key()?;
+    let witness = |hash: &[u8], path: &DerivationPath| -> Result<BootstrapWitness, KeyError> {
+      let attributes = key.byron_attributes(path, network)?;
+      Ok(BootstrapWitness::new(&key.pub_key(path)?, key.sign(hash, path)?, attributes))
+    };
+    transaction_hash(body)
+      .and_then(|hash| paths.iter().map(|path| witness(&hash, path)).collect())
+      .map_err(|err| Error::from_key_error(&Network::CARDANO, err))
+  }
+
+  // CIP-30 signData. Path key should be payment key of the address, or stake key of reward address.
+  pub fn cardano_sign_data(
+    &self, path: &DerivationPath, address: &str, payload: &[u8]
+  ) -> Result<DataSignature, Error> {
+    sign_data(self.cardano_private_key()?, path, address, payload)
+      .map_err(|err| Error::from_key_error(&Network::CARDANO, err))
+  }
+
+  // CIP-36 vote public key, 32 bytes without chain code
+  pub fn cardano_vote_key(&self, path: &DerivationPath) -> Result<Vec<u8>, Error> {
+    check_vote_path(path).map_err(|err| Error::from_key_error(&Network::CARDANO, err))?;
+    let mut key = self.pub_key(&Network::CARDANO, path)?;
+    key.truncate(32);
+    Ok(key)
+  }
+
+  // CIP-36 registration transaction metadata {61284: registration, 61285: {1: signature}},
+  // signed by the stake key
+  pub fn cardano_sign_vote_registration(
+    &self, registration: &VoteRegistration, stake: &DerivationPath
+  ) -> Result<Vec<u8>, Error> {
+    let key = self.cardano_private_key()?;
+    let sign = || -> Result<Vec<u8>, KeyError> {
+      check_stake_path(stake)?;
+      let registration = registration.to_cbor(&key.pub_key(stake)?)?;
+      let signature = key.sign(&registration_hash(&registration), stake)?;
+      Ok(registration_metadata(&registration, &signature))
+    };
+    sign().map_err(|err| Error::from_key_error(&Network::CARDANO, err))
+  }
+
+  fn cardano_payment_key(&self, path: &DerivationPath) -> Result<Vec<u8>, Error> {
+    check_payment_path(path).map_err(|err| Error::from_key_error(&Network::CARDANO, err))?;
+    self.pub_key(&Network::CARDANO, path)
+  }
+
+  fn cardano_private_key(&self) -> Result<&Key, Error> {
+    self.network_key::<Key>(&Network::CARDANO).map_err(|err| {
+      match self.network_key::<WatchOnlyKey>(&Network::CARDANO) {
+        Ok(_) => Error::from_key_error(&Network::CARDANO, KeyError::PrivateKeyIsNotAvailable),
+        Err(_) => err
+      }
+    })
+  }
+
+  fn cardano_stake_key(&self, path: &DerivationPath) -> Result<Vec<u8>, Error> {
+    check_stake_path(path).map_err(|err| Error::from_key_error(&Network::CARDANO, err))?;
+    self.pub_key(&Network::CARDANO, path)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::byron;
+  use super::super::cbor;
+  use super::super::fixture::mnemonic_key;
+  use super::super::{
+    base_address, reward_address, transaction_hash, verify_data, Delegation, MasterKeyScheme,
+    NetworkId, Pointer, VoteRegistration, CATALYST_PURPOSE
+  };
+  use cryptoxide::blake2b::Blake2b;
+  use derivation_path::DerivationPath;
+  use key::Key;
+  use keychain::Keychain;
+  use network::Network;
+
+  // CIP-19 test vectors mnemonic. Payment key is m/1852'/1815'/0'/0/0 of it.
+  const MNEMONIC: &str = "test walk nut penalty hip pave soap entry language right filter choice";
+
+  fn keychain() -> Keychain {
+    Keychain::new(vec![mnemonic_key(MNEMONIC, MasterKeyScheme::Icarus).boxed()])
+  }
+
+  #[test]
+  fn shelley_addresses() {
+    let keychain = keychain();
+    let payment = DerivationPath::from("m/1852'/1815'/0'/0/0").unwrap();
+    let stake = DerivationPath::from("m/1852'/1815'/0'/2/0").unwrap();
+
+    let stake_key = keychain.pub_key(&Network::CARDANO, &stake).unwrap();
+    let payment_key = keychain.pub_key(&Network::CARDANO, &payment).unwrap();
+    assert_eq!(
+      keychain.cardano_base_address(&payment, &stake, NetworkId::Mainnet).unwrap(),
+      base_address(&payment_key, &stake_key, NetworkId::Mainnet).unwrap()
+    );
+    assert_eq!(
+      keychain.cardano_enterprise_address(&payment, NetworkId::Preprod).unwrap(),
+      "addr_test1vz2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzerspjrlsz"
+    );
+    let pointer = Pointer { slot: 2498243, tx_index: 27, cert_index: 3 };
+    assert_eq!(
+      keychain.cardano_pointer_address(&payment, &pointer, NetworkId::Mainnet).unwrap(),
+      "addr1gx2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzer5pnz75xxcrzqf96k"
+    );
+    assert_eq!(
+      keychain.cardano_reward_address(&stake, NetworkId::Preview).unwrap(),
+      reward_address(&stake_key, NetworkId::Preview).unwrap()
+    );
+
+    assert!(keychain.cardano_base_address(&stake, &payment, NetworkId::Mainnet).is_err());
+    let byron = DerivationPath::from("m/44'/1815'/0'/0/0").unwrap();
+    assert!(keychain.cardano_enterprise_address(&byron, NetworkId::Mainnet).is_err());
+  }
+
+  #[test]
+  fn icarus_byron_address() {
+    let keychain = keychain();
+    let path = DerivationPath::from("m/44'/1815'/0'/0/0").unwrap();
+
+    let address = keychain.cardano_byron_address(&path, NetworkId::Mainnet).unwrap();
+    assert_eq!(address, "Ae2tdPwUPEZ7sZ29n6td4jkbnK31Q2BdQQ2rrMGYAXeEjVznrixjc5PZ2rj");
+    assert_eq!(
+      keychain.cardano_byron_address(&path, NetworkId::Preprod).unwrap(),
+      "FHnt4NL7yPYBbqDP8zA6FjEnDsoFpMaMzvb7pUYGTvpDa5ZfU8AoytoidiCA6BQ"
+    );
+    assert_eq!(keychain.cardano_byron_address_path(&address).unwrap(), None);
+    let shelley = DerivationPath::from("m/1852'/1815'/0'/0/0").unwrap();
+    assert!(keychain.cardano_byron_address(&shelley, NetworkId::Mainnet).is_err());
+  }
+
+  #[test]
+  fn transaction_witnesses() {
+    let keychain = keychain();
+    let body = cbor::encode_map(&[(cbor::encode_uint(2), cbor::encode_uint(170000))]);
+    let hash = transaction_hash(&body).unwrap();
+
+    let payment = DerivationPath::from("m/1852'/1815'/0'/0/0").unwrap();
+    let stake = DerivationPath::from("m/1852'/1815'/0'/2/0").unwrap();
+    let witnesses = keychain.cardano_sign_transaction(&body, &[payment.clone(), stake]).unwrap();
+    assert_eq!(witnesses.len(), 2);
+    let payment_key = keychain.pub_key(&Network::CARDANO, &payment).unwrap();
+    assert_eq!(witnesses[0].vkey, &payment_key[..32]);
+    assert!(keychain.verify(&Network::CARDANO, &hash, &witnesses[0].signature, &payment).unwrap());
+    assert!(keychain.cardano_sign_transaction(&body[1..], &[payment]).is_err());
+
+    // Byron address is recalculated from bootstrap witness
+    let byron = DerivationPath::from("m/44'/1815'/0'/0/0").unwrap();
+    let witnesses =
+      keychain.cardano_sign_bootstrap_transaction(&body, &[byron.clone()], NetworkId::Preprod);
+    let witness = &witnesses.unwrap()[0];
+    let xpub = [&witness.vkey[..], &witness.chain_code[..]].concat();
+    assert_eq!(
+      byron::encode(&xpub, &witness.attributes).unwrap(),
+      keychain.cardano_byron_address(&byron, NetworkId::Preprod).unwrap()
+    );
+    assert!(keychain.verify(&Network::CARDANO, &hash, &witness.signature, &byron).unwrap());
+  }
+
+  #[test]
+  fn sign_data() {
+    let keychain = keychain();
+    let payment = DerivationPath::from("m/1852'/1815'/0'/0/0").unwrap();
+    let stake = DerivationPath::from("m/1852'/1815'/0'/2/0").unwrap();
+    let address = keychain.cardano_base_address(&payment, &stake, NetworkId::Mainnet).unwrap();
+
+    let signature = keychain.cardano_sign_data(&payment, &address, b"message").unwrap();
+    assert!(verify_data(&signature, &address, b"message").unwrap());
+    assert!(keychain.cardano_sign_data(&stake, &address, b"message").is_err());
+  }
+
+  #[test]
+  fn vote_registration() {
+    let keychain = keychain();
+    let vote = DerivationPath::from("m/1694'/1815'/0'/0/0").unwrap();
+    let stake = DerivationPath::from("m/1852'/1815'/0'/2/0").unwrap();
+    let payment = DerivationPath::from("m/1852'/1815'/0'/0/0").unwrap();
+
+    let vote_key = keychain.cardano_vote_key(&vote).unwrap();
+    assert_eq!(vote_key.len(), 32);
+    assert!(keychain.cardano_vote_key(&stake).is_err());
+
+    let registration = VoteRegistration {
+      delegations: vec![Delegation { vote_key, weight: 1 }],
+      payment_address: keychain.cardano_enterprise_address(&payment, NetworkId::Mainnet).unwrap(),
+      nonce: 74028450,
+      voting_purpose: CATALYST_PURPOSE
+    };
+    let metadata = keychain.cardano_sign_vote_registration(&registration, &stake).unwrap();
+
+    // Signature is checked against Blake2b-256 of {61284: registration}
+    let mut decoder = cbor::Decoder::new(&metadata);
+    assert_eq!(decoder.map().unwrap(), 2);
+    assert_eq!(decoder.uint().unwrap(), 61284);
+    let signed = cbor::encode_map(&[(cbor::encode_uint(61284), decoder.item().unwrap().to_vec())]);
+    assert_eq!(decoder.uint().unwrap(), 61285);
+    assert_eq!(decoder.map().unwrap(), 1);
+    assert_eq!(decoder.uint().unwrap(), 1);
+    let signature = decoder.bytes().unwrap();
+    let mut hash = [0u8; 32];
+    Blake2b::blake2b(&mut hash, &signed, &[]);
+    assert!(keychain.verify(&Network::CARDANO, &hash, signature, &stake).unwrap());
+
+    assert!(keychain.cardano_sign_vote_registration(&registration, &payment).is_err());
+  }
+}
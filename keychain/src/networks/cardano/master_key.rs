@@ -0,0 +1,202 @@
+use super::cbor;
+use super::key::KeyError;
+use cryptoxide::blake2b::Blake2b;
+use cryptoxide::digest::Digest;
+use cryptoxide::hmac::Hmac;
+use cryptoxide::mac::Mac;
+use cryptoxide::pbkdf2::pbkdf2;
+use cryptoxide::sha2::{Sha256, Sha512};
+use ed25519_bip32::XPRV_SIZE;
+use key::Error;
+use key_factory::KeySeed;
+
+const ICARUS_ITERATIONS: u32 = 4096;
+const LEDGER_HMAC_KEY: &[u8] = b"ed25519 seed";
+
+// CIP-3 root key generation schemes. Stored as the first byte of key data.
+#[derive(Primitive, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MasterKeyScheme {
+  // Pre CIP-3 keys of this library: SHA-512 of BIP39 seed. Not compatible with other wallets.
+  Legacy = 0,
+  // PBKDF2 over mnemonic entropy. Used by Yoroi, Daedalus (Shelley) and most light wallets.
+  Icarus = 1,
+  // SLIP-10 like HMAC over BIP39 seed. Used by Ledger hardware wallets.
+  Ledger = 2,
+  // Daedalus Byron wallets. Keys are derived with V1 scheme and random index paths.
+  Daedalus = 3
+}
+
+pub(super) fn generate(scheme: MasterKeyScheme, seed: &KeySeed) -> Result<[u8; XPRV_SIZE], Error> {
+  match scheme {
+    MasterKeyScheme::Legacy => Ok(legacy(&seed.seed)),
+    MasterKeyScheme::Icarus => entropy(scheme, seed).map(|ent| icarus(ent, &seed.passphrase)),
+    MasterKeyScheme::Ledger => Ok(ledger(&seed.seed)),
+    MasterKeyScheme::Daedalus => entropy(scheme, seed).map(daedalus)
+  }
+}
+
+fn entropy(scheme: MasterKeyScheme, seed: &KeySeed) -> Result<&[u8], Error> {
+  seed
+    .entropy
+    .as_deref()
+    .ok_or_else(|| Error::InvalidKeyData(Box::new(KeyError::EntropyIsNotAvailable(scheme))))
+}
+
+fn legacy(seed: &[u8]) -> [u8; XPRV_SIZE] {
+  let mut out = [0u8; XPRV_SIZE];
+
+  let mut hasher = Sha512::new();
+  hasher.input(&seed[0..32]);
+  hasher.result(&mut out[0..64]);
+  out[0] &= 248;
+  out[31] &= 63;
+  out[31] |= 64;
+  out[31] &= 0b1101_1111; // set 3rd highest bit to 0 as per the spec
+  out[64..96].clone_from_slice(&seed[32..64]);
+  out
+}
+
+fn icarus(entropy: &[u8], passphrase: &str) -> [u8; XPRV_SIZE] {
+  let mut out = [0u8; XPRV_SIZE];
+  let mut mac = Hmac::new(Sha512::new(), passphrase.as_bytes());
+  pbkdf2(&mut mac, entropy, ICARUS_ITERATIONS, &mut out);
+  out[0] &= 0b1111_1000;
+  out[31] &= 0b0001_1111;
+  out[31] |= 0b0100_0000;
+  out
+}
+
+fn ledger(seed: &[u8]) -> [u8; XPRV_SIZE] {
+  let mut out = [0u8; XPRV_SIZE];
+  let mut message = Vec::from(seed);
+  // Rehash until the third highest bit of the key is cleared
+  loop {
+    let mut mac = Hmac::new(Sha512::new(), LEDGER_HMAC_KEY);
+    mac.input(&message);
+    mac.raw_result(&mut out[0..64]);
+    if out[31] & 0b0010_0000 == 0 {
+      break;
+    }
+    message = Vec::from(&out[0..64]);
+  }
+  out[0] &= 0b1111_1000;
+  out[31] &= 0b0111_1111;
+  out[31] |= 0b0100_0000;
+
+  let mut mac = Hmac::new(Sha256::new(), LEDGER_HMAC_KEY);
+  mac.input(&[1]);
+  mac.input(seed);
+  mac.raw_result(&mut out[64..96]);
+  out
+}
+
+// Daedalus ignores passphrase. Root seed is CBOR of Blake2b-256 of CBOR encoded entropy.
+fn daedalus(entropy: &[u8]) -> [u8; XPRV_SIZE] {
+  let mut hash = [0u8; 32];
+  Blake2b::blake2b(&mut hash, &cbor::encode_bytes(entropy), &[]);
+  let seed = cbor::encode_bytes(&hash);
+
+  let mut out = [0u8; XPRV_SIZE];
+  let mut block = [0u8; 64];
+  let mut mac = Hmac::new(Sha512::new(), &seed);
+  for iteration in 1.. {
+    mac.reset();
+    mac.input(format!("Root Seed Chain {}", iteration).as_bytes());
+    mac.raw_result(&mut block);
+
+    let mut hasher = Sha512::new();
+    hasher.input(&block[0..32]);
+    hasher.result(&mut out[0..64]);
+    out[0] &= 0b1111_1000;
+    out[31] &= 0b0011_1111;
+    out[31] |= 0b0100_0000;
+    if out[31] & 0b0010_0000 == 0 {
+      break;
+    }
+  }
+  out[64..96].copy_from_slice(&block[32..64]);
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::key::Key;
+  use super::{generate, MasterKeyScheme};
+  use bip39::util::hex;
+  use key_factory::KeySeed;
+  use mnemonic::{entropy_from_mnemonic, seed_from_mnemonic, Language};
+
+  // CIP-3 test vectors
+  const MNEMONIC: &str =
+    "eight country switch draw meat scout mystery blade tip drift useless good keep usage title";
+
+  fn seed(passphrase: &str) -> KeySeed {
+    let entropy = entropy_from_mnemonic(MNEMONIC, Language::English).unwrap();
+    KeySeed { seed: Vec::new(), entropy: Some(entropy), passphrase: passphrase.to_owned() }
+  }
+
+  #[test]
+  fn icarus() {
+    assert_eq!(
+      hex::encode(&generate(MasterKeyScheme::Icarus, &seed("")).unwrap()),
+      "c065afd2832cd8b087c4d9ab7011f481ee1e0721e78ea5dd609f3ab3f156d245\
+       d176bd8fd4ec60b4731c3918a2a72a0226c0cd119ec35b47e4d55884667f552a\
+       23f7fdcd4a10c6cd2c7393ac61d877873e248f417634aa3d812af327ffe9d620"
+    );
+    assert_eq!(
+      hex::encode(&generate(MasterKeyScheme::Icarus, &seed("foo")).unwrap()),
+      "70531039904019351e1afb361cd1b312a4d0565d4ff9f8062d38acf4b15cce41\
+       d7b5738d9c893feea55512a3004acb0d222c35d3e3d5cde943a15a9824cbac59\
+       443cf67e589614076ba01e354b1a432e0e6db3b59e37fc56b5fb0222970a010e"
+    );
+    let mut no_entropy = seed("");
+    no_entropy.entropy = None;
+    assert!(generate(MasterKeyScheme::Icarus, &no_entropy).is_err());
+  }
+
+  // Root key of Byron scheme from CIP-3, checked against an independent implementation
+  #[test]
+  fn daedalus() {
+    let root = "f0b97ee4c028188e3a2af7f91cfcab2d7384a545686bca45c707fd33ffa37e5b\
+                1269a058ee416c8f2ec15a8df163d8b453d9967fb658815584a8ef0af3bad6e4\
+                e37d77efab7b2d1899ef1f9f2bb3b74e12be4e470fd22217e679ea614d96f84e";
+    assert_eq!(hex::encode(&generate(MasterKeyScheme::Daedalus, &seed("")).unwrap()), root);
+    assert_eq!(hex::encode(&generate(MasterKeyScheme::Daedalus, &seed("foo")).unwrap()), root);
+    let mut no_entropy = seed("");
+    no_entropy.entropy = None;
+    assert!(generate(MasterKeyScheme::Daedalus, &no_entropy).is_err());
+  }
+
+  #[test]
+  fn ledger() {
+    let mnemonic = "recall grace sport punch exhibit mad harbor stand obey short width stem \
+                    awkward used stairs wool ugly trap season stove worth toward congress jaguar";
+    let ledger = |passphrase: &str| {
+      let seed = seed_from_mnemonic(mnemonic, passphrase, 256, Language::English).unwrap();
+      let seed = KeySeed { seed, entropy: None, passphrase: passphrase.to_owned() };
+      hex::encode(&generate(MasterKeyScheme::Ledger, &seed).unwrap())
+    };
+    assert_eq!(
+      ledger(""),
+      "a08cf85b564ecf3b947d8d4321fb96d70ee7bb760877e371899b14e2ccf88658\
+       104b884682b57efd97decbb318a45c05a527b9cc5c2f64f7352935a049ceea60\
+       680d52308194ccef2a18e6812b452a5815fbd7f5babc083856919aaf668fe7e4"
+    );
+    assert_eq!(
+      ledger("foo"),
+      "488b13cdf56ed4ced9b2bcd61924ca7a81d317810d4ddbbd8ab3e86431289a58\
+       e0e3f635ab873a97f046c77bca4203419125293c9ab6d97a252a07353fdfa050\
+       55209a1e929b4223692cbf6900d41e8b42d14449ede56dbab8c4c7fd981e139a"
+    );
+  }
+
+  #[test]
+  fn unmarked_key_data_is_legacy() {
+    let data = generate(MasterKeyScheme::Icarus, &seed("")).unwrap();
+    let key = Key::from_data(&data).unwrap();
+    assert_eq!(key.scheme(), MasterKeyScheme::Legacy);
+    assert_eq!(key.data()[0], MasterKeyScheme::Legacy as u8);
+    assert_eq!(&key.data()[1..], &data[..]);
+    assert!(Key::from_data(&key.data()[1..95]).is_err());
+  }
+}
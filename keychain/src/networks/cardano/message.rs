@@ -0,0 +1,288 @@
+// CIP-8 message signing, as used by CIP-30 signData. Payload is bound to the address
+// through COSE_Sign1 protected header and signed as is, without hashing.
+
+use super::address::{address_bytes, key_hash, KEY_HASH_SIZE};
+use super::cbor::{self, Decoder};
+use super::key::Key;
+use cryptoxide::blake2b::Blake2b;
+use cryptoxide::ed25519;
+use derivation_path::DerivationPath;
+use key::{Error, Key as IKey};
+use std::fmt;
+
+// COSE header labels and values (RFC 8152)
+const ALGORITHM: i64 = 1;
+const EDDSA: i64 = -8;
+const ADDRESS: &str = "address";
+const HASHED: &str = "hashed";
+const SIGN1_TAG: u64 = 18;
+const SIGNATURE1_CONTEXT: &str = "Signature1";
+
+// COSE_Key labels and values for Ed25519 key
+const KEY_TYPE: i64 = 1;
+const OKP: i64 = 1;
+const KEY_ALGORITHM: i64 = 3;
+const CURVE: i64 = -1;
+const ED25519: i64 = 6;
+const X: i64 = -2;
+
+// ed25519 public key part of extended public key
+const PUBLIC_KEY_SIZE: usize = 32;
+
+// Address types with key hash payment credential, and reward address with stake key hash.
+// Signing key hash follows the address header.
+const KEY_HASH_ADDRESS_TYPES: [u8; 5] = [0b0000, 0b0010, 0b0100, 0b0110, 0b1110];
+
+#[derive(Debug)]
+pub enum MessageError {
+  UnsupportedAddress(u8),
+  KeyMismatch,
+  UnsupportedAlgorithm,
+  MissingHeader(&'static str)
+}
+
+impl fmt::Display for MessageError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      &MessageError::UnsupportedAddress(header) => {
+        write!(f, "Address type {} can't sign data", header >> 4)
+      }
+      &MessageError::KeyMismatch => write!(f, "Key doesn't match the address"),
+      &MessageError::UnsupportedAlgorithm => write!(f, "Unsupported COSE algorithm"),
+      &MessageError::MissingHeader(header) => write!(f, "COSE header {} is missing", header)
+    }
+  }
+}
+
+impl std::error::Error for MessageError {}
+
+impl From<MessageError> for Error {
+  fn from(err: MessageError) -> Self {
+    Error::InvalidData(Box::new(err))
+  }
+}
+
+// CIP-30 DataSignature: COSE_Sign1 and COSE_Key CBOR
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataSignature {
+  pub signature: Vec<u8>,
+  pub key: Vec<u8>
+}
+
+pub(super) fn sign_data(
+  key: &Key, path: &DerivationPath, address: &str, payload: &[u8]
+) -> Result<DataSignature, Error> {
+  // Byron addresses can't be used for data signing
+  let address = address_bytes(address)?;
+  let pub_key = key.pub_key(path)?;
+  check_signing_key(&address, &pub_key)?;
+
+  let protected = protected_header(&address);
+  let signature = key.sign(&sig_structure(&protected, payload), path)?;
+  let sign1 = cbor::encode_array(&[
+    cbor::encode_bytes(&protected),
+    cbor::encode_map(&[(cbor::encode_text(HASHED), cbor::encode_bool(false))]),
+    cbor::encode_bytes(payload),
+    cbor::encode_bytes(&signature)
+  ]);
+  Ok(DataSignature { signature: sign1, key: cose_key(&pub_key[..PUBLIC_KEY_SIZE]) })
+}
+
+// Checks that signature was made for the address and payload by the key of the address.
+// Payload may be detached from COSE_Sign1 or hashed with Blake2b-224.
+pub fn verify_data(
+  signature: &DataSignature, address: &str, payload: &[u8]
+) -> Result<bool, Error> {
+  let address = address_bytes(address)?;
+  let pub_key = cose_key_x(&signature.key)?;
+
+  let mut decoder = Decoder::new(&signature.signature);
+  // COSE_Sign1 may be tagged
+  if signature.signature.starts_with(&cbor::encode_tag(SIGN1_TAG, &[])) {
+    decoder.tag()?;
+  }
+  if decoder.array()? != 4 {
+    return Err(cbor::Error::InvalidLength(4).into());
+  }
+  let protected = decoder.bytes()?;
+  let hashed = unprotected_hashed(decoder.item()?)?;
+  let signed_payload = decoder.item()?;
+  let sig = decoder.bytes()?;
+  decoder.finish()?;
+
+  let signed_address = protected_address(protected)?;
+  let payload = if hashed {
+    let mut hash = [0u8; KEY_HASH_SIZE];
+    Blake2b::blake2b(&mut hash, payload, &[]);
+    Vec::from(&hash[..])
+  } else {
+    Vec::from(payload)
+  };
+  if signed_payload != &cbor::encode_null()[..]
+    && Decoder::new(signed_payload).bytes()? != &payload[..]
+  {
+    return Ok(false);
+  }
+  if signed_address != &address[..] || check_signing_key(&address, pub_key).is_err() {
+    return Ok(false);
+  }
+  if sig.len() != ed25519::SIGNATURE_LENGTH {
+    return Err(Error::InvalidSignatureSize(sig.len(), ed25519::SIGNATURE_LENGTH));
+  }
+  Ok(ed25519::verify(&sig_structure(protected, &payload), pub_key, sig))
+}
+
+fn check_signing_key(address: &[u8], pub_key: &[u8]) -> Result<(), Error> {
+  let header = *address.first().ok_or(MessageError::UnsupportedAddress(0xff))?;
+  if !KEY_HASH_ADDRESS_TYPES.contains(&(header >> 4)) || address.len() < KEY_HASH_SIZE + 1 {
+    return Err(MessageError::UnsupportedAddress(header).into());
+  }
+  if address[1..KEY_HASH_SIZE + 1] != key_hash(pub_key)? {
+    return Err(MessageError::KeyMismatch.into());
+  }
+  Ok(())
+}
+
+fn protected_header(address: &[u8]) -> Vec<u8> {
+  cbor::encode_map(&[
+    (cbor::encode_int(ALGORITHM), cbor::encode_int(EDDSA)),
+    (cbor::encode_text(ADDRESS), cbor::encode_bytes(address))
+  ])
+}
+
+fn protected_address(protected: &[u8]) -> Result<&[u8], Error> {
+  let mut decoder = Decoder::new(protected);
+  let mut address = None;
+  for _ in 0..decoder.map()? {
+    let label = decoder.item()?;
+    if label == &cbor::encode_int(ALGORITHM)[..] {
+      if decoder.item()? != &cbor::encode_int(EDDSA)[..] {
+        return Err(MessageError::UnsupportedAlgorithm.into());
+      }
+    } else if label == &cbor::encode_text(ADDRESS)[..] {
+      address = Some(decoder.bytes()?);
+    } else {
+      decoder.item()?;
+    }
+  }
+  decoder.finish()?;
+  address.ok_or_else(|| MessageError::MissingHeader(ADDRESS).into())
+}
+
+fn unprotected_hashed(unprotected: &[u8]) -> Result<bool, Error> {
+  let mut decoder = Decoder::new(unprotected);
+  let mut hashed = false;
+  for _ in 0..decoder.map()? {
+    let label = decoder.item()?;
+    let value = decoder.item()?;
+    if label == &cbor::encode_text(HASHED)[..] {
+      hashed = value == &cbor::encode_bool(true)[..];
+    }
+  }
+  Ok(hashed)
+}
+
+// Sig_structure of COSE_Sign1 with empty external data
+fn sig_structure(protected: &[u8], payload: &[u8]) -> Vec<u8> {
+  cbor::encode_array(&[
+    cbor::encode_text(SIGNATURE1_CONTEXT),
+    cbor::encode_bytes(protected),
+    cbor::encode_bytes(&[]),
+    cbor::encode_bytes(payload)
+  ])
+}
+
+fn cose_key(pub_key: &[u8]) -> Vec<u8> {
+  cbor::encode_map(&[
+    (cbor::encode_int(KEY_TYPE), cbor::encode_int(OKP)),
+    (cbor::encode_int(KEY_ALGORITHM), cbor::encode_int(EDDSA)),
+    (cbor::encode_int(CURVE), cbor::encode_int(ED25519)),
+    (cbor::encode_int(X), cbor::encode_bytes(pub_key))
+  ])
+}
+
+fn cose_key_x(key: &[u8]) -> Result<&[u8], Error> {
+  let mut decoder = Decoder::new(key);
+  let mut x = None;
+  for _ in 0..decoder.map()? {
+    let label = decoder.item()?;
+    if label == &cbor::encode_int(X)[..] {
+      x = Some(decoder.bytes()?);
+    } else if label == &cbor::encode_int(CURVE)[..] {
+      if decoder.item()? != &cbor::encode_int(ED25519)[..] {
+        return Err(MessageError::UnsupportedAlgorithm.into());
+      }
+    } else {
+      decoder.item()?;
+    }
+  }
+  decoder.finish()?;
+  let x = x.ok_or(MessageError::MissingHeader("x"))?;
+  if x.len() != PUBLIC_KEY_SIZE {
+    return Err(Error::InvalidKeySize(x.len(), PUBLIC_KEY_SIZE));
+  }
+  Ok(x)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::address::{enterprise_address, reward_address, NetworkId};
+  use super::super::fixture::key;
+  use super::{sign_data, verify_data};
+  use bip39::util::hex;
+  use derivation_path::DerivationPath;
+  use key::Key as IKey;
+
+  #[test]
+  fn sign_and_verify() {
+    let key = key();
+    let path = DerivationPath::from("m/1852'/1815'/0'/0/0").unwrap();
+    let pub_key = key.pub_key(&path).unwrap();
+    let address = enterprise_address(&pub_key, NetworkId::Mainnet).unwrap();
+
+    let signed = sign_data(&key, &path, &address, b"hello").unwrap();
+    assert_eq!(
+      hex::encode(&signed.key),
+      format!("a4010103272006215820{}", hex::encode(&pub_key[..32]))
+    );
+    // [protected {1: -8, "address": h'61..'}, {"hashed": false}, h'hello', signature]
+    assert!(hex::encode(&signed.signature).starts_with("84582aa201276761646472657373581d61"));
+    assert!(verify_data(&signed, &address, b"hello").unwrap());
+    assert!(!verify_data(&signed, &address, b"hellO").unwrap());
+
+    let other = DerivationPath::from("m/1852'/1815'/0'/0/1").unwrap();
+    let other_address = enterprise_address(&key.pub_key(&other).unwrap(), NetworkId::Mainnet);
+    assert!(!verify_data(&signed, &other_address.unwrap(), b"hello").unwrap());
+    assert!(sign_data(&key, &other, &address, b"hello").is_err());
+  }
+
+  #[test]
+  fn stake_key() {
+    let key = key();
+    let path = DerivationPath::from("m/1852'/1815'/0'/2/0").unwrap();
+    let address = reward_address(&key.pub_key(&path).unwrap(), NetworkId::Preprod).unwrap();
+    let signed = sign_data(&key, &path, &address, &[]).unwrap();
+    assert!(verify_data(&signed, &address, &[]).unwrap());
+    assert!(sign_data(&key, &path, "Ae2tdPwUPEZ", &[]).is_err());
+  }
+
+  #[test]
+  fn malformed_cbor() {
+    let key = key();
+    let path = DerivationPath::from("m/1852'/1815'/0'/0/0").unwrap();
+    let address = enterprise_address(&key.pub_key(&path).unwrap(), NetworkId::Mainnet).unwrap();
+    let signed = sign_data(&key, &path, &address, b"hello").unwrap();
+
+    // unprotected header {0: [[[...]]]} nested deeper than decoder allows
+    let mut unprotected = vec![0xa1, 0x00];
+    unprotected.extend(vec![0x81; 200000]);
+    unprotected.push(0x00);
+    let mut nested = signed.clone();
+    nested.signature = [&signed.signature[..45], &unprotected[..]].concat();
+    assert!(verify_data(&nested, &address, b"hello").is_err());
+
+    let mut huge = signed.clone();
+    huge.key = hex::decode("bbffffffffffffffff").unwrap();
+    assert!(verify_data(&huge, &address, b"hello").is_err());
+  }
+}
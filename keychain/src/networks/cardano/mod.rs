@@ -1,6 +1,15 @@
+mod address;
+mod byron;
+mod cbor;
 mod factory;
 mod key;
 mod key_path;
+mod keychain;
+mod master_key;
+mod message;
+mod transaction;
+mod vote;
+mod watch_only;
 
 use network::Network;
 
@@ -8,6 +17,40 @@ impl Network {
   pub const CARDANO: Network = Network(key_path::BIP44_COIN_TYPE);
 }
 
+pub use self::address::{
+  base_address, enterprise_address, key_hash, pointer_address, reward_address, NetworkId, Pointer
+};
+pub use self::byron::icarus_address;
 pub use self::factory::KeyFactory;
 pub use self::key::KeyError as PrivateKeyError;
-pub use self::key_path::KeyPath;
+pub use self::key_path::{KeyPath, Role, CIP1852_PURPOSE, CIP36_PURPOSE};
+pub use self::master_key::MasterKeyScheme;
+pub use self::message::{verify_data, DataSignature};
+pub use self::transaction::{transaction_hash, witness_set, BootstrapWitness, VKeyWitness};
+pub use self::vote::{
+  Delegation, VoteRegistration, CATALYST_PURPOSE, REGISTRATION_LABEL, SIGNATURE_LABEL
+};
+
+#[cfg(test)]
+mod fixture {
+  use super::key::Key;
+  use super::master_key::MasterKeyScheme;
+  use key_factory::KeySeed;
+  use mnemonic::{entropy_from_mnemonic, seed_from_mnemonic, Language};
+
+  pub const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon \
+                              abandon abandon abandon about";
+
+  // Master key of 12 words mnemonic generated with the scheme
+  pub fn mnemonic_key(mnemonic: &str, scheme: MasterKeyScheme) -> Key {
+    let seed = seed_from_mnemonic(mnemonic, "", 128, Language::English).unwrap();
+    let entropy = entropy_from_mnemonic(mnemonic, Language::English).unwrap();
+    let seed = KeySeed { seed, entropy: Some(entropy), passphrase: String::new() };
+    Key::from_data(&Key::data_from_seed(&seed, scheme).unwrap()).unwrap()
+  }
+
+  // Legacy master key of the BIP39 test vectors mnemonic
+  pub fn key() -> Key {
+    mnemonic_key(MNEMONIC, MasterKeyScheme::Legacy)
+  }
+}
@@ -0,0 +1,131 @@
+use super::cbor::{self, Decoder};
+use cryptoxide::blake2b::Blake2b;
+use key::Error;
+
+pub const TRANSACTION_HASH_SIZE: usize = 32;
+
+// Transaction witness set keys
+const VKEY_WITNESSES: u64 = 0;
+const BOOTSTRAP_WITNESSES: u64 = 2;
+
+// ed25519 public key part of extended public key, chain code follows it
+const VKEY_SIZE: usize = 32;
+
+// Blake2b-256 of transaction body bytes exactly as they were encoded.
+// Body is never re-encoded, as any change of encoding changes transaction id.
+pub fn transaction_hash(body: &[u8]) -> Result<[u8; TRANSACTION_HASH_SIZE], Error> {
+  let mut decoder = Decoder::new(body);
+  decoder.item()?;
+  decoder.finish()?;
+  Decoder::new(body).map_len()?;
+  let mut hash = [0u8; TRANSACTION_HASH_SIZE];
+  Blake2b::blake2b(&mut hash, body, &[]);
+  Ok(hash)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VKeyWitness {
+  pub vkey: Vec<u8>,
+  pub signature: Vec<u8>
+}
+
+impl VKeyWitness {
+  pub(super) fn new(xpub: &[u8], signature: Vec<u8>) -> Self {
+    Self { vkey: Vec::from(&xpub[..VKEY_SIZE]), signature }
+  }
+
+  pub fn to_cbor(&self) -> Vec<u8> {
+    cbor::encode_array(&[cbor::encode_bytes(&self.vkey), cbor::encode_bytes(&self.signature)])
+  }
+}
+
+// Witness of Byron address input. Address root is recalculated from key, chain code and
+// address attributes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BootstrapWitness {
+  pub vkey: Vec<u8>,
+  pub signature: Vec<u8>,
+  pub chain_code: Vec<u8>,
+  pub attributes: Vec<u8>
+}
+
+impl BootstrapWitness {
+  pub(super) fn new(xpub: &[u8], signature: Vec<u8>, attributes: Vec<u8>) -> Self {
+    Self {
+      vkey: Vec::from(&xpub[..VKEY_SIZE]),
+      signature,
+      chain_code: Vec::from(&xpub[VKEY_SIZE..]),
+      attributes
+    }
+  }
+
+  pub fn to_cbor(&self) -> Vec<u8> {
+    cbor::encode_array(&[
+      cbor::encode_bytes(&self.vkey),
+      cbor::encode_bytes(&self.signature),
+      cbor::encode_bytes(&self.chain_code),
+      cbor::encode_bytes(&self.attributes)
+    ])
+  }
+}
+
+// Transaction witness set map. Empty witness lists are omitted.
+pub fn witness_set(vkeys: &[VKeyWitness], bootstraps: &[BootstrapWitness]) -> Vec<u8> {
+  let mut entries = Vec::new();
+  if !vkeys.is_empty() {
+    let witnesses: Vec<Vec<u8>> = vkeys.iter().map(|witness| witness.to_cbor()).collect();
+    entries.push((cbor::encode_uint(VKEY_WITNESSES), cbor::encode_array(&witnesses)));
+  }
+  if !bootstraps.is_empty() {
+    let witnesses: Vec<Vec<u8>> = bootstraps.iter().map(|witness| witness.to_cbor()).collect();
+    entries.push((cbor::encode_uint(BOOTSTRAP_WITNESSES), cbor::encode_array(&witnesses)));
+  }
+  cbor::encode_map(&entries)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{transaction_hash, witness_set, VKeyWitness};
+  use bip39::util::hex;
+
+  // Body with one input, one output, fee and ttl
+  const BODY: &str = "a400818258200000000000000000000000000000000000000000000000000000000000000000\
+                      00018182581d61000000000000000000000000000000000000000000000000000000001a000f\
+                      4240021a0002917d031a00ba3b4d";
+
+  #[test]
+  fn hash() {
+    let body = hex::decode(BODY).unwrap();
+    assert_eq!(
+      hex::encode(&transaction_hash(&body).unwrap()),
+      "16308bb90e62ceac6ff84be556dea19611ff9c0d98e19d9d30bf3e353e41e287"
+    );
+    assert!(transaction_hash(&body[..body.len() - 1]).is_err());
+    assert!(transaction_hash(&[&body[..], &[0]].concat()).is_err());
+    assert!(transaction_hash(&hex::decode("8100").unwrap()).is_err());
+  }
+
+  #[test]
+  fn hash_encodings() {
+    let body = hex::decode(BODY).unwrap();
+    let indefinite = [&[0xbf], &body[1..], &[0xff]].concat();
+    assert_eq!(
+      hex::encode(&transaction_hash(&indefinite).unwrap()),
+      "eb9a95fc93a0e3a96e4bc0b24486dbf98301667690ef9468ca5f2f8673e3a14d"
+    );
+    let mut nested = vec![0xa1, 0x00];
+    nested.extend(vec![0x81; 200000]);
+    nested.push(0x00);
+    assert!(transaction_hash(&nested).is_err());
+    assert!(transaction_hash(&hex::decode("bbffffffffffffffff").unwrap()).is_err());
+  }
+
+  #[test]
+  fn witnesses() {
+    let witness = VKeyWitness { vkey: vec![1; 32], signature: vec![2; 64] };
+    let set = hex::encode(&witness_set(&[witness], &[]));
+    assert!(set.starts_with("a100818258200101"));
+    assert!(set.ends_with(&format!("5840{}", "02".repeat(64))));
+    assert_eq!(hex::encode(&witness_set(&[], &[])), "a0");
+  }
+}
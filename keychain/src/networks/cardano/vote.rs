@@ -0,0 +1,197 @@
+// CIP-36 vote key registration. Registration is stored in transaction metadata and signed
+// by stake key, so voting power of the stake is delegated to the vote keys.
+
+use super::address::address_bytes;
+use super::cbor;
+use super::key::check_address_path;
+use super::key_path::CIP36_PURPOSE;
+use cryptoxide::blake2b::Blake2b;
+use derivation_path::DerivationPath;
+use ed25519_bip32::XPUB_SIZE;
+use key::Error;
+use key_path::Error as KPError;
+
+pub const REGISTRATION_LABEL: u64 = 61284;
+pub const SIGNATURE_LABEL: u64 = 61285;
+
+pub const REGISTRATION_HASH_SIZE: usize = 32;
+
+// Catalyst voting purpose
+pub const CATALYST_PURPOSE: u64 = 0;
+
+// ed25519 public key part of extended public key
+const PUBLIC_KEY_SIZE: usize = 32;
+
+// Registration map keys
+const DELEGATIONS: u64 = 1;
+const STAKE_KEY: u64 = 2;
+const PAYMENT_ADDRESS: u64 = 3;
+const NONCE: u64 = 4;
+const VOTING_PURPOSE: u64 = 5;
+const SIGNATURE: u64 = 1;
+
+// Share of voting power for the vote key. Vote key can be owned by another wallet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Delegation {
+  pub vote_key: Vec<u8>,
+  pub weight: u32
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VoteRegistration {
+  pub delegations: Vec<Delegation>,
+  // Shelley address for voting rewards
+  pub payment_address: String,
+  // Should increase with each registration, current slot number is used usually
+  pub nonce: u64,
+  pub voting_purpose: u64
+}
+
+impl VoteRegistration {
+  // Registration map, stored under 61284 metadata label
+  pub(super) fn to_cbor(&self, stake_key: &[u8]) -> Result<Vec<u8>, Error> {
+    let delegations = self
+      .delegations
+      .iter()
+      .map(|delegation| {
+        Ok(cbor::encode_array(&[
+          cbor::encode_bytes(public_key(&delegation.vote_key)?),
+          cbor::encode_uint(delegation.weight as u64)
+        ]))
+      })
+      .collect::<Result<Vec<Vec<u8>>, Error>>()?;
+    let payment_address = address_bytes(&self.payment_address)?;
+    Ok(cbor::encode_map(&[
+      (cbor::encode_uint(DELEGATIONS), cbor::encode_array(&delegations)),
+      (cbor::encode_uint(STAKE_KEY), cbor::encode_bytes(public_key(stake_key)?)),
+      (cbor::encode_uint(PAYMENT_ADDRESS), cbor::encode_bytes(&payment_address)),
+      (cbor::encode_uint(NONCE), cbor::encode_uint(self.nonce)),
+      (cbor::encode_uint(VOTING_PURPOSE), cbor::encode_uint(self.voting_purpose))
+    ]))
+  }
+}
+
+// Blake2b-256 hash of {61284: registration} metadata, which is signed by stake key
+pub(super) fn registration_hash(registration: &[u8]) -> [u8; REGISTRATION_HASH_SIZE] {
+  let metadata =
+    cbor::encode_map(&[(cbor::encode_uint(REGISTRATION_LABEL), Vec::from(registration))]);
+  let mut hash = [0u8; REGISTRATION_HASH_SIZE];
+  Blake2b::blake2b(&mut hash, &metadata, &[]);
+  hash
+}
+
+// Transaction metadata {61284: registration, 61285: {1: signature}}
+pub(super) fn registration_metadata(registration: &[u8], signature: &[u8]) -> Vec<u8> {
+  let witness = cbor::encode_map(&[(cbor::encode_uint(SIGNATURE), cbor::encode_bytes(signature))]);
+  cbor::encode_map(&[
+    (cbor::encode_uint(REGISTRATION_LABEL), Vec::from(registration)),
+    (cbor::encode_uint(SIGNATURE_LABEL), witness)
+  ])
+}
+
+// Vote keys are m/1694'/1815'/account'/0/index
+pub(super) fn check_vote_path(path: &DerivationPath) -> Result<(), Error> {
+  check_address_path(path)?;
+  if path.path()[0] != CIP36_PURPOSE {
+    return Err(KPError::InvalidPurpose(path.path()[0], CIP36_PURPOSE).into());
+  }
+  Ok(())
+}
+
+// Extended keys are accepted, chain code is ignored
+fn public_key(key: &[u8]) -> Result<&[u8], Error> {
+  if key.len() != PUBLIC_KEY_SIZE && key.len() != XPUB_SIZE {
+    return Err(Error::InvalidKeySize(key.len(), PUBLIC_KEY_SIZE));
+  }
+  Ok(&key[..PUBLIC_KEY_SIZE])
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{registration_hash, registration_metadata, Delegation, VoteRegistration};
+  use bip39::util::hex;
+  use cryptoxide::ed25519;
+
+  // CIP-36 test vector: stake signing key, two vote key delegations, payment address,
+  // nonce 1234 and Catalyst purpose. Signature is deterministic ed25519 of registration hash.
+  const STAKE_KEY: &str = "f5beaeff7932a4164d270afde7716067582412e8977e67986cd9b456fc082e3a";
+  const VOTE_KEYS: [&str; 2] = [
+    "a6a3c0447aeb9cc54cf6422ba32b294e5e1c3ef6d782f2acff4a70694c4d1663",
+    "00588e8e1d18cba576a4d35758069fe94e53f638b6faf7c07b8abd2bc5c5cdee"
+  ];
+  const PAYMENT_ADDRESS: &str =
+    "addr_test1qprhw4s70k0vzyhvxp6h97hvrtlkrlcvlmtgmaxdtjz87xrjkctk27yp\
+                                 uv9dzlzxusqse89naweygpjn5dxnygvus05sdq9h07";
+  const REGISTRATION: &str = "a50182825820a6a3c0447aeb9cc54cf6422ba32b294e5e1c3ef6d782f2acff4a706\
+                              94c4d16630182582000588e8e1d18cba576a4d35758069fe94e53f638b6faf7c07b\
+                              8abd2bc5c5cdee0302582086870efc99c453a873a16492ce87738ec79a0ebd06437\
+                              9a62e2c9cf4e119219e035839004777561e7d9ec112ec307572faec1aff61ff0cfe\
+                              d68df4cd5c847f1872b617657881e30ad17c46e4010c9cb3ebb2440653a34d32219\
+                              c83e9041904d20500";
+  const SIGNATURE: &str = "9a538012275eb442b8d7d5ff25647388948569911d6c1de6cc88647649595e3980f5c\
+                           bd3197a12b49b2f03f2f85f531ac60814d5d3bbdc5323960401f44cde08";
+
+  const ADDRESS: &str = "addr1vx2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzers66hrl8";
+
+  fn registration() -> VoteRegistration {
+    VoteRegistration {
+      delegations: vec![
+        Delegation { vote_key: vec![1; 32], weight: 1 },
+        Delegation { vote_key: vec![2; 64], weight: 3 },
+      ],
+      payment_address: ADDRESS.to_owned(),
+      nonce: 1234,
+      voting_purpose: 0
+    }
+  }
+
+  #[test]
+  fn registration_cbor() {
+    let (secret, public) = ed25519::keypair(&hex::decode(STAKE_KEY).unwrap());
+    assert_eq!(
+      hex::encode(&public),
+      "86870efc99c453a873a16492ce87738ec79a0ebd064379a62e2c9cf4e119219e"
+    );
+    let registration = VoteRegistration {
+      delegations: vec![
+        Delegation { vote_key: hex::decode(VOTE_KEYS[0]).unwrap(), weight: 1 },
+        Delegation { vote_key: hex::decode(VOTE_KEYS[1]).unwrap(), weight: 3 },
+      ],
+      payment_address: PAYMENT_ADDRESS.to_owned(),
+      nonce: 1234,
+      voting_purpose: 0
+    };
+    let cbor = registration.to_cbor(&public).unwrap();
+    assert_eq!(hex::encode(&cbor), REGISTRATION);
+
+    let hash = registration_hash(&cbor);
+    assert_eq!(
+      hex::encode(&hash),
+      "2d441a69fc00874906512ec9ee617aeab9b10fbf05d43662c7b180a459fbe694"
+    );
+    let signature = ed25519::signature(&hash, &secret);
+    assert_eq!(hex::encode(&signature[..]), SIGNATURE);
+    assert_eq!(
+      hex::encode(&registration_metadata(&cbor, &signature)),
+      format!("a219ef64{}19ef65a1015840{}", REGISTRATION, SIGNATURE)
+    );
+  }
+
+  #[test]
+  fn invalid_registration() {
+    let mut invalid = registration();
+    invalid.delegations[0].vote_key.truncate(31);
+    assert!(invalid.to_cbor(&[3; 32]).is_err());
+    assert!(registration().to_cbor(&[3; 31]).is_err());
+    assert!(registration().to_cbor(&[3; 33]).is_err());
+    assert!(registration().to_cbor(&[3; 64]).is_ok());
+    invalid = registration();
+    invalid.delegations[1].vote_key.push(2);
+    assert!(invalid.to_cbor(&[3; 32]).is_err());
+    invalid.delegations[1].vote_key.truncate(63);
+    assert!(invalid.to_cbor(&[3; 32]).is_err());
+    invalid = registration();
+    invalid.payment_address = "Ae2tdPwUPEZ".to_owned();
+    assert!(invalid.to_cbor(&[3; 32]).is_err());
+  }
+}
@@ -0,0 +1,121 @@
+use super::key::{
+  check_account_path, check_address_path, signature_from_bytes, KeyError, ACCOUNT_XPUB_HRP
+};
+use bech32::{self, FromBase32, ToBase32, Variant};
+use derivation_path::DerivationPath;
+use ed25519_bip32::{DerivationScheme, XPub, XPUB_SIZE};
+use key::{Error, Key as IKey};
+use key_path::{Error as KPError, ACCOUNT_DEPTH};
+use network::Network;
+use std::any::Any;
+
+const D_SCHEME: DerivationScheme = DerivationScheme::V2;
+
+pub struct WatchOnlyKey {
+  xpub: XPub,
+  account: DerivationPath
+}
+
+impl WatchOnlyKey {
+  // Account index isn't stored in ed25519-bip32 public key, so it's taken from the path
+  pub fn from_account_xpub(path: &DerivationPath, xpub: &str) -> Result<Self, Error> {
+    check_account_path(path)?;
+    let (hrp, data, _) =
+      bech32::decode(xpub).map_err(|err| Error::InvalidKeyData(Box::new(err)))?;
+    if hrp != ACCOUNT_XPUB_HRP {
+      return Err(Error::InvalidKeyData(Box::new(KeyError::InvalidPrefix(hrp))));
+    }
+    let bytes =
+      Vec::<u8>::from_base32(&data).map_err(|err| Error::InvalidKeyData(Box::new(err)))?;
+    if bytes.len() != XPUB_SIZE {
+      return Err(Error::InvalidKeySize(bytes.len(), XPUB_SIZE));
+    }
+    let xpub = XPub::from_slice(&bytes)
+      .map_err(|_| Error::InvalidKeyData(Box::new(KeyError::LengthInvalid(bytes.len()))))?;
+    Ok(Self { xpub, account: path.prefix(ACCOUNT_DEPTH) })
+  }
+
+  // 64 bytes of ed25519-bip32 account public key with chain code
+  pub fn account_pub_key(&self, path: &DerivationPath) -> Result<Vec<u8>, Error> {
+    self.check_account(path).map(|_| Vec::from(self.xpub.as_ref()))
+  }
+
+  fn check_account(&self, path: &DerivationPath) -> Result<(), Error> {
+    check_account_path(path)?;
+    if !path.starts_with(&self.account) {
+      return Err(KPError::InvalidAccount(path.path()[2]).into());
+    }
+    Ok(())
+  }
+
+  fn derive_public(&self, path: &DerivationPath) -> Result<XPub, Error> {
+    check_address_path(path)?;
+    self.check_account(path)?;
+    path.path()[ACCOUNT_DEPTH..]
+      .iter()
+      .try_fold(self.xpub, |key, &index| key.derive(D_SCHEME, index))
+      .map_err(|_| Error::InvalidKeyData(Box::new(KeyError::PublicDerivationFailed)))
+  }
+}
+
+impl IKey for WatchOnlyKey {
+  fn network(&self) -> Network {
+    Network::CARDANO
+  }
+
+  fn pub_key(&self, path: &DerivationPath) -> Result<Vec<u8>, Error> {
+    self.derive_public(path).map(|pk| Vec::from(pk.as_ref()))
+  }
+
+  fn address(&self, _: &DerivationPath) -> Result<String, Error> {
+    Err(Error::OperationIsNotSupported("address"))
+  }
+
+  fn account_xpub(&self, path: &DerivationPath) -> Result<String, Error> {
+    let xpub = self.account_pub_key(path)?;
+    bech32::encode(ACCOUNT_XPUB_HRP, xpub.to_base32(), Variant::Bech32)
+      .map_err(|err| Error::InvalidKeyData(Box::new(err)))
+  }
+
+  fn sign(&self, _: &[u8], _: &DerivationPath) -> Result<Vec<u8>, Error> {
+    Err(Error::PrivateKeyIsNotAvailable)
+  }
+
+  fn verify(&self, data: &[u8], signature: &[u8], path: &DerivationPath) -> Result<bool, Error> {
+    let signature = signature_from_bytes(signature)?;
+    self.derive_public(path).map(|pk| pk.verify(data, &signature))
+  }
+
+  fn as_any(&self) -> Option<&dyn Any> {
+    Some(self)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::fixture::key;
+  use super::WatchOnlyKey;
+  use derivation_path::DerivationPath;
+  use key::{Error, Key as IKey};
+
+  #[test]
+  fn watch_only_matches_full_key() {
+    let key = key();
+
+    let path = DerivationPath::from("m/1852'/1815'/0'/2/0").unwrap();
+    let xpub = key.account_xpub(&path).unwrap();
+    let watch = WatchOnlyKey::from_account_xpub(&path, &xpub).unwrap();
+
+    assert_eq!(watch.pub_key(&path).unwrap(), key.pub_key(&path).unwrap());
+    assert_eq!(watch.account_xpub(&path).unwrap(), xpub);
+    assert!(watch.pub_key(&DerivationPath::from("m/1852'/1815'/1'/0/0").unwrap()).is_err());
+    assert!(watch.pub_key(&DerivationPath::from("m/44'/1815'/0'/0/0").unwrap()).is_err());
+
+    let signature = key.sign(b"message", &path).unwrap();
+    assert!(watch.verify(b"message", &signature, &path).unwrap());
+    match watch.sign(b"message", &path) {
+      Err(Error::PrivateKeyIsNotAvailable) => {}
+      _ => panic!("watch-only key should not sign")
+    }
+  }
+}
@@ -0,0 +1,91 @@
+use bip39::util::hex;
+use key::Error;
+
+use secp256k1_bip32::hash::keccak256;
+
+pub const ADDRESS_SIZE: usize = 20;
+
+// 0x04 prefix followed by X and Y coordinates
+const UNCOMPRESSED_KEY_SIZE: usize = 65;
+
+// Last 20 bytes of Keccak-256 hash of 64 bytes of uncompressed public key
+pub fn address(pub_key: &[u8]) -> Result<[u8; ADDRESS_SIZE], Error> {
+  if pub_key.len() != UNCOMPRESSED_KEY_SIZE {
+    return Err(Error::InvalidKeySize(pub_key.len(), UNCOMPRESSED_KEY_SIZE));
+  }
+  let hash = keccak256(&pub_key[1..]);
+  let mut address = [0u8; ADDRESS_SIZE];
+  address.copy_from_slice(&hash[hash.len() - ADDRESS_SIZE..]);
+  Ok(address)
+}
+
+// EIP-55 mixed-case checksum encoding. With chain id it is EIP-1191 chain-specific checksum.
+pub fn checksum(address: &[u8; ADDRESS_SIZE], chain_id: Option<u64>) -> String {
+  let lower = hex::encode(address);
+  let hash = match chain_id {
+    Some(chain_id) => keccak256(format!("{}0x{}", chain_id, lower).as_bytes()),
+    None => keccak256(lower.as_bytes())
+  };
+  let checksummed: String = lower
+    .chars()
+    .enumerate()
+    .map(|(index, ch)| {
+      let nibble = (hash[index / 2] >> (if index % 2 == 0 { 4 } else { 0 })) & 0x0f;
+      if nibble >= 8 {
+        ch.to_ascii_uppercase()
+      } else {
+        ch
+      }
+    })
+    .collect();
+  format!("0x{}", checksummed)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::fixture::key;
+  use super::{checksum, ADDRESS_SIZE};
+  use bip39::util::hex;
+  use derivation_path::DerivationPath;
+  use key::Key as IKey;
+
+  const ADDRESSES: [&str; 4] = [
+    "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+    "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+    "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+    "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb"
+  ];
+
+  fn raw(address: &str) -> [u8; ADDRESS_SIZE] {
+    let mut raw = [0u8; ADDRESS_SIZE];
+    raw.copy_from_slice(&hex::decode(&address[2..].to_lowercase()).unwrap());
+    raw
+  }
+
+  #[test]
+  fn eip55() {
+    for address in ADDRESSES.iter() {
+      assert_eq!(&checksum(&raw(address), None), address);
+    }
+  }
+
+  #[test]
+  fn eip1191() {
+    let rsk = [
+      "0x5aaEB6053f3e94c9b9a09f33669435E7ef1bEAeD",
+      "0xFb6916095cA1Df60bb79ce92cE3EA74c37c5d359",
+      "0xDBF03B407c01E7CD3cBea99509D93F8Dddc8C6FB",
+      "0xD1220A0Cf47c7B9BE7a2e6ba89F429762E7B9adB"
+    ];
+    for (address, expected) in ADDRESSES.iter().zip(rsk.iter()) {
+      assert_eq!(&checksum(&raw(address), Some(30)), expected);
+    }
+  }
+
+  #[test]
+  fn key_address() {
+    let key = key();
+    let path = DerivationPath::from("m/44'/60'/0'/0/0").unwrap();
+    assert_eq!(key.address(&path).unwrap(), "0x9858EfFD232B4033E47d90003D41EC34EcaEda94");
+  }
+}
@@ -1,5 +1,7 @@
 use super::key::Key;
+use super::watch_only::WatchOnlyKey;
 use bip39::Seed;
+use derivation_path::DerivationPath;
 use key::{Error as KeyError, Key as IKey};
 use key_factory::{KeyFactory as IKeyFactory, SeedSize};
 use network::Network;
@@ -29,4 +31,10 @@ impl IKeyFactory for KeyFactory {
     let seed = Seed::from_slice(seed).map_err(|err| KeyError::InvalidMnemonic(err.into()))?;
     Key::data_from_seed(&seed)
   }
+
+  fn key_from_account_xpub(
+    &self, path: &DerivationPath, xpub: &str
+  ) -> Result<Box<dyn IKey>, KeyError> {
+    WatchOnlyKey::from_account_xpub(path, xpub).map(|pk| -> Box<dyn IKey> { pk.boxed() })
+  }
 }
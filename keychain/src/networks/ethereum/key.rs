@@ -1,10 +1,14 @@
+use super::address::{address, checksum};
 use super::key_path::BIP44_COIN_TYPE;
+use super::message::{encode_v, split_signature, RecoveryIdFormat};
 use bip39;
+use derivation_path::DerivationPath;
 use key::{Error, Key as IKey};
-use key_path::{Error as KPError, KeyPath, BIP44_PURPOSE, BIP44_SOFT_UPPER_BOUND};
+use key_path::{Error as KPError, ACCOUNT_DEPTH, ADDRESS_DEPTH, BIP44_PURPOSE};
 use network::Network;
+use std::any::Any;
 
-use secp256k1_bip32::XPrv;
+use secp256k1_bip32::{Version, XPrv, XPub};
 
 pub struct Key {
   xprv: XPrv
@@ -24,45 +28,105 @@ impl Key {
     Ok(xprv.serialize())
   }
 
-  fn derive_private(&self, path: &dyn KeyPath) -> Result<XPrv, Error> {
-    if path.purpose() != BIP44_PURPOSE {
-      return Err(KPError::InvalidPurpose(path.purpose(), BIP44_PURPOSE).into());
-    }
-    if path.coin() != BIP44_COIN_TYPE {
-      return Err(KPError::InvalidCoin(path.coin(), BIP44_COIN_TYPE).into());
-    }
-    if path.account() < BIP44_SOFT_UPPER_BOUND {
-      return Err(KPError::InvalidAccount(path.account()).into());
-    }
-    if path.change() != 0 && path.change() != 1 {
-      return Err(KPError::InvalidChange(path.change()).into());
-    }
-    if path.address() >= BIP44_SOFT_UPPER_BOUND {
-      return Err(KPError::InvalidAddress(path.address()).into());
-    }
+  fn derive_account(&self, path: &DerivationPath) -> Result<XPrv, Error> {
+    check_account_path(path)?;
+    self.xprv.derive(path.path()[ACCOUNT_DEPTH - 1]).map_err(|err| err.into())
+  }
+
+  fn derive_private(&self, path: &DerivationPath) -> Result<XPrv, Error> {
+    check_key_path(path)?;
+    self.xprv.derive_path(&path.path()[ACCOUNT_DEPTH - 1..]).map_err(|err| err.into())
+  }
+
+  // Signs precomputed 32 byte hash. Returns r || s || v signature.
+  pub fn sign_hash(
+    &self, hash: &[u8], path: &DerivationPath, format: RecoveryIdFormat
+  ) -> Result<Vec<u8>, Error> {
     self
-      .xprv
-      .derive(path.account())
-      .and_then(|pk| pk.derive(path.change()))
-      .and_then(|pk| pk.derive(path.address()))
-      .map_err(|err| err.into())
+      .derive_private(path)?
+      .sign_digest(hash)
+      .map(|signature| encode_v(signature, format))
+      .map_err(Error::from_secp_sign_error)
+  }
+}
+
+// Operations which need only public keys. Implemented by full and watch-only keys.
+pub(super) trait PublicKey {
+  fn derive_public(&self, path: &DerivationPath) -> Result<XPub, Error>;
+
+  // Accepts r || s || v signature with any `v` encoding
+  fn verify_hash(
+    &self, hash: &[u8], signature: &[u8], path: &DerivationPath
+  ) -> Result<bool, Error> {
+    let (signature, _) = split_signature(signature)?;
+    self.derive_public(path)?.verify_digest(hash, signature).map_err(|err| err.into())
+  }
+}
+
+impl PublicKey for Key {
+  fn derive_public(&self, path: &DerivationPath) -> Result<XPub, Error> {
+    self.derive_private(path).map(|pk| pk.public())
   }
 }
 
+pub(super) fn check_account_path(path: &DerivationPath) -> Result<(), Error> {
+  if path.depth() < ACCOUNT_DEPTH {
+    return Err(KPError::InvalidDepth(path.depth()).into());
+  }
+  let indexes = path.path();
+  if indexes[0] != BIP44_PURPOSE {
+    return Err(KPError::InvalidPurpose(indexes[0], BIP44_PURPOSE).into());
+  }
+  if indexes[1] != BIP44_COIN_TYPE {
+    return Err(KPError::InvalidCoin(indexes[1], BIP44_COIN_TYPE).into());
+  }
+  if !DerivationPath::is_hardened(indexes[2]) {
+    return Err(KPError::InvalidAccount(indexes[2]).into());
+  }
+  Ok(())
+}
+
+// Account key itself or up to two non-hardened levels below it:
+// m/44'/60'/account', m/44'/60'/account'/index and m/44'/60'/account'/change/index
+pub(super) fn check_key_path(path: &DerivationPath) -> Result<(), Error> {
+  check_account_path(path)?;
+  if path.depth() > ADDRESS_DEPTH {
+    return Err(KPError::InvalidDepth(path.depth()).into());
+  }
+  for (position, &index) in path.path().iter().enumerate().skip(ACCOUNT_DEPTH) {
+    if DerivationPath::is_hardened(index) {
+      return Err(KPError::InvalidIndex(position + 1, index).into());
+    }
+  }
+  Ok(())
+}
+
 impl IKey for Key {
   fn network(&self) -> Network {
     Network::ETHEREUM
   }
 
-  fn pub_key(&self, path: &dyn KeyPath) -> Result<Vec<u8>, Error> {
+  fn pub_key(&self, path: &DerivationPath) -> Result<Vec<u8>, Error> {
     self.derive_private(path).map(|pk| pk.public().serialize())
   }
 
-  fn sign(&self, data: &[u8], path: &dyn KeyPath) -> Result<Vec<u8>, Error> {
+  fn address(&self, path: &DerivationPath) -> Result<String, Error> {
+    self.pub_key(path).and_then(|pk| address(&pk)).map(|address| checksum(&address, None))
+  }
+
+  fn account_xpub(&self, path: &DerivationPath) -> Result<String, Error> {
+    self.derive_account(path).map(|pk| pk.public().to_base58(Version::Xpub))
+  }
+
+  fn sign(&self, data: &[u8], path: &DerivationPath) -> Result<Vec<u8>, Error> {
     self.derive_private(path)?.sign(data).map_err(|err| Error::from_secp_sign_error(err))
   }
 
-  fn verify(&self, data: &[u8], signature: &[u8], path: &dyn KeyPath) -> Result<bool, Error> {
+  fn verify(&self, data: &[u8], signature: &[u8], path: &DerivationPath) -> Result<bool, Error> {
     self.derive_private(path)?.public().verify(data, signature).map_err(|err| err.into())
   }
+
+  fn as_any(&self) -> Option<&dyn Any> {
+    Some(self)
+  }
 }
@@ -0,0 +1,136 @@
+use super::address::{address, checksum};
+use super::key::{Key, PublicKey};
+use super::message::{personal_message_hash, RecoveryIdFormat};
+use super::transaction::Transaction;
+use super::typed_data::TypedData;
+use super::watch_only::WatchOnlyKey;
+use derivation_path::DerivationPath;
+use error::Error;
+use key::Error as KeyError;
+use keychain::Keychain;
+use network::Network;
+
+impl Keychain {
+  // EIP-55 checksummed address. Chain id switches checksum to EIP-1191 chain-specific one.
+  pub fn ethereum_address(
+    &self, path: &DerivationPath, chain_id: Option<u64>
+  ) -> Result<String, Error> {
+    let pub_key = self.pub_key(&Network::ETHEREUM, path)?;
+    address(&pub_key)
+      .map(|address| checksum(&address, chain_id))
+      .map_err(|err| Error::from_key_error(&Network::ETHEREUM, err))
+  }
+
+  // EIP-191 personal_sign. Message is prefixed with "\x19Ethereum Signed Message:\n<length>".
+  pub fn ethereum_sign_personal_message(
+    &self, message: &[u8], path: &DerivationPath, format: RecoveryIdFormat
+  ) -> Result<Vec<u8>, Error> {
+    self
+      .ethereum_private_key()?
+      .sign_hash(&personal_message_hash(message), path, format)
+      .map_err(|err| Error::from_key_error(&Network::ETHEREUM, err))
+  }
+
+  pub fn ethereum_verify_personal_message(
+    &self, message: &[u8], signature: &[u8], path: &DerivationPath
+  ) -> Result<bool, Error> {
+    self
+      .ethereum_public_key()?
+      .verify_hash(&personal_message_hash(message), signature, path)
+      .map_err(|err| Error::from_key_error(&Network::ETHEREUM, err))
+  }
+
+  // EIP-712 eth_signTypedData_v4. Accepts typed data JSON with domain, types, primaryType and message.
+  pub fn ethereum_sign_typed_data(
+    &self, typed_data: &str, path: &DerivationPath, format: RecoveryIdFormat
+  ) -> Result<Vec<u8>, Error> {
+    let hash = typed_data_hash(typed_data)?;
+    self
+      .ethereum_private_key()?
+      .sign_hash(&hash, path, format)
+      .map_err(|err| Error::from_key_error(&Network::ETHEREUM, err))
+  }
+
+  pub fn ethereum_verify_typed_data(
+    &self, typed_data: &str, signature: &[u8], path: &DerivationPath
+  ) -> Result<bool, Error> {
+    let hash = typed_data_hash(typed_data)?;
+    self
+      .ethereum_public_key()?
+      .verify_hash(&hash, signature, path)
+      .map_err(|err| Error::from_key_error(&Network::ETHEREUM, err))
+  }
+
+  // Signed raw transaction, ready for eth_sendRawTransaction
+  pub fn ethereum_sign_transaction(
+    &self, transaction: &Transaction, path: &DerivationPath
+  ) -> Result<Vec<u8>, Error> {
+    self
+      .ethereum_private_key()?
+      .sign_hash(&transaction.signing_hash(), path, RecoveryIdFormat::Raw)
+      .and_then(|signature| transaction.encode_signed(&signature))
+      .map_err(|err| Error::from_key_error(&Network::ETHEREUM, err))
+  }
+}
+
+pub(super) fn typed_data_hash(typed_data: &str) -> Result<[u8; 32], Error> {
+  TypedData::from_json(typed_data)
+    .and_then(|data| data.hash())
+    .map_err(|err| Error::from_key_error(&Network::ETHEREUM, err.into()))
+}
+
+impl Keychain {
+  fn ethereum_public_key(&self) -> Result<&dyn PublicKey, Error> {
+    self.network_key::<Key>(&Network::ETHEREUM).map(|key| key as &dyn PublicKey).or_else(|_| {
+      self.network_key::<WatchOnlyKey>(&Network::ETHEREUM).map(|key| key as &dyn PublicKey)
+    })
+  }
+
+  fn ethereum_private_key(&self) -> Result<&Key, Error> {
+    self.network_key::<Key>(&Network::ETHEREUM).map_err(|err| {
+      match self.network_key::<WatchOnlyKey>(&Network::ETHEREUM) {
+        Ok(_) => Error::from_key_error(&Network::ETHEREUM, KeyError::PrivateKeyIsNotAvailable),
+        Err(_) => err
+      }
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::fixture::{keychain, MAIL};
+  use super::super::message::RecoveryIdFormat;
+  use super::super::recovery::recover_typed_data_signer;
+  use bip39::util::hex;
+  use derivation_path::DerivationPath;
+
+  #[test]
+  fn typed_data_round_trip() {
+    let keychain = keychain();
+    let path = DerivationPath::from("m/44'/60'/0'/0/0").unwrap();
+    let signer = keychain.ethereum_address(&path, None).unwrap();
+    let signature =
+      keychain.ethereum_sign_typed_data(MAIL, &path, RecoveryIdFormat::Legacy).unwrap();
+    assert_eq!(signature.len(), 65);
+    assert!(signature[64] == 27 || signature[64] == 28);
+    assert!(keychain.ethereum_verify_typed_data(MAIL, &signature, &path).unwrap());
+    assert_eq!(recover_typed_data_signer(MAIL, &signature).unwrap(), signer);
+
+    let changed = MAIL.replace("Hello, Bob!", "Hello, Eve!");
+    assert!(!keychain.ethereum_verify_typed_data(&changed, &signature, &path).unwrap());
+    let other = DerivationPath::from("m/44'/60'/0'/0/1").unwrap();
+    assert!(!keychain.ethereum_verify_typed_data(MAIL, &signature, &other).unwrap());
+    assert!(keychain.ethereum_sign_typed_data("{}", &path, RecoveryIdFormat::Legacy).is_err());
+
+    // EIP-712 example signature by the key of Cow
+    let signature = hex::decode(
+      "4355c47d63924e8a72e509b65029052eb6c299d53a04e167c5775fd466751c9d\
+       07299936d304c153f6443dfa05f40ff007d72911b6f72307f996231605b915621c"
+    )
+    .unwrap();
+    assert_eq!(
+      recover_typed_data_signer(MAIL, &signature).unwrap(),
+      "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826"
+    );
+  }
+}
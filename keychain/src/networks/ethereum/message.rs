@@ -0,0 +1,106 @@
+use key::Error;
+
+use secp256k1_bip32::hash::keccak256;
+use secp256k1_bip32::KeyError as SecpKeyError;
+
+const PERSONAL_MESSAGE_PREFIX: &[u8] = b"\x19Ethereum Signed Message:\n";
+
+// r || s
+const RS_SIZE: usize = 64;
+// r || s || v
+pub const SIGNATURE_SIZE: usize = RS_SIZE + 1;
+
+const LEGACY_V_OFFSET: u8 = 27;
+
+// Encoding of the `v` byte in r || s || v signatures
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RecoveryIdFormat {
+  // v is 0 or 1
+  Raw,
+  // v is 27 or 28. Expected by most dapps and by ecrecover.
+  Legacy
+}
+
+// EIP-191 version 0x45 hash, used by personal_sign and by eth_sign of geth compatible nodes
+pub fn personal_message_hash(message: &[u8]) -> [u8; 32] {
+  let length = message.len().to_string();
+  let mut data = Vec::with_capacity(PERSONAL_MESSAGE_PREFIX.len() + length.len() + message.len());
+  data.extend_from_slice(PERSONAL_MESSAGE_PREFIX);
+  data.extend_from_slice(length.as_bytes());
+  data.extend_from_slice(message);
+  keccak256(&data)
+}
+
+// Re-encodes `v` of the r || s || v signature with raw recovery id
+pub(super) fn encode_v(mut signature: Vec<u8>, format: RecoveryIdFormat) -> Vec<u8> {
+  if format == RecoveryIdFormat::Legacy {
+    signature[RS_SIZE] += LEGACY_V_OFFSET;
+  }
+  signature
+}
+
+// Splits signature into r || s and raw recovery id. Both `v` encodings are accepted.
+pub(super) fn split_signature(signature: &[u8]) -> Result<(&[u8], u8), Error> {
+  if signature.len() != SIGNATURE_SIZE {
+    return Err(Error::InvalidSignatureSize(signature.len(), SIGNATURE_SIZE));
+  }
+  let recovery_id = match signature[RS_SIZE] {
+    v @ 0..=1 => v,
+    v @ 27..=28 => v - LEGACY_V_OFFSET,
+    _ => return Err(SecpKeyError::InvalidRecoveryId.into())
+  };
+  Ok((&signature[..RS_SIZE], recovery_id))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::fixture::key;
+  use super::super::key::PublicKey;
+  use super::super::watch_only::WatchOnlyKey;
+  use super::{encode_v, personal_message_hash, split_signature, RecoveryIdFormat};
+  use bip39::util::hex;
+  use derivation_path::DerivationPath;
+  use key::Key as IKey;
+
+  #[test]
+  fn personal_hash() {
+    assert_eq!(
+      hex::encode(&personal_message_hash(b"Hello World")),
+      "a1de988600a42c4b4ab089b619297c17d53cffae5d5120d82d8a92d0bb3b78f2"
+    );
+  }
+
+  #[test]
+  fn v_encoding() {
+    let raw = vec![1u8; 65];
+    let legacy = encode_v(raw.clone(), RecoveryIdFormat::Legacy);
+    assert_eq!(legacy[64], 28);
+    assert_eq!(split_signature(&legacy).unwrap(), (&raw[..64], 1));
+    assert_eq!(split_signature(&raw).unwrap(), (&raw[..64], 1));
+
+    let mut invalid = raw.clone();
+    invalid[64] = 2;
+    assert!(split_signature(&invalid).is_err());
+    assert!(split_signature(&raw[..64]).is_err());
+  }
+
+  #[test]
+  fn sign_personal_message() {
+    let key = key();
+    let account = DerivationPath::from("m/44'/60'/0'").unwrap();
+    let watch =
+      WatchOnlyKey::from_account_xpub(&account, &key.account_xpub(&account).unwrap()).unwrap();
+    let path = DerivationPath::from("m/44'/60'/0'/0/0").unwrap();
+    let hash = personal_message_hash(b"Hello World");
+
+    let raw = key.sign_hash(&hash, &path, RecoveryIdFormat::Raw).unwrap();
+    let legacy = key.sign_hash(&hash, &path, RecoveryIdFormat::Legacy).unwrap();
+    assert!(raw[64] <= 1);
+    assert_eq!(legacy[64], raw[64] + 27);
+    assert_eq!(&legacy[..64], &raw[..64]);
+
+    assert!(key.verify_hash(&hash, &legacy, &path).unwrap());
+    assert!(watch.verify_hash(&hash, &raw, &path).unwrap());
+    assert!(!watch.verify_hash(&personal_message_hash(b"Hello"), &legacy, &path).unwrap());
+  }
+}
@@ -1,6 +1,14 @@
+mod address;
 mod factory;
 mod key;
 mod key_path;
+mod keychain;
+mod message;
+mod recovery;
+mod rlp;
+mod transaction;
+mod typed_data;
+mod watch_only;
 
 use network::Network;
 
@@ -10,3 +18,68 @@ impl Network {
 
 pub use self::factory::KeyFactory;
 pub use self::key_path::KeyPath;
+pub use self::message::{personal_message_hash, RecoveryIdFormat};
+pub use self::recovery::{
+  recover_personal_message_signer, recover_signer, recover_typed_data_signer
+};
+pub use self::transaction::{
+  AccessListItem, AccessListTransaction, DynamicFeeTransaction, Error as TransactionError,
+  LegacyTransaction, Transaction
+};
+pub use self::typed_data::{Error as TypedDataError, TypedData};
+
+#[cfg(test)]
+mod fixture {
+  use super::key::Key;
+  use bip39::Seed;
+  use key::Key as IKey;
+  use keychain::Keychain;
+  use mnemonic::{seed_from_mnemonic, Language};
+
+  const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon \
+                          abandon abandon about";
+
+  // EIP-712 example
+  pub const MAIL: &str = r#"{
+    "types": {
+      "EIP712Domain": [
+        { "name": "name", "type": "string" },
+        { "name": "version", "type": "string" },
+        { "name": "chainId", "type": "uint256" },
+        { "name": "verifyingContract", "type": "address" }
+      ],
+      "Person": [
+        { "name": "name", "type": "string" },
+        { "name": "wallet", "type": "address" }
+      ],
+      "Mail": [
+        { "name": "from", "type": "Person" },
+        { "name": "to", "type": "Person" },
+        { "name": "contents", "type": "string" }
+      ]
+    },
+    "primaryType": "Mail",
+    "domain": {
+      "name": "Ether Mail",
+      "version": "1",
+      "chainId": 1,
+      "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+    },
+    "message": {
+      "from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+      "to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+      "contents": "Hello, Bob!"
+    }
+  }"#;
+
+  // Master key of the BIP39 test vectors mnemonic
+  pub fn key() -> Key {
+    let seed = seed_from_mnemonic(MNEMONIC, "", 128, Language::English).unwrap();
+    let data = Key::data_from_seed(&Seed::from_slice(&seed).unwrap()).unwrap();
+    Key::from_data(&data).unwrap()
+  }
+
+  pub fn keychain() -> Keychain {
+    Keychain::new(vec![key().boxed()])
+  }
+}
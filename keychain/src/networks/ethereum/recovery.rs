@@ -0,0 +1,66 @@
+use super::address::{address, checksum, ADDRESS_SIZE};
+use super::keychain::typed_data_hash;
+use super::message::{personal_message_hash, split_signature};
+use error::Error;
+use key::Error as KeyError;
+use network::Network;
+
+use secp256k1_bip32::recover;
+
+// ecrecover. Returns EIP-55 checksummed address of the hash signer.
+pub fn recover_signer(hash: &[u8], signature: &[u8]) -> Result<String, Error> {
+  recover_address(hash, signature)
+    .map(|address| checksum(&address, None))
+    .map_err(|err| Error::from_key_error(&Network::ETHEREUM, err))
+}
+
+pub fn recover_personal_message_signer(message: &[u8], signature: &[u8]) -> Result<String, Error> {
+  recover_signer(&personal_message_hash(message), signature)
+}
+
+pub fn recover_typed_data_signer(typed_data: &str, signature: &[u8]) -> Result<String, Error> {
+  recover_signer(&typed_data_hash(typed_data)?, signature)
+}
+
+fn recover_address(hash: &[u8], signature: &[u8]) -> Result<[u8; ADDRESS_SIZE], KeyError> {
+  let (rs, recovery_id) = split_signature(signature)?;
+  let mut raw = rs.to_vec();
+  raw.push(recovery_id);
+  recover(hash, &raw).map_err(KeyError::from).and_then(|pub_key| address(&pub_key))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::fixture::key;
+  use super::super::message::{personal_message_hash, RecoveryIdFormat};
+  use super::super::typed_data::TypedData;
+  use super::{recover_personal_message_signer, recover_typed_data_signer};
+  use derivation_path::DerivationPath;
+  use key::Key as IKey;
+
+  const TYPED_DATA: &str = r#"{
+    "types": {
+      "Person": [{ "name": "name", "type": "string" }, { "name": "wallet", "type": "address" }]
+    },
+    "primaryType": "Person",
+    "domain": { "name": "Login", "chainId": 1 },
+    "message": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" }
+  }"#;
+
+  #[test]
+  fn recover_signers() {
+    let key = key();
+    let path = DerivationPath::from("m/44'/60'/0'/0/0").unwrap();
+    let signer = key.address(&path).unwrap();
+
+    let hash = personal_message_hash(b"Login nonce 42");
+    let signature = key.sign_hash(&hash, &path, RecoveryIdFormat::Legacy).unwrap();
+    assert_eq!(recover_personal_message_signer(b"Login nonce 42", &signature).unwrap(), signer);
+    assert_ne!(recover_personal_message_signer(b"Login nonce 43", &signature).unwrap(), signer);
+
+    let hash = TypedData::from_json(TYPED_DATA).unwrap().hash().unwrap();
+    let signature = key.sign_hash(&hash, &path, RecoveryIdFormat::Raw).unwrap();
+    assert_eq!(recover_typed_data_signer(TYPED_DATA, &signature).unwrap(), signer);
+    assert!(recover_typed_data_signer(TYPED_DATA, &signature[..64]).is_err());
+  }
+}
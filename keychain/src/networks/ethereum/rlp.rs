@@ -0,0 +1,71 @@
+// Recursive Length Prefix encoding. Lists take already encoded items.
+
+const STRING_OFFSET: u8 = 0x80;
+const LIST_OFFSET: u8 = 0xc0;
+const SHORT_LENGTH: usize = 55;
+
+pub fn encode_bytes(bytes: &[u8]) -> Vec<u8> {
+  if bytes.len() == 1 && bytes[0] < STRING_OFFSET {
+    return bytes.to_vec();
+  }
+  let mut encoded = encode_length(bytes.len(), STRING_OFFSET);
+  encoded.extend_from_slice(bytes);
+  encoded
+}
+
+// Big-endian integer without leading zeros. Zero is an empty string.
+pub fn encode_uint(value: u128) -> Vec<u8> {
+  let bytes = value.to_be_bytes();
+  let start = bytes.iter().position(|&byte| byte != 0).unwrap_or(bytes.len());
+  encode_bytes(&bytes[start..])
+}
+
+pub fn encode_list(items: &[Vec<u8>]) -> Vec<u8> {
+  let length = items.iter().map(|item| item.len()).sum();
+  let mut encoded = encode_length(length, LIST_OFFSET);
+  for item in items {
+    encoded.extend_from_slice(item);
+  }
+  encoded
+}
+
+fn encode_length(length: usize, offset: u8) -> Vec<u8> {
+  if length <= SHORT_LENGTH {
+    return vec![offset + length as u8];
+  }
+  let bytes = (length as u64).to_be_bytes();
+  let start = bytes.iter().position(|&byte| byte != 0).unwrap_or(bytes.len());
+  let mut encoded = vec![offset + SHORT_LENGTH as u8 + (bytes.len() - start) as u8];
+  encoded.extend_from_slice(&bytes[start..]);
+  encoded
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{encode_bytes, encode_list, encode_uint};
+  use bip39::util::hex;
+
+  #[test]
+  fn encode() {
+    assert_eq!(hex::encode(&encode_bytes(b"dog")), "83646f67");
+    assert_eq!(hex::encode(&encode_bytes(b"")), "80");
+    assert_eq!(hex::encode(&encode_bytes(&[0x0f])), "0f");
+    assert_eq!(hex::encode(&encode_uint(0)), "80");
+    assert_eq!(hex::encode(&encode_uint(15)), "0f");
+    assert_eq!(hex::encode(&encode_uint(1024)), "820400");
+    assert_eq!(
+      hex::encode(&encode_list(&[encode_bytes(b"cat"), encode_bytes(b"dog")])),
+      "c88363617483646f67"
+    );
+    assert_eq!(hex::encode(&encode_list(&[])), "c0");
+    let lorem = b"Lorem ipsum dolor sit amet, consectetur adipisicing elit";
+    assert_eq!(&hex::encode(&encode_bytes(lorem))[..4], "b838");
+    // [ [], [[]], [ [], [[]] ] ]
+    let empty = encode_list(&[]);
+    let nested = encode_list(&[empty.clone()]);
+    assert_eq!(
+      hex::encode(&encode_list(&[empty.clone(), nested.clone(), encode_list(&[empty, nested])])),
+      "c7c0c1c0c3c0c1c0"
+    );
+  }
+}
@@ -0,0 +1,523 @@
+use super::address::ADDRESS_SIZE;
+use super::message::split_signature;
+use super::rlp::{encode_bytes, encode_list, encode_uint};
+use super::typed_data::parse_hex;
+use key::Error as KeyError;
+use serde_json::{self, Map, Value};
+use std::error::Error as AnyError;
+use std::fmt;
+
+use secp256k1_bip32::hash::keccak256;
+
+const ACCESS_LIST_TYPE: u8 = 0x01;
+const DYNAMIC_FEE_TYPE: u8 = 0x02;
+
+// EIP-155 v = recovery id + chain id * 2 + 35
+const EIP155_V_OFFSET: u128 = 35;
+
+const STORAGE_KEY_SIZE: usize = 32;
+
+#[derive(Debug)]
+pub enum Error {
+  InvalidJson(serde_json::Error),
+  MissingField(&'static str),
+  InvalidField(&'static str),
+  UnsupportedType(u64)
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      &Error::InvalidJson(ref err) => write!(f, "Invalid transaction JSON: {}", err),
+      &Error::MissingField(field) => write!(f, "Transaction field {} is missing", field),
+      &Error::InvalidField(field) => write!(f, "Invalid transaction field {}", field),
+      &Error::UnsupportedType(tx_type) => write!(f, "Unsupported transaction type {}", tx_type)
+    }
+  }
+}
+
+impl AnyError for Error {}
+
+impl From<Error> for KeyError {
+  fn from(err: Error) -> Self {
+    KeyError::InvalidData(Box::new(err))
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessListItem {
+  pub address: [u8; ADDRESS_SIZE],
+  pub storage_keys: Vec<[u8; STORAGE_KEY_SIZE]>
+}
+
+// Legacy transaction with EIP-155 replay protection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LegacyTransaction {
+  pub chain_id: u64,
+  pub nonce: u64,
+  pub gas_price: u128,
+  pub gas_limit: u64,
+  pub to: Option<[u8; ADDRESS_SIZE]>,
+  pub value: u128,
+  pub data: Vec<u8>
+}
+
+// EIP-2930 type 1 transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessListTransaction {
+  pub chain_id: u64,
+  pub nonce: u64,
+  pub gas_price: u128,
+  pub gas_limit: u64,
+  pub to: Option<[u8; ADDRESS_SIZE]>,
+  pub value: u128,
+  pub data: Vec<u8>,
+  pub access_list: Vec<AccessListItem>
+}
+
+// EIP-1559 type 2 transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DynamicFeeTransaction {
+  pub chain_id: u64,
+  pub nonce: u64,
+  pub max_priority_fee_per_gas: u128,
+  pub max_fee_per_gas: u128,
+  pub gas_limit: u64,
+  pub to: Option<[u8; ADDRESS_SIZE]>,
+  pub value: u128,
+  pub data: Vec<u8>,
+  pub access_list: Vec<AccessListItem>
+}
+
+// Empty `to` creates a contract
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transaction {
+  Legacy(LegacyTransaction),
+  AccessList(AccessListTransaction),
+  DynamicFee(DynamicFeeTransaction)
+}
+
+impl Transaction {
+  // eth_signTransaction JSON object. Quantities are 0x hex strings or JSON numbers.
+  // Type is detected from fee fields when `type` is missing.
+  pub fn from_json(json: &str) -> Result<Self, Error> {
+    let value: Value = serde_json::from_str(json).map_err(Error::InvalidJson)?;
+    let object = value.as_object().ok_or(Error::InvalidField("transaction"))?;
+    let tx_type = match object.get("type") {
+      Some(tx_type) => u64_quantity(tx_type, "type")?,
+      None if object.contains_key("maxFeePerGas") => DYNAMIC_FEE_TYPE as u64,
+      None if object.contains_key("accessList") => ACCESS_LIST_TYPE as u64,
+      None => 0
+    };
+    let chain_id = u64_field(object, "chainId")?;
+    let nonce = u64_field(object, "nonce")?;
+    let gas_limit = match object.get("gas") {
+      Some(gas) => u64_quantity(gas, "gas")?,
+      None => u64_field(object, "gasLimit")?
+    };
+    let to = match object.get("to") {
+      None | Some(&Value::Null) => None,
+      Some(to) => Some(address(to, "to")?)
+    };
+    let value = match object.get("value") {
+      Some(value) => quantity(value, "value")?,
+      None => 0
+    };
+    let data = match object.get("data").or_else(|| object.get("input")) {
+      Some(data) => data.as_str().and_then(parse_hex).ok_or(Error::InvalidField("data"))?,
+      None => Vec::new()
+    };
+    match tx_type {
+      0 => Ok(Transaction::Legacy(LegacyTransaction {
+        chain_id,
+        nonce,
+        gas_price: quantity_field(object, "gasPrice")?,
+        gas_limit,
+        to,
+        value,
+        data
+      })),
+      1 => Ok(Transaction::AccessList(AccessListTransaction {
+        chain_id,
+        nonce,
+        gas_price: quantity_field(object, "gasPrice")?,
+        gas_limit,
+        to,
+        value,
+        data,
+        access_list: access_list(object)?
+      })),
+      2 => Ok(Transaction::DynamicFee(DynamicFeeTransaction {
+        chain_id,
+        nonce,
+        max_priority_fee_per_gas: quantity_field(object, "maxPriorityFeePerGas")?,
+        max_fee_per_gas: quantity_field(object, "maxFeePerGas")?,
+        gas_limit,
+        to,
+        value,
+        data,
+        access_list: access_list(object)?
+      })),
+      _ => Err(Error::UnsupportedType(tx_type))
+    }
+  }
+
+  pub fn chain_id(&self) -> u64 {
+    match self {
+      &Transaction::Legacy(ref tx) => tx.chain_id,
+      &Transaction::AccessList(ref tx) => tx.chain_id,
+      &Transaction::DynamicFee(ref tx) => tx.chain_id
+    }
+  }
+
+  // Unsigned encoding. Legacy transactions append chain id, 0, 0 as EIP-155 requires.
+  pub fn signing_payload(&self) -> Vec<u8> {
+    match self {
+      &Transaction::Legacy(ref tx) => {
+        let mut items = tx.fields();
+        items.extend_from_slice(&[
+          encode_uint(tx.chain_id as u128),
+          encode_uint(0),
+          encode_uint(0)
+        ]);
+        encode_list(&items)
+      }
+      &Transaction::AccessList(ref tx) => typed(ACCESS_LIST_TYPE, tx.fields()),
+      &Transaction::DynamicFee(ref tx) => typed(DYNAMIC_FEE_TYPE, tx.fields())
+    }
+  }
+
+  pub fn signing_hash(&self) -> [u8; 32] {
+    keccak256(&self.signing_payload())
+  }
+
+  // Raw transaction for eth_sendRawTransaction. Accepts r || s || v signature of the signing hash.
+  pub fn encode_signed(&self, signature: &[u8]) -> Result<Vec<u8>, KeyError> {
+    let (rs, recovery_id) = split_signature(signature)?;
+    let (r, s) = (encode_uint_bytes(&rs[..32]), encode_uint_bytes(&rs[32..]));
+    Ok(match self {
+      &Transaction::Legacy(ref tx) => {
+        let v = recovery_id as u128 + tx.chain_id as u128 * 2 + EIP155_V_OFFSET;
+        let mut items = tx.fields();
+        items.extend_from_slice(&[encode_uint(v), r, s]);
+        encode_list(&items)
+      }
+      &Transaction::AccessList(ref tx) => {
+        let mut items = tx.fields();
+        items.extend_from_slice(&[encode_uint(recovery_id as u128), r, s]);
+        typed(ACCESS_LIST_TYPE, items)
+      }
+      &Transaction::DynamicFee(ref tx) => {
+        let mut items = tx.fields();
+        items.extend_from_slice(&[encode_uint(recovery_id as u128), r, s]);
+        typed(DYNAMIC_FEE_TYPE, items)
+      }
+    })
+  }
+}
+
+impl LegacyTransaction {
+  fn fields(&self) -> Vec<Vec<u8>> {
+    vec![
+      encode_uint(self.nonce as u128),
+      encode_uint(self.gas_price),
+      encode_uint(self.gas_limit as u128),
+      encode_to(&self.to),
+      encode_uint(self.value),
+      encode_bytes(&self.data),
+    ]
+  }
+}
+
+impl AccessListTransaction {
+  fn fields(&self) -> Vec<Vec<u8>> {
+    vec![
+      encode_uint(self.chain_id as u128),
+      encode_uint(self.nonce as u128),
+      encode_uint(self.gas_price),
+      encode_uint(self.gas_limit as u128),
+      encode_to(&self.to),
+      encode_uint(self.value),
+      encode_bytes(&self.data),
+      encode_access_list(&self.access_list),
+    ]
+  }
+}
+
+impl DynamicFeeTransaction {
+  fn fields(&self) -> Vec<Vec<u8>> {
+    vec![
+      encode_uint(self.chain_id as u128),
+      encode_uint(self.nonce as u128),
+      encode_uint(self.max_priority_fee_per_gas),
+      encode_uint(self.max_fee_per_gas),
+      encode_uint(self.gas_limit as u128),
+      encode_to(&self.to),
+      encode_uint(self.value),
+      encode_bytes(&self.data),
+      encode_access_list(&self.access_list),
+    ]
+  }
+}
+
+// EIP-2718 typed transaction envelope
+fn typed(tx_type: u8, items: Vec<Vec<u8>>) -> Vec<u8> {
+  let mut encoded = vec![tx_type];
+  encoded.extend_from_slice(&encode_list(&items));
+  encoded
+}
+
+fn encode_to(to: &Option<[u8; ADDRESS_SIZE]>) -> Vec<u8> {
+  match to {
+    &Some(ref address) => encode_bytes(address),
+    &None => encode_bytes(&[])
+  }
+}
+
+fn encode_uint_bytes(bytes: &[u8]) -> Vec<u8> {
+  let start = bytes.iter().position(|&byte| byte != 0).unwrap_or(bytes.len());
+  encode_bytes(&bytes[start..])
+}
+
+fn encode_access_list(access_list: &[AccessListItem]) -> Vec<u8> {
+  let items: Vec<Vec<u8>> = access_list
+    .iter()
+    .map(|item| {
+      let keys: Vec<Vec<u8>> = item.storage_keys.iter().map(|key| encode_bytes(key)).collect();
+      encode_list(&[encode_bytes(&item.address), encode_list(&keys)])
+    })
+    .collect();
+  encode_list(&items)
+}
+
+fn quantity(value: &Value, field: &'static str) -> Result<u128, Error> {
+  match value {
+    &Value::Number(ref number) => number.as_u64().map(|number| number as u128),
+    &Value::String(ref string) => {
+      let digits = string.strip_prefix("0x").ok_or(Error::InvalidField(field))?;
+      if digits.is_empty() {
+        return Err(Error::InvalidField(field));
+      }
+      u128::from_str_radix(digits, 16).ok()
+    }
+    _ => None
+  }
+  .ok_or(Error::InvalidField(field))
+}
+
+fn u64_quantity(value: &Value, field: &'static str) -> Result<u64, Error> {
+  let value = quantity(value, field)?;
+  if value > u64::MAX as u128 {
+    return Err(Error::InvalidField(field));
+  }
+  Ok(value as u64)
+}
+
+fn quantity_field(object: &Map<String, Value>, field: &'static str) -> Result<u128, Error> {
+  object.get(field).ok_or(Error::MissingField(field)).and_then(|value| quantity(value, field))
+}
+
+fn u64_field(object: &Map<String, Value>, field: &'static str) -> Result<u64, Error> {
+  object.get(field).ok_or(Error::MissingField(field)).and_then(|value| u64_quantity(value, field))
+}
+
+fn address(value: &Value, field: &'static str) -> Result<[u8; ADDRESS_SIZE], Error> {
+  let bytes = value
+    .as_str()
+    .and_then(parse_hex)
+    .filter(|bytes| bytes.len() == ADDRESS_SIZE)
+    .ok_or(Error::InvalidField(field))?;
+  let mut address = [0u8; ADDRESS_SIZE];
+  address.copy_from_slice(&bytes);
+  Ok(address)
+}
+
+fn access_list(object: &Map<String, Value>) -> Result<Vec<AccessListItem>, Error> {
+  let items = match object.get("accessList") {
+    Some(items) => items.as_array().ok_or(Error::InvalidField("accessList"))?,
+    None => return Ok(Vec::new())
+  };
+  items
+    .iter()
+    .map(|item| {
+      let item_address = item.get("address").ok_or(Error::MissingField("address"))?;
+      let keys = item
+        .get("storageKeys")
+        .and_then(|keys| keys.as_array())
+        .ok_or(Error::InvalidField("storageKeys"))?;
+      let storage_keys: Result<Vec<[u8; STORAGE_KEY_SIZE]>, Error> = keys
+        .iter()
+        .map(|key| {
+          let bytes = key
+            .as_str()
+            .and_then(parse_hex)
+            .filter(|bytes| bytes.len() == STORAGE_KEY_SIZE)
+            .ok_or(Error::InvalidField("storageKeys"))?;
+          let mut storage_key = [0u8; STORAGE_KEY_SIZE];
+          storage_key.copy_from_slice(&bytes);
+          Ok(storage_key)
+        })
+        .collect();
+      Ok(AccessListItem { address: address(item_address, "address")?, storage_keys: storage_keys? })
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::fixture::keychain;
+  use super::super::recovery::recover_signer;
+  use super::Transaction;
+  use bip39::util::hex;
+  use derivation_path::DerivationPath;
+
+  // EIP-155 example transaction
+  const LEGACY: &str = r#"{
+    "nonce": "0x9",
+    "gasPrice": "0x4a817c800",
+    "gas": 21000,
+    "to": "0x3535353535353535353535353535353535353535",
+    "value": "0xde0b6b3a7640000",
+    "chainId": 1
+  }"#;
+
+  // Signer of the fixture key at m/44'/60'/0'/0/0
+  const SENDER: &str = "0x9858EfFD232B4033E47d90003D41EC34EcaEda94";
+
+  // Raw transactions are computed by an independent implementation, which reproduces the EIP-155
+  // example signature. ECDSA nonces are deterministic (RFC6979).
+  const ACCESS_LIST: &str = r#"{
+    "type": "0x1",
+    "chainId": "0x1",
+    "nonce": "0x8",
+    "gasPrice": "0x4a817c800",
+    "gas": "0x7530",
+    "to": "0x3535353535353535353535353535353535353535",
+    "value": "0x2386f26fc10000",
+    "data": "0xa9059cbb",
+    "accessList": [{
+      "address": "0xde0b295669a9fd93d5f28d9ec85e40f4cb697bae",
+      "storageKeys": [
+        "0x0000000000000000000000000000000000000000000000000000000000000003",
+        "0x0000000000000000000000000000000000000000000000000000000000000007"
+      ]
+    }]
+  }"#;
+  const ACCESS_LIST_SIGNED: &str =
+    "01f8cd01088504a817c800827530943535353535353535353535353535353535353535872386f26fc1000084a905\
+     9cbbf85bf85994de0b295669a9fd93d5f28d9ec85e40f4cb697baef842a000000000000000000000000000000000\
+     00000000000000000000000000000003a00000000000000000000000000000000000000000000000000000000000\
+     00000701a03a1bd371d560e1cb7e6f6b4029ebb5a42add4dc317e357e3a5e9ab08bc31f4b1a07b79d82c23eb1350\
+     d595e656f1545839dec41980a717da1ea50607edd91c4dcb";
+  const DYNAMIC_FEE: &str = r#"{
+    "type": "0x2",
+    "chainId": "0x1",
+    "nonce": "0x3",
+    "maxPriorityFeePerGas": "0x77359400",
+    "maxFeePerGas": "0x2540be400",
+    "gas": "0x7530",
+    "to": "0x3535353535353535353535353535353535353535",
+    "value": "0x2386f26fc10000",
+    "data": "0xa9059cbb",
+    "accessList": [{
+      "address": "0xde0b295669a9fd93d5f28d9ec85e40f4cb697bae",
+      "storageKeys": [
+        "0x0000000000000000000000000000000000000000000000000000000000000003",
+        "0x0000000000000000000000000000000000000000000000000000000000000007"
+      ]
+    }]
+  }"#;
+  const DYNAMIC_FEE_SIGNED: &str =
+    "02f8d2010384773594008502540be400827530943535353535353535353535353535353535353535872386f26fc1\
+     000084a9059cbbf85bf85994de0b295669a9fd93d5f28d9ec85e40f4cb697baef842a00000000000000000000000\
+     000000000000000000000000000000000000000003a0000000000000000000000000000000000000000000000000\
+     000000000000000701a00b6329e07909267c1aa9101da61b25db0b22786590ddade6165d02c1e09e83a0a05338ef\
+     ae999ad0d148a7743b1969cbff538f2a806b10079be46366bbcdc8b2ae";
+
+  #[test]
+  fn legacy_eip155() {
+    let tx = Transaction::from_json(LEGACY).unwrap();
+    assert_eq!(
+      hex::encode(&tx.signing_payload()),
+      "ec098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a764000080018080"
+    );
+    assert_eq!(
+      hex::encode(&tx.signing_hash()),
+      "daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53"
+    );
+    let signature = hex::decode(
+      "28ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276\
+       67cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d8300"
+    )
+    .unwrap();
+    assert_eq!(
+      hex::encode(&tx.encode_signed(&signature).unwrap()),
+      "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a0\
+       28ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb70330\
+       4b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+    );
+  }
+
+  #[test]
+  fn typed_transactions() {
+    let dynamic_fee = Transaction::from_json(
+      r#"{
+        "chainId": "0x1",
+        "nonce": "0x0",
+        "maxPriorityFeePerGas": "0x1",
+        "maxFeePerGas": "0x2",
+        "gas": "0x5208",
+        "to": "0x3535353535353535353535353535353535353535",
+        "value": "0x0"
+      }"#
+    )
+    .unwrap();
+    let address = "35".repeat(20);
+    assert_eq!(
+      hex::encode(&dynamic_fee.signing_payload()),
+      format!("02df0180010282520894{}8080c0", address)
+    );
+    let access_list = Transaction::from_json(
+      r#"{
+        "type": "0x1",
+        "chainId": "0x1",
+        "nonce": "0x0",
+        "gasPrice": "0x1",
+        "gas": "0x5208",
+        "value": "0x0",
+        "data": "0x",
+        "accessList": [{
+          "address": "0x3535353535353535353535353535353535353535",
+          "storageKeys": ["0x0000000000000000000000000000000000000000000000000000000000000001"]
+        }]
+      }"#
+    )
+    .unwrap();
+    assert_eq!(
+      hex::encode(&access_list.signing_payload()),
+      format!("01f843018001825208808080f838f794{}e1a0{}01", address, "00".repeat(31))
+    );
+    let signed = access_list.encode_signed(&[0x01; 65]).unwrap();
+    assert_eq!(signed[0], 0x01);
+    assert!(Transaction::from_json(r#"{ "type": "0x3", "chainId": 1 }"#).is_err());
+    assert!(Transaction::from_json(r#"{ "nonce": "0x0", "gas": 1, "gasPrice": 1 }"#).is_err());
+  }
+
+  #[test]
+  fn sign_typed_transactions() {
+    let keychain = keychain();
+    let path = DerivationPath::from("m/44'/60'/0'/0/0").unwrap();
+    for &(json, signed) in
+      [(ACCESS_LIST, ACCESS_LIST_SIGNED), (DYNAMIC_FEE, DYNAMIC_FEE_SIGNED)].iter()
+    {
+      let tx = Transaction::from_json(json).unwrap();
+      let raw = keychain.ethereum_sign_transaction(&tx, &path).unwrap();
+      assert_eq!(hex::encode(&raw), signed);
+
+      // Raw transaction ends with y parity, r and s of 32 bytes each
+      let tail = &raw[raw.len() - 67..];
+      let signature = [&tail[2..34], &tail[35..], &tail[..1]].concat();
+      assert_eq!(recover_signer(&tx.signing_hash(), &signature).unwrap(), SENDER);
+    }
+  }
+}
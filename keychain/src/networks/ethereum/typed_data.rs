@@ -0,0 +1,446 @@
+use bip39::util::hex;
+use key::Error as KeyError;
+use serde_json::{self, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error as AnyError;
+use std::fmt;
+
+use secp256k1_bip32::hash::keccak256;
+
+const DOMAIN_TYPE: &str = "EIP712Domain";
+const WORD_SIZE: usize = 32;
+const ADDRESS_SIZE: usize = 20;
+
+// Domain fields in the order used when `types` doesn't describe EIP712Domain
+const DOMAIN_FIELDS: [(&str, &str); 5] = [
+  ("name", "string"),
+  ("version", "string"),
+  ("chainId", "uint256"),
+  ("verifyingContract", "address"),
+  ("salt", "bytes32")
+];
+
+#[derive(Debug)]
+pub enum Error {
+  InvalidJson(serde_json::Error),
+  UnknownType(String),
+  MissingField(String, String),
+  InvalidValue(String, String)
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      &Error::InvalidJson(ref err) => write!(f, "Invalid typed data JSON: {}", err),
+      &Error::UnknownType(ref name) => write!(f, "Unknown type {}", name),
+      &Error::MissingField(ref name, ref field) => {
+        write!(f, "Field {} of type {} is missing", field, name)
+      }
+      &Error::InvalidValue(ref name, ref value) => {
+        write!(f, "Invalid value {} for type {}", value, name)
+      }
+    }
+  }
+}
+
+impl AnyError for Error {}
+
+impl From<Error> for KeyError {
+  fn from(err: Error) -> Self {
+    KeyError::InvalidData(Box::new(err))
+  }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Field {
+  name: String,
+  #[serde(rename = "type")]
+  field_type: String
+}
+
+// EIP-712 typed structured data in eth_signTypedData_v4 JSON format
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypedData {
+  types: BTreeMap<String, Vec<Field>>,
+  primary_type: String,
+  domain: Value,
+  #[serde(default)]
+  message: Value
+}
+
+impl TypedData {
+  pub fn from_json(json: &str) -> Result<Self, Error> {
+    let mut data: Self = serde_json::from_str(json).map_err(Error::InvalidJson)?;
+    if !data.types.contains_key(DOMAIN_TYPE) {
+      let fields = data.domain_fields()?;
+      data.types.insert(DOMAIN_TYPE.to_owned(), fields);
+    }
+    Ok(data)
+  }
+
+  pub fn primary_type(&self) -> &str {
+    &self.primary_type
+  }
+
+  pub fn domain_separator(&self) -> Result<[u8; WORD_SIZE], Error> {
+    self.hash_struct(DOMAIN_TYPE, &self.domain)
+  }
+
+  // Empty for EIP712Domain primary type, which signs the domain only
+  pub fn message_hash(&self) -> Result<Option<[u8; WORD_SIZE]>, Error> {
+    if self.primary_type == DOMAIN_TYPE {
+      return Ok(None);
+    }
+    self.hash_struct(&self.primary_type, &self.message).map(Some)
+  }
+
+  // keccak256("\x19\x01" || domainSeparator || hashStruct(message))
+  pub fn hash(&self) -> Result<[u8; WORD_SIZE], Error> {
+    let mut data = Vec::with_capacity(2 + WORD_SIZE * 2);
+    data.extend_from_slice(b"\x19\x01");
+    data.extend_from_slice(&self.domain_separator()?);
+    if let Some(message) = self.message_hash()? {
+      data.extend_from_slice(&message);
+    }
+    Ok(keccak256(&data))
+  }
+
+  // Type with all referenced struct types sorted by name
+  pub fn encode_type(&self, name: &str) -> Result<String, Error> {
+    let mut dependencies = BTreeSet::new();
+    self.dependencies(name, &mut dependencies)?;
+    dependencies.remove(name);
+    let mut encoded = self.encode_single_type(name)?;
+    for dependency in dependencies.iter() {
+      encoded.push_str(&self.encode_single_type(dependency)?);
+    }
+    Ok(encoded)
+  }
+
+  pub fn hash_struct(&self, name: &str, value: &Value) -> Result<[u8; WORD_SIZE], Error> {
+    self.encode_data(name, value).map(|data| keccak256(&data))
+  }
+
+  fn domain_fields(&self) -> Result<Vec<Field>, Error> {
+    let domain = self
+      .domain
+      .as_object()
+      .ok_or_else(|| Error::InvalidValue(DOMAIN_TYPE.to_owned(), self.domain.to_string()))?;
+    Ok(
+      DOMAIN_FIELDS
+        .iter()
+        .filter(|&&(name, _)| domain.contains_key(name))
+        .map(|&(name, field_type)| Field {
+          name: name.to_owned(),
+          field_type: field_type.to_owned()
+        })
+        .collect()
+    )
+  }
+
+  fn fields(&self, name: &str) -> Result<&[Field], Error> {
+    self
+      .types
+      .get(name)
+      .map(|fields| fields.as_slice())
+      .ok_or_else(|| Error::UnknownType(name.to_owned()))
+  }
+
+  fn dependencies(&self, name: &str, found: &mut BTreeSet<String>) -> Result<(), Error> {
+    if found.contains(name) {
+      return Ok(());
+    }
+    found.insert(name.to_owned());
+    for field in self.fields(name)? {
+      let base = base_type(&field.field_type);
+      if self.types.contains_key(base) {
+        self.dependencies(base, found)?;
+      }
+    }
+    Ok(())
+  }
+
+  fn encode_single_type(&self, name: &str) -> Result<String, Error> {
+    let fields: Vec<String> = self
+      .fields(name)?
+      .iter()
+      .map(|field| format!("{} {}", field.field_type, field.name))
+      .collect();
+    Ok(format!("{}({})", name, fields.join(",")))
+  }
+
+  // typeHash || encoded field values
+  fn encode_data(&self, name: &str, value: &Value) -> Result<Vec<u8>, Error> {
+    let object =
+      value.as_object().ok_or_else(|| Error::InvalidValue(name.to_owned(), value.to_string()))?;
+    let fields = self.fields(name)?;
+    let mut data = Vec::with_capacity(WORD_SIZE * (fields.len() + 1));
+    data.extend_from_slice(&keccak256(self.encode_type(name)?.as_bytes()));
+    for field in fields {
+      let value = object
+        .get(&field.name)
+        .ok_or_else(|| Error::MissingField(name.to_owned(), field.name.clone()))?;
+      data.extend_from_slice(&self.encode_value(&field.field_type, value)?);
+    }
+    Ok(data)
+  }
+
+  fn encode_value(&self, field_type: &str, value: &Value) -> Result<[u8; WORD_SIZE], Error> {
+    let invalid = || Error::InvalidValue(field_type.to_owned(), value.to_string());
+    if field_type.ends_with(']') {
+      let open = field_type.rfind('[').ok_or_else(|| Error::UnknownType(field_type.to_owned()))?;
+      let items = value.as_array().ok_or_else(invalid)?;
+      let size = &field_type[open + 1..field_type.len() - 1];
+      if !size.is_empty() && size.parse::<usize>().ok() != Some(items.len()) {
+        return Err(invalid());
+      }
+      let mut data = Vec::with_capacity(WORD_SIZE * items.len());
+      for item in items {
+        data.extend_from_slice(&self.encode_value(&field_type[..open], item)?);
+      }
+      return Ok(keccak256(&data));
+    }
+    if self.types.contains_key(field_type) {
+      return self.encode_data(field_type, value).map(|data| keccak256(&data));
+    }
+    match field_type {
+      "string" => value.as_str().map(|string| keccak256(string.as_bytes())).ok_or_else(invalid),
+      "bytes" => {
+        value.as_str().and_then(parse_hex).map(|bytes| keccak256(&bytes)).ok_or_else(invalid)
+      }
+      "bool" => value.as_bool().map(|flag| right_aligned(&[flag as u8])).ok_or_else(invalid),
+      "address" => value
+        .as_str()
+        .and_then(parse_hex)
+        .filter(|bytes| bytes.len() == ADDRESS_SIZE)
+        .map(|bytes| right_aligned(&bytes))
+        .ok_or_else(invalid),
+      _ if field_type.starts_with("bytes") => {
+        let size = type_size(field_type, "bytes").filter(|size| (1..=WORD_SIZE).contains(size));
+        let size = size.ok_or_else(|| Error::UnknownType(field_type.to_owned()))?;
+        let bytes = value
+          .as_str()
+          .and_then(parse_hex)
+          .filter(|bytes| bytes.len() == size)
+          .ok_or_else(invalid)?;
+        let mut word = [0u8; WORD_SIZE];
+        word[..size].copy_from_slice(&bytes);
+        Ok(word)
+      }
+      _ if field_type.starts_with("uint") => {
+        let bits = integer_bits(field_type, "uint")?;
+        match parse_integer(value) {
+          Some((false, word)) if fits(&word, bits, 0) => Ok(word),
+          _ => Err(invalid())
+        }
+      }
+      _ if field_type.starts_with("int") => {
+        let bits = integer_bits(field_type, "int")?;
+        match parse_integer(value) {
+          Some((false, word)) if fits(&word, bits - 1, 0) => Ok(word),
+          Some((true, word)) => {
+            let word = negate(&word);
+            if fits(&word, bits - 1, 0xff) {
+              Ok(word)
+            } else {
+              Err(invalid())
+            }
+          }
+          _ => Err(invalid())
+        }
+      }
+      _ => Err(Error::UnknownType(field_type.to_owned()))
+    }
+  }
+}
+
+// Struct name of the possibly array type
+fn base_type(field_type: &str) -> &str {
+  match field_type.find('[') {
+    Some(index) => &field_type[..index],
+    None => field_type
+  }
+}
+
+fn type_size(field_type: &str, prefix: &str) -> Option<usize> {
+  field_type[prefix.len()..].parse::<usize>().ok()
+}
+
+// intN and uintN have N multiple of 8 up to 256. Plain int and uint are 256 bits.
+fn integer_bits(field_type: &str, prefix: &str) -> Result<usize, Error> {
+  if field_type.len() == prefix.len() {
+    return Ok(WORD_SIZE * 8);
+  }
+  type_size(field_type, prefix)
+    .filter(|&bits| bits > 0 && bits <= WORD_SIZE * 8 && bits % 8 == 0)
+    .ok_or_else(|| Error::UnknownType(field_type.to_owned()))
+}
+
+// Hex string with optional 0x prefix
+pub(super) fn parse_hex(string: &str) -> Option<Vec<u8>> {
+  let digits =
+    if string.starts_with("0x") || string.starts_with("0X") { &string[2..] } else { string };
+  if digits.len() % 2 != 0 || !digits.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+    return None;
+  }
+  hex::decode(digits).ok()
+}
+
+fn right_aligned(bytes: &[u8]) -> [u8; WORD_SIZE] {
+  let mut word = [0u8; WORD_SIZE];
+  word[WORD_SIZE - bytes.len()..].copy_from_slice(bytes);
+  word
+}
+
+// Sign and 256-bit big-endian magnitude of JSON number or decimal / 0x hex string
+fn parse_integer(value: &Value) -> Option<(bool, [u8; WORD_SIZE])> {
+  match value {
+    &Value::Number(ref number) => match (number.as_u64(), number.as_i64()) {
+      (Some(unsigned), _) => Some((false, right_aligned(&u64_bytes(unsigned)))),
+      (None, Some(signed)) => Some((true, right_aligned(&u64_bytes(signed.unsigned_abs())))),
+      _ => None
+    },
+    &Value::String(ref string) => {
+      let string = string.trim();
+      let (negative, digits) = match string.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, string)
+      };
+      let word = if digits.starts_with("0x") || digits.starts_with("0X") {
+        let mut digits = digits[2..].to_owned();
+        if digits.len() % 2 != 0 {
+          digits.insert(0, '0');
+        }
+        parse_hex(&digits)
+          .filter(|bytes| !bytes.is_empty() && bytes.len() <= WORD_SIZE)
+          .map(|bytes| right_aligned(&bytes))
+      } else {
+        parse_decimal(digits)
+      }?;
+      Some((negative && word.iter().any(|&byte| byte != 0), word))
+    }
+    _ => None
+  }
+}
+
+fn u64_bytes(value: u64) -> [u8; 8] {
+  let mut bytes = [0u8; 8];
+  for (index, byte) in bytes.iter_mut().enumerate() {
+    *byte = (value >> (56 - index * 8)) as u8;
+  }
+  bytes
+}
+
+fn parse_decimal(digits: &str) -> Option<[u8; WORD_SIZE]> {
+  if digits.is_empty() {
+    return None;
+  }
+  let mut word = [0u8; WORD_SIZE];
+  for digit in digits.chars() {
+    let mut carry = digit.to_digit(10)?;
+    for byte in word.iter_mut().rev() {
+      let value = *byte as u32 * 10 + carry;
+      *byte = value as u8;
+      carry = value >> 8;
+    }
+    if carry != 0 {
+      return None;
+    }
+  }
+  Some(word)
+}
+
+// Two's complement
+fn negate(word: &[u8; WORD_SIZE]) -> [u8; WORD_SIZE] {
+  let mut result = [0u8; WORD_SIZE];
+  let mut carry = 1u16;
+  for (index, byte) in word.iter().enumerate().rev() {
+    let value = (!*byte) as u16 + carry;
+    result[index] = value as u8;
+    carry = value >> 8;
+  }
+  result
+}
+
+// All bits above the lowest `bits` are equal to the `fill` bits
+fn fits(word: &[u8; WORD_SIZE], bits: usize, fill: u8) -> bool {
+  let high_bits = WORD_SIZE * 8 - bits;
+  word.iter().enumerate().all(|(index, &byte)| {
+    let high_in_byte = high_bits.saturating_sub(index * 8).min(8);
+    let mask = !(0xffu16 >> high_in_byte) as u8;
+    byte & mask == fill & mask
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::fixture::MAIL;
+  use super::TypedData;
+  use bip39::util::hex;
+
+  #[test]
+  fn eip712_mail() {
+    let data = TypedData::from_json(MAIL).unwrap();
+    assert_eq!(
+      data.encode_type("Mail").unwrap(),
+      "Mail(Person from,Person to,string contents)Person(string name,address wallet)"
+    );
+    assert_eq!(
+      hex::encode(&data.domain_separator().unwrap()),
+      "f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f"
+    );
+    assert_eq!(
+      hex::encode(&data.message_hash().unwrap().unwrap()),
+      "c52c0ee5d84264471806290a3f2c4cecfc5490626bf912d01f240d7a274b371e"
+    );
+    assert_eq!(
+      hex::encode(&data.hash().unwrap()),
+      "be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2"
+    );
+  }
+
+  #[test]
+  fn implicit_domain_type() {
+    let json = MAIL.replacen("\"EIP712Domain\"", "\"Unused\"", 1);
+    let data = TypedData::from_json(&json).unwrap();
+    assert_eq!(
+      hex::encode(&data.hash().unwrap()),
+      "be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2"
+    );
+  }
+
+  #[test]
+  fn integers() {
+    let types = r#"{ "Value": [{ "name": "value", "type": "TYPE" }] }"#;
+    let hash = |field_type: &str, value: &str| {
+      let json = format!(
+        r#"{{ "types": {}, "primaryType": "Value", "domain": {{}}, "message": {{ "value": {} }} }}"#,
+        types.replace("TYPE", field_type),
+        value
+      );
+      TypedData::from_json(&json).and_then(|data| data.hash())
+    };
+    assert!(hash("uint8", "255").is_ok());
+    assert!(hash("uint8", "256").is_err());
+    assert!(hash("uint8", "-1").is_err());
+    assert!(hash("int8", "-128").is_ok());
+    assert!(hash("int8", "\"-129\"").is_err());
+    assert!(hash("int8", "128").is_err());
+    assert_eq!(hash("uint256", "\"0x10\"").unwrap(), hash("uint256", "16").unwrap());
+    assert_eq!(hash("int256", "\"-1\"").unwrap(), hash("int256", "-1").unwrap());
+    assert!(hash(
+      "uint256",
+      "\"115792089237316195423570985008687907853269984665640564039457584007913129639935\""
+    )
+    .is_ok());
+    assert!(hash(
+      "uint256",
+      "\"115792089237316195423570985008687907853269984665640564039457584007913129639936\""
+    )
+    .is_err());
+    assert!(hash("bytes2", "\"0x0102\"").is_ok());
+    assert!(hash("bytes2", "\"0x01\"").is_err());
+    assert!(hash("uint7", "1").is_err());
+  }
+}
@@ -0,0 +1,74 @@
+use super::address::{address, checksum};
+use super::key::{check_account_path, check_key_path, PublicKey};
+use derivation_path::DerivationPath;
+use key::{Error, Key as IKey};
+use key_path::{Error as KPError, ACCOUNT_DEPTH};
+use network::Network;
+use std::any::Any;
+
+use secp256k1_bip32::{KeyError as SecpKeyError, Version, XPub};
+
+pub struct WatchOnlyKey {
+  xpub: XPub,
+  account: DerivationPath
+}
+
+impl WatchOnlyKey {
+  pub fn from_account_xpub(path: &DerivationPath, xpub: &str) -> Result<Self, Error> {
+    check_account_path(path)?;
+    let (xpub, version) = XPub::from_base58(xpub)?;
+    if version != Version::Xpub {
+      return Err(SecpKeyError::UnknownVersion(version.public()).into());
+    }
+    if xpub.depth() as usize != ACCOUNT_DEPTH || xpub.index() != path.path()[2] {
+      return Err(KPError::InvalidAccount(path.path()[2]).into());
+    }
+    Ok(Self { xpub, account: path.prefix(ACCOUNT_DEPTH) })
+  }
+
+  fn check_account(&self, path: &DerivationPath) -> Result<(), Error> {
+    check_account_path(path)?;
+    if !path.starts_with(&self.account) {
+      return Err(KPError::InvalidAccount(path.path()[2]).into());
+    }
+    Ok(())
+  }
+}
+
+impl PublicKey for WatchOnlyKey {
+  fn derive_public(&self, path: &DerivationPath) -> Result<XPub, Error> {
+    check_key_path(path)?;
+    self.check_account(path)?;
+    self.xpub.derive_path(&path.path()[ACCOUNT_DEPTH..]).map_err(|err| err.into())
+  }
+}
+
+impl IKey for WatchOnlyKey {
+  fn network(&self) -> Network {
+    Network::ETHEREUM
+  }
+
+  fn pub_key(&self, path: &DerivationPath) -> Result<Vec<u8>, Error> {
+    self.derive_public(path).map(|pk| pk.serialize())
+  }
+
+  fn address(&self, path: &DerivationPath) -> Result<String, Error> {
+    self.pub_key(path).and_then(|pk| address(&pk)).map(|address| checksum(&address, None))
+  }
+
+  fn account_xpub(&self, path: &DerivationPath) -> Result<String, Error> {
+    self.check_account(path).map(|_| self.xpub.to_base58(Version::Xpub))
+  }
+
+  fn sign(&self, _: &[u8], _: &DerivationPath) -> Result<Vec<u8>, Error> {
+    Err(Error::PrivateKeyIsNotAvailable)
+  }
+
+  fn verify(&self, data: &[u8], signature: &[u8], path: &DerivationPath) -> Result<bool, Error> {
+    self.derive_public(path)?.verify(data, signature).map_err(|err| err.into())
+  }
+
+  fn as_any(&self) -> Option<&dyn Any> {
+    Some(self)
+  }
+}
@@ -0,0 +1,25 @@
+use bs58;
+use error::KeyError;
+use hash::sha256d;
+
+pub const CHECKSUM_SIZE: usize = 4;
+
+pub fn encode_check(data: &[u8]) -> String {
+  let mut payload = Vec::with_capacity(data.len() + CHECKSUM_SIZE);
+  payload.extend_from_slice(data);
+  payload.extend_from_slice(&sha256d(data)[0..CHECKSUM_SIZE]);
+  bs58::encode(payload).into_string()
+}
+
+pub fn decode_check(string: &str) -> Result<Vec<u8>, KeyError> {
+  let mut data = bs58::decode(string).into_vec().map_err(|_| KeyError::InvalidBase58)?;
+  if data.len() < CHECKSUM_SIZE {
+    return Err(KeyError::InvalidBase58);
+  }
+  let payload_size = data.len() - CHECKSUM_SIZE;
+  if data[payload_size..] != sha256d(&data[0..payload_size])[0..CHECKSUM_SIZE] {
+    return Err(KeyError::InvalidChecksum);
+  }
+  data.truncate(payload_size);
+  Ok(data)
+}
@@ -5,6 +5,7 @@ use std::fmt;
 #[derive(Debug)]
 pub enum KeyError {
   InvalidSignature(usize, usize),
+  InvalidDerSignature,
   InvalidPublicKey,
   InvalidSecretKey,
   InvalidRecoveryId,
@@ -14,6 +15,11 @@ pub enum KeyError {
   InvalidDataSize(usize, usize),
   InvalidEntropySize(usize),
   DeriveDepthTooBig,
+  HardenedPublicDerivation(u32),
+  InvalidBase58,
+  InvalidChecksum,
+  UnknownVersion(u32),
+  InvalidRootKey,
   InternalError
 }
 
@@ -23,6 +29,7 @@ impl fmt::Display for KeyError {
       &KeyError::InvalidSignature(bad, good) => {
         write!(f, "Invalid signature {}, expected {}", bad, good)
       }
+      &KeyError::InvalidDerSignature => write!(f, "Invalid DER signature"),
       &KeyError::InvalidPublicKey => write!(f, "Invalid public key"),
       &KeyError::InvalidSecretKey => write!(f, "Invalid secret key"),
       &KeyError::InvalidRecoveryId => write!(f, "Invalid recovery id"),
@@ -34,7 +41,16 @@ impl fmt::Display for KeyError {
       }
       &KeyError::InvalidEntropySize(size) => write!(f, "Invalid entropy size {}", size),
       &KeyError::InternalError => write!(f, "Unknown internal error"),
-      &KeyError::DeriveDepthTooBig => write!(f, "Derive depth is too big")
+      &KeyError::DeriveDepthTooBig => write!(f, "Derive depth is too big"),
+      &KeyError::HardenedPublicDerivation(index) => {
+        write!(f, "Hardened index {} can't be derived from public key", index)
+      }
+      &KeyError::InvalidBase58 => write!(f, "Invalid base58 string"),
+      &KeyError::InvalidChecksum => write!(f, "Invalid checksum"),
+      &KeyError::UnknownVersion(version) => write!(f, "Unknown key version {:#010x}", version),
+      &KeyError::InvalidRootKey => {
+        write!(f, "Root key has non-zero parent fingerprint or child index")
+      }
     }
   }
 }
@@ -0,0 +1,83 @@
+use base58;
+use byteorder::{BigEndian, ByteOrder, WriteBytesExt};
+use error::KeyError;
+
+pub mod data_layout {
+  pub const VERSION_SIZE: usize = 4;
+  pub const DEPTH_SIZE: usize = 1;
+  pub const FINGERPRINT_SIZE: usize = 4;
+  pub const INDEX_SIZE: usize = 4;
+  pub const CHAIN_CODE_SIZE: usize = 32;
+  pub const KEY_SIZE: usize = 33;
+
+  pub const EXTENDED_KEY_SIZE: usize =
+    VERSION_SIZE + DEPTH_SIZE + FINGERPRINT_SIZE + INDEX_SIZE + CHAIN_CODE_SIZE + KEY_SIZE;
+
+  pub const VERSION_START: usize = 0;
+  pub const VERSION_END: usize = VERSION_START + VERSION_SIZE;
+  pub const DEPTH_START: usize = VERSION_END;
+  pub const FINGERPRINT_START: usize = DEPTH_START + DEPTH_SIZE;
+  pub const FINGERPRINT_END: usize = FINGERPRINT_START + FINGERPRINT_SIZE;
+  pub const INDEX_START: usize = FINGERPRINT_END;
+  pub const INDEX_END: usize = INDEX_START + INDEX_SIZE;
+  pub const CHAIN_CODE_START: usize = INDEX_END;
+  pub const CHAIN_CODE_END: usize = CHAIN_CODE_START + CHAIN_CODE_SIZE;
+  pub const KEY_START: usize = CHAIN_CODE_END;
+  pub const KEY_END: usize = KEY_START + KEY_SIZE;
+}
+
+// BIP32 serialization format shared by extended private and public keys
+pub struct ExtendedKey {
+  pub version: u32,
+  pub depth: u8,
+  pub parent_fingerprint: [u8; data_layout::FINGERPRINT_SIZE],
+  pub index: u32,
+  pub chaincode: [u8; data_layout::CHAIN_CODE_SIZE],
+  pub key: [u8; data_layout::KEY_SIZE]
+}
+
+impl ExtendedKey {
+  pub fn to_base58(&self) -> String {
+    use self::data_layout::*;
+
+    let mut data = Vec::with_capacity(EXTENDED_KEY_SIZE);
+    data.write_u32::<BigEndian>(self.version).unwrap();
+    data.push(self.depth);
+    data.extend_from_slice(&self.parent_fingerprint);
+    data.write_u32::<BigEndian>(self.index).unwrap();
+    data.extend_from_slice(&self.chaincode);
+    data.extend_from_slice(&self.key);
+    base58::encode_check(&data)
+  }
+
+  pub fn from_base58(string: &str) -> Result<Self, KeyError> {
+    use self::data_layout::*;
+
+    let data = base58::decode_check(string)?;
+    if data.len() != EXTENDED_KEY_SIZE {
+      return Err(KeyError::InvalidDataSize(data.len(), EXTENDED_KEY_SIZE));
+    }
+
+    let mut parent_fingerprint = [0u8; FINGERPRINT_SIZE];
+    parent_fingerprint.copy_from_slice(&data[FINGERPRINT_START..FINGERPRINT_END]);
+    let mut chaincode = [0u8; CHAIN_CODE_SIZE];
+    chaincode.copy_from_slice(&data[CHAIN_CODE_START..CHAIN_CODE_END]);
+    let mut key = [0u8; KEY_SIZE];
+    key.copy_from_slice(&data[KEY_START..KEY_END]);
+
+    let extended = Self {
+      version: BigEndian::read_u32(&data[VERSION_START..VERSION_END]),
+      depth: data[DEPTH_START],
+      parent_fingerprint,
+      index: BigEndian::read_u32(&data[INDEX_START..INDEX_END]),
+      chaincode,
+      key
+    };
+
+    if extended.depth == 0 && (extended.index != 0 || extended.parent_fingerprint != [0u8; 4]) {
+      return Err(KeyError::InvalidRootKey);
+    }
+
+    Ok(extended)
+  }
+}
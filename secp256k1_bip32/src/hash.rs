@@ -0,0 +1,47 @@
+use cryptoxide::digest::Digest;
+use cryptoxide::sha2::Sha256;
+use cryptoxide::sha3::Sha3;
+use ripemd160::{Digest as RipeDigest, Ripemd160};
+
+pub const HASH_SIZE: usize = 32;
+pub const HASH160_SIZE: usize = 20;
+
+pub fn sha256(data: &[u8]) -> [u8; HASH_SIZE] {
+  let mut hasher = Sha256::new();
+  let mut out = [0u8; HASH_SIZE];
+  hasher.input(data);
+  hasher.result(&mut out);
+  out
+}
+
+// BIP340 tagged hash: SHA256(SHA256(tag) || SHA256(tag) || data)
+pub fn tagged_hash(tag: &str, data: &[u8]) -> [u8; HASH_SIZE] {
+  let tag = sha256(tag.as_bytes());
+  let mut hasher = Sha256::new();
+  let mut out = [0u8; HASH_SIZE];
+  hasher.input(&tag);
+  hasher.input(&tag);
+  hasher.input(data);
+  hasher.result(&mut out);
+  out
+}
+
+pub fn sha256d(data: &[u8]) -> [u8; HASH_SIZE] {
+  sha256(&sha256(data))
+}
+
+pub fn hash160(data: &[u8]) -> [u8; HASH160_SIZE] {
+  let mut hasher = Ripemd160::new();
+  hasher.input(&sha256(data));
+  let mut out = [0u8; HASH160_SIZE];
+  out.copy_from_slice(hasher.result().as_slice());
+  out
+}
+
+pub fn keccak256(data: &[u8]) -> [u8; HASH_SIZE] {
+  let mut hasher = Sha3::keccak256();
+  let mut out = [0u8; HASH_SIZE];
+  hasher.input(data);
+  hasher.result(&mut out);
+  out
+}
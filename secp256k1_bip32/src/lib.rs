@@ -1,14 +1,24 @@
 extern crate bip39;
 
+extern crate bs58;
 extern crate byteorder;
 extern crate cryptoxide;
 extern crate ripemd160;
 extern crate secp256k1;
 
+pub mod base58;
 mod error;
+mod extended;
+pub mod hash;
 mod private;
 mod public;
+mod recovery;
+mod schnorr;
+mod version;
 
 pub use self::error::KeyError;
 pub use self::private::XPrv;
-pub use self::public::XPub;
+pub use self::public::{compressed_public_key, verify_der, XPub};
+pub use self::recovery::recover;
+pub use self::schnorr::{verify as verify_schnorr, SCHNORR_SIGNATURE_SIZE, X_ONLY_KEY_SIZE};
+pub use self::version::Version;
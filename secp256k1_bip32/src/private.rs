@@ -4,15 +4,17 @@ use cryptoxide::digest::Digest;
 use cryptoxide::hmac::Hmac;
 use cryptoxide::mac::Mac;
 use cryptoxide::sha2::{Sha256, Sha512};
-use cryptoxide::sha3::Sha3;
-use ripemd160::{Digest as RipeDigest, Ripemd160};
 use secp256k1::{sign, util, Message, PublicKey, SecretKey};
 
 use super::error::KeyError;
+use super::extended::ExtendedKey;
+use super::hash::keccak256;
 use super::public::XPub;
+use super::schnorr;
+use super::version::Version;
 
 const HMAC_KEY: &[u8] = b"Bitcoin seed";
-const BIP44_SOFT_UPPER_BOUND: u32 = 0x80000000;
+pub(crate) const BIP44_SOFT_UPPER_BOUND: u32 = 0x80000000;
 
 mod data_layout {
   pub const DEPTH_SIZE: usize = 1;
@@ -41,6 +43,7 @@ mod data_layout {
   pub const CHECKSUM_END: usize = CHECKSUM_START + CHECKSUM_SIZE;
 }
 
+#[derive(Clone)]
 pub struct XPrv {
   key: SecretKey,
   chaincode: [u8; data_layout::CHAIN_CODE_SIZE],
@@ -82,10 +85,14 @@ impl XPrv {
   }
 
   pub fn from_seed(seed: &bip39::Seed) -> Result<Self, KeyError> {
+    Self::from_seed_bytes(seed.as_ref())
+  }
+
+  fn from_seed_bytes(seed: &[u8]) -> Result<Self, KeyError> {
     use self::data_layout::*;
 
     let mut hmac = Hmac::new(Sha512::new(), HMAC_KEY);
-    hmac.input(seed.as_ref());
+    hmac.input(seed);
     let result = hmac.result();
     let entropy = result.code();
     if entropy.len() < ENTROPY_SIZE {
@@ -108,8 +115,70 @@ impl XPrv {
     })
   }
 
+  pub fn from_base58(string: &str) -> Result<(Self, Version), KeyError> {
+    let extended = ExtendedKey::from_base58(string)?;
+    let version =
+      Version::from_private(extended.version).ok_or(KeyError::UnknownVersion(extended.version))?;
+    if extended.key[0] != 0 {
+      return Err(KeyError::InvalidSecretKey);
+    }
+    let key = SecretKey::parse_slice(&extended.key[1..]).map_err(|err| KeyError::from(err))?;
+    let xprv = Self {
+      key,
+      chaincode: extended.chaincode,
+      parent_fingerprint: extended.parent_fingerprint,
+      depth: extended.depth,
+      index: extended.index
+    };
+    Ok((xprv, version))
+  }
+
+  pub fn to_base58(&self, version: Version) -> String {
+    let mut key = [0u8; util::SECRET_KEY_SIZE + 1];
+    key[1..].copy_from_slice(&self.key.serialize());
+    ExtendedKey {
+      version: version.private(),
+      depth: self.depth,
+      parent_fingerprint: self.parent_fingerprint,
+      index: self.index,
+      chaincode: self.chaincode,
+      key
+    }
+    .to_base58()
+  }
+
   pub fn public(&self) -> XPub {
-    XPub::new(PublicKey::from_secret_key(&self.key))
+    XPub::new(
+      PublicKey::from_secret_key(&self.key),
+      self.chaincode,
+      self.parent_fingerprint,
+      self.depth,
+      self.index
+    )
+  }
+
+  pub fn depth(&self) -> u8 {
+    self.depth
+  }
+
+  pub fn index(&self) -> u32 {
+    self.index
+  }
+
+  pub fn chain_code(&self) -> [u8; data_layout::CHAIN_CODE_SIZE] {
+    self.chaincode
+  }
+
+  pub fn parent_fingerprint(&self) -> [u8; data_layout::FINGERPRINT_SIZE] {
+    self.parent_fingerprint
+  }
+
+  pub fn fingerprint(&self) -> [u8; data_layout::FINGERPRINT_SIZE] {
+    self.public().fingerprint()
+  }
+
+  pub fn secret(&self) -> [u8; util::SECRET_KEY_SIZE] {
+    self.key.serialize()
   }
 
   pub fn serialize(&self) -> Vec<u8> {
@@ -137,12 +206,11 @@ impl XPrv {
   }
 
   pub fn sign(&self, data: &[u8]) -> Result<Vec<u8>, KeyError> {
-    let mut sha3 = Sha3::keccak256();
-    sha3.input(data);
-    let mut out = [0u8; util::MESSAGE_SIZE];
-    sha3.result(&mut out);
+    self.sign_digest(&keccak256(data))
+  }
 
-    let message = Message::parse(&out);
+  pub fn sign_digest(&self, digest: &[u8]) -> Result<Vec<u8>, KeyError> {
+    let message = Message::parse_slice(digest).map_err(|e| KeyError::from(e))?;
 
     let (signature, recovery) = sign(&message, &self.key).map_err(|e| KeyError::from(e))?;
 
@@ -158,6 +226,30 @@ impl XPrv {
     Ok(data)
   }
 
+  // DER encoded signature with low S value, as required by Bitcoin consensus rules
+  pub fn sign_digest_der(&self, digest: &[u8]) -> Result<Vec<u8>, KeyError> {
+    let message = Message::parse_slice(digest).map_err(|e| KeyError::from(e))?;
+
+    let (mut signature, _) = sign(&message, &self.key).map_err(|e| KeyError::from(e))?;
+    signature.normalize_s();
+
+    Ok(Vec::from(signature.serialize_der().as_ref()))
+  }
+
+  // BIP340 signature of the digest. Aux randomness protects against side channel attacks.
+  pub fn sign_schnorr(&self, digest: &[u8], aux: &[u8]) -> Result<Vec<u8>, KeyError> {
+    schnorr::sign(&self.key, digest, aux)
+  }
+
+  // Signature for BIP86 taproot output key, which commits to no script tree
+  pub fn sign_taproot(&self, digest: &[u8], aux: &[u8]) -> Result<Vec<u8>, KeyError> {
+    schnorr::sign(&schnorr::tweak_secret(&self.key)?, digest, aux)
+  }
+
+  pub fn derive_path(&self, path: &[u32]) -> Result<Self, KeyError> {
+    path.iter().try_fold(self.clone(), |key, &index| key.derive(index))
+  }
+
   pub fn derive(&self, index: u32) -> Result<Self, KeyError> {
     use self::data_layout::*;
 
@@ -208,17 +300,153 @@ impl XPrv {
       }
     }
 
-    let mut hasher = Ripemd160::new();
-    hasher.input(&self.public().compressed_sha256());
-    let mut fingerprint = [0u8; FINGERPRINT_SIZE];
-    fingerprint.copy_from_slice(&hasher.result()[0..FINGERPRINT_SIZE]);
-
     Ok(Self {
       key: newpk,
       depth: self.depth + 1,
       chaincode,
       index,
-      parent_fingerprint: fingerprint
+      parent_fingerprint: self.fingerprint()
     })
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use bip39::util::hex;
+  use hash::{sha256, sha256d};
+  use secp256k1::Signature;
+
+  fn key_one() -> XPrv {
+    let mut secret = [0u8; 32];
+    secret[31] = 1;
+    XPrv {
+      key: SecretKey::parse(&secret).unwrap(),
+      chaincode: [0u8; 32],
+      parent_fingerprint: [0u8; 4],
+      depth: 0,
+      index: 0
+    }
+  }
+
+  #[test]
+  fn sign_digest_rfc6979() {
+    let digest = sha256(b"Satoshi Nakamoto");
+    let signature = key_one().sign_digest(&digest).unwrap();
+    assert_eq!(
+      hex::encode(&signature[0..64]),
+      "934b1ea10a4b3c1757e2b0c017d0b6143ce3c9a7e6a4a49860d7a6ab210ee3d8\
+       2442ce9d2b916064108014783e923ec36b49743e2ffa1c4496f01a512aafd9e5"
+    );
+  }
+
+  #[test]
+  fn sign_digest_der_low_s() {
+    let key = key_one();
+    let digest = sha256d(b"Bitcoin");
+    let der = key.sign_digest_der(&digest).unwrap();
+    assert_eq!(der[0], 0x30);
+    assert_eq!(der[1] as usize, der.len() - 2);
+    assert!(key.public().verify_digest_der(&digest, &der).unwrap());
+
+    let mut high_s = Signature::parse_der(&der).unwrap();
+    let s = high_s.s.clone();
+    high_s.s.neg_in_place(&s);
+    let high_der = high_s.serialize_der();
+    assert!(!key.public().verify_digest_der(&digest, high_der.as_ref()).unwrap());
+  }
+
+  fn check_bip32(xprv: &XPrv, expected_xprv: &str, expected_xpub: &str) {
+    assert_eq!(xprv.to_base58(Version::Xpub), expected_xprv);
+    assert_eq!(xprv.public().to_base58(Version::Xpub), expected_xpub);
+
+    let (parsed, version) = XPrv::from_base58(expected_xprv).unwrap();
+    assert_eq!(version, Version::Xpub);
+    assert_eq!(parsed.serialize(), xprv.serialize());
+
+    let (parsed, version) = XPub::from_base58(expected_xpub).unwrap();
+    assert_eq!(version, Version::Xpub);
+    assert_eq!(parsed.to_base58(Version::Xpub), expected_xpub);
+  }
+
+  #[test]
+  fn bip32_vector_1() {
+    let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+    let master = XPrv::from_seed_bytes(&seed).unwrap();
+    check_bip32(
+      &master,
+      "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi",
+      "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8"
+    );
+    let child = master.derive(BIP44_SOFT_UPPER_BOUND).unwrap();
+    check_bip32(
+      &child,
+      "xprv9uHRZZhk6KAJC1avXpDAp4MDc3sQKNxDiPvvkX8Br5ngLNv1TxvUxt4cV1rGL5hj6KCesnDYUhd7oWgT11eZG7XnxHrnYeSvkzY7d2bhkJ7",
+      "xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw"
+    );
+    let child = child.derive(1).unwrap();
+    check_bip32(
+      &child,
+      "xprv9wTYmMFdV23N2TdNG573QoEsfRrWKQgWeibmLntzniatZvR9BmLnvSxqu53Kw1UmYPxLgboyZQaXwTCg8MSY3H2EU4pWcQDnRnrVA1xe8fs",
+      "xpub6ASuArnXKPbfEwhqN6e3mwBcDTgzisQN1wXN9BJcM47sSikHjJf3UFHKkNAWbWMiGj7Wf5uMash7SyYq527Hqck2AxYysAA7xmALppuCkwQ"
+    );
+    let child = child.derive(BIP44_SOFT_UPPER_BOUND + 2).unwrap();
+    check_bip32(
+      &child,
+      "xprv9z4pot5VBttmtdRTWfWQmoH1taj2axGVzFqSb8C9xaxKymcFzXBDptWmT7FwuEzG3ryjH4ktypQSAewRiNMjANTtpgP4mLTj34bhnZX7UiM",
+      "xpub6D4BDPcP2GT577Vvch3R8wDkScZWzQzMMUm3PWbmWvVJrZwQY4VUNgqFJPMM3No2dFDFGTsxxpG5uJh7n7epu4trkrX7x7DogT5Uv6fcLW5"
+    );
+  }
+
+  #[test]
+  fn bip32_public_derivation() {
+    let (xpub, _) = XPub::from_base58("xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw").unwrap();
+    assert_eq!(
+      xpub.derive(1).unwrap().to_base58(Version::Xpub),
+      "xpub6ASuArnXKPbfEwhqN6e3mwBcDTgzisQN1wXN9BJcM47sSikHjJf3UFHKkNAWbWMiGj7Wf5uMash7SyYq527Hqck2AxYysAA7xmALppuCkwQ"
+    );
+
+    let (xpub, _) = XPub::from_base58("xpub6D4BDPcP2GT577Vvch3R8wDkScZWzQzMMUm3PWbmWvVJrZwQY4VUNgqFJPMM3No2dFDFGTsxxpG5uJh7n7epu4trkrX7x7DogT5Uv6fcLW5").unwrap();
+    let child = xpub.derive(2).unwrap();
+    assert_eq!(
+      child.to_base58(Version::Xpub),
+      "xpub6FHa3pjLCk84BayeJxFW2SP4XRrFd1JYnxeLeU8EqN3vDfZmbqBqaGJAyiLjTAwm6ZLRQUMv1ZACTj37sR62cfN7fe5JnJ7dh8zL4fiyLHV"
+    );
+    assert_eq!(child.parent_fingerprint(), xpub.fingerprint());
+    assert_eq!(
+      child.derive(1000000000).unwrap().to_base58(Version::Xpub),
+      "xpub6H1LXWLaKsWFhvm6RVpEL9P4KfRZSW7abD2ttkWP3SSQvnyA8FSVqNTEcYFgJS2UaFcxupHiYkro49S8yGasTvXEYBVPamhGW6cFJodrTHy"
+    );
+
+    match xpub.derive(BIP44_SOFT_UPPER_BOUND) {
+      Err(KeyError::HardenedPublicDerivation(_)) => {}
+      _ => panic!("hardened public derivation succeeded")
+    }
+  }
+
+  #[test]
+  fn bip32_invalid_keys() {
+    // pubkey version / prvkey mismatch
+    assert!(XPub::from_base58("xpub661MyMwAqRbcEYS8w7XLSVeEsBXy79zSzH1J8vCdxAZningWLdN3zgtU6LBpB85b3D2yc8sfvZU521AAwdZafEz7mnzBBsz4wKY5fTtTQBm").is_err());
+    // prvkey version / pubkey mismatch
+    assert!(XPrv::from_base58("xprv9s21ZrQH143K24Mfq5zL5MhWK9hUhhGbd45hLXo2Pq2oqzMMo63oStZzFGTQQD3dC4H2D5GBj7vWvSQaaBv5cxi9gafk7NF3pnBju6dwKvH").is_err());
+    // zero depth with non-zero parent fingerprint
+    match XPub::from_base58("xpub661no6RGEX3uJkY4bNnPcw4URcQTrSibUZ4NqJEw5eBkv7ovTwgiT91XX27VbEXGENhYRCf7hyEbWrR3FewATdCEebj6znwMfQkhRYHRLpJ") {
+      Err(KeyError::InvalidRootKey) => {}
+      _ => panic!("root key with parent fingerprint accepted")
+    }
+  }
+
+  #[test]
+  fn slip132_versions() {
+    let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+    let xpub = XPrv::from_seed_bytes(&seed).unwrap().public();
+    for version in Version::ALL.iter() {
+      let (_, parsed) = XPub::from_base58(&xpub.to_base58(*version)).unwrap();
+      assert_eq!(parsed, *version);
+    }
+    assert!(xpub.to_base58(Version::Zpub).starts_with("zpub"));
+    assert!(xpub.to_base58(Version::ZpubMultisig).starts_with("Zpub"));
+    assert!(xpub.to_base58(Version::Tpub).starts_with("tpub"));
+  }
+}
@@ -1,46 +1,181 @@
 use super::error::KeyError;
+use super::extended::data_layout::{CHAIN_CODE_SIZE, FINGERPRINT_SIZE, INDEX_SIZE};
+use super::extended::ExtendedKey;
+use super::hash::{hash160, keccak256};
+use super::private::BIP44_SOFT_UPPER_BOUND;
+use super::schnorr::{self, X_ONLY_KEY_SIZE};
+use super::version::Version;
+use byteorder::{BigEndian, WriteBytesExt};
 use cryptoxide::digest::Digest;
-use cryptoxide::sha2::Sha256;
-use cryptoxide::sha3::Sha3;
-use secp256k1::{util, verify, Message, PublicKey, Signature};
+use cryptoxide::hmac::Hmac;
+use cryptoxide::mac::Mac;
+use cryptoxide::sha2::{Sha256, Sha512};
+use secp256k1::{util, verify, Message, PublicKey, SecretKey, Signature};
 
-pub struct XPub(PublicKey);
+#[derive(Clone)]
+pub struct XPub {
+  key: PublicKey,
+  chaincode: [u8; CHAIN_CODE_SIZE],
+  parent_fingerprint: [u8; FINGERPRINT_SIZE],
+  depth: u8,
+  index: u32
+}
 
 impl XPub {
-  pub(crate) fn new(key: PublicKey) -> Self {
-    Self(key)
+  pub(crate) fn new(
+    key: PublicKey, chaincode: [u8; CHAIN_CODE_SIZE], parent_fingerprint: [u8; FINGERPRINT_SIZE],
+    depth: u8, index: u32
+  ) -> Self {
+    Self { key, chaincode, parent_fingerprint, depth, index }
+  }
+
+  pub fn from_base58(string: &str) -> Result<(Self, Version), KeyError> {
+    let extended = ExtendedKey::from_base58(string)?;
+    let version =
+      Version::from_public(extended.version).ok_or(KeyError::UnknownVersion(extended.version))?;
+    let key = PublicKey::parse_compressed(&extended.key).map_err(|err| KeyError::from(err))?;
+    let xpub = Self::new(
+      key,
+      extended.chaincode,
+      extended.parent_fingerprint,
+      extended.depth,
+      extended.index
+    );
+    Ok((xpub, version))
+  }
+
+  pub fn to_base58(&self, version: Version) -> String {
+    let mut key = [0u8; util::COMPRESSED_PUBLIC_KEY_SIZE];
+    key.copy_from_slice(&self.key.serialize_compressed());
+    ExtendedKey {
+      version: version.public(),
+      depth: self.depth,
+      parent_fingerprint: self.parent_fingerprint,
+      index: self.index,
+      chaincode: self.chaincode,
+      key
+    }
+    .to_base58()
+  }
+
+  pub fn depth(&self) -> u8 {
+    self.depth
+  }
+
+  pub fn index(&self) -> u32 {
+    self.index
+  }
+
+  pub fn chain_code(&self) -> [u8; CHAIN_CODE_SIZE] {
+    self.chaincode
+  }
+
+  pub fn parent_fingerprint(&self) -> [u8; FINGERPRINT_SIZE] {
+    self.parent_fingerprint
+  }
+
+  pub fn fingerprint(&self) -> [u8; FINGERPRINT_SIZE] {
+    let mut fingerprint = [0u8; FINGERPRINT_SIZE];
+    fingerprint.copy_from_slice(&hash160(&self.key.serialize_compressed())[0..FINGERPRINT_SIZE]);
+    fingerprint
+  }
+
+  // Public parent key to public child key derivation. Works only for non-hardened indexes.
+  pub fn derive_path(&self, path: &[u32]) -> Result<Self, KeyError> {
+    path.iter().try_fold(self.clone(), |key, &index| key.derive(index))
+  }
+
+  pub fn derive(&self, index: u32) -> Result<Self, KeyError> {
+    if index >= BIP44_SOFT_UPPER_BOUND {
+      return Err(KeyError::HardenedPublicDerivation(index));
+    }
+    if self.depth == u8::MAX {
+      return Err(KeyError::DeriveDepthTooBig);
+    }
+
+    let mut input = Vec::with_capacity(util::COMPRESSED_PUBLIC_KEY_SIZE + INDEX_SIZE);
+    input.extend_from_slice(&self.key.serialize_compressed());
+    if input.write_u32::<BigEndian>(index).is_err() {
+      return Err(KeyError::InternalError);
+    }
+
+    let mut hmac = Hmac::new(Sha512::new(), &self.chaincode);
+    hmac.input(&input);
+    let result = hmac.result();
+    let entropy = result.code();
+
+    if entropy.len() < util::SECRET_KEY_SIZE + CHAIN_CODE_SIZE {
+      return Err(KeyError::InvalidEntropySize(entropy.len()));
+    }
+
+    let mut chaincode = [0u8; CHAIN_CODE_SIZE];
+    chaincode.copy_from_slice(&entropy[util::SECRET_KEY_SIZE..]);
+
+    let mut newkey = self.key.clone();
+    let tweaked = SecretKey::parse_slice(&entropy[0..util::SECRET_KEY_SIZE])
+      .and_then(|tweak| newkey.tweak_add_assign(&tweak));
+
+    if tweaked.is_err() {
+      if index + 1 < BIP44_SOFT_UPPER_BOUND {
+        return self.derive(index + 1);
+      } else {
+        return Err(KeyError::TweakOutOfRange);
+      }
+    }
+
+    Ok(Self {
+      key: newkey,
+      chaincode,
+      parent_fingerprint: self.fingerprint(),
+      depth: self.depth + 1,
+      index
+    })
   }
 
   pub fn serialize(&self) -> Vec<u8> {
-    Vec::from(self.0.serialize().as_ref())
+    Vec::from(self.key.serialize().as_ref())
   }
 
   pub fn serialize_compressed(&self) -> Vec<u8> {
-    Vec::from(self.0.serialize_compressed().as_ref())
+    Vec::from(self.key.serialize_compressed().as_ref())
+  }
+
+  // BIP340 public key: X coordinate of the point with even Y
+  pub fn x_only(&self) -> [u8; X_ONLY_KEY_SIZE] {
+    schnorr::x_only(&self.key)
+  }
+
+  // BIP86 taproot output key for key path only spending
+  pub fn taproot_output_key(&self) -> Result<[u8; X_ONLY_KEY_SIZE], KeyError> {
+    schnorr::tweak_public(&self.key)
   }
 
   pub fn verify(&self, data: &[u8], signature: &[u8]) -> Result<bool, KeyError> {
+    self.verify_digest(&keccak256(data), signature)
+  }
+
+  pub fn verify_digest(&self, digest: &[u8], signature: &[u8]) -> Result<bool, KeyError> {
     if signature.len() != util::SIGNATURE_SIZE {
       return Err(KeyError::InvalidSignature(signature.len(), util::SIGNATURE_SIZE));
     }
-    let mut sha3 = Sha3::keccak256();
-    sha3.input(data);
-    let mut out = [0u8; util::MESSAGE_SIZE];
-    sha3.result(&mut out);
-
-    let message = Message::parse(&out);
+    let message = Message::parse_slice(digest).map_err(|err| KeyError::from(err))?;
 
     Signature::parse_slice(signature)
-      .map(|signature| verify(&message, &signature, &self.0))
+      .map(|signature| verify(&message, &signature, &self.key))
       .map_err(|err| err.into())
   }
 
+  // Accepts only strict DER signatures with low S value
+  pub fn verify_digest_der(&self, digest: &[u8], signature: &[u8]) -> Result<bool, KeyError> {
+    verify_der_with_key(&self.key, digest, signature)
+  }
+
   pub fn sha256(&self) -> [u8; util::MESSAGE_SIZE] {
-    Self::_sha256(&self.0.serialize())
+    Self::_sha256(&self.key.serialize())
   }
 
   pub fn compressed_sha256(&self) -> [u8; util::MESSAGE_SIZE] {
-    Self::_sha256(&self.0.serialize_compressed())
+    Self::_sha256(&self.key.serialize_compressed())
   }
 
   fn _sha256(data: &[u8]) -> [u8; util::MESSAGE_SIZE] {
@@ -51,3 +186,27 @@ impl XPub {
     out
   }
 }
+
+// DER signature verification with serialized compressed or uncompressed public key
+pub fn verify_der(digest: &[u8], pub_key: &[u8], signature: &[u8]) -> Result<bool, KeyError> {
+  let key = PublicKey::parse_slice(pub_key, None).map_err(|err| KeyError::from(err))?;
+  verify_der_with_key(&key, digest, signature)
+}
+
+pub fn compressed_public_key(pub_key: &[u8]) -> Result<Vec<u8>, KeyError> {
+  let key = PublicKey::parse_slice(pub_key, None).map_err(|err| KeyError::from(err))?;
+  Ok(Vec::from(key.serialize_compressed().as_ref()))
+}
+
+fn verify_der_with_key(key: &PublicKey, digest: &[u8], signature: &[u8]) -> Result<bool, KeyError> {
+  let message = Message::parse_slice(digest).map_err(|err| KeyError::from(err))?;
+  let parsed = Signature::parse_der(signature).map_err(|_| KeyError::InvalidDerSignature)?;
+
+  let mut normalized = parsed.clone();
+  normalized.normalize_s();
+  if normalized != parsed {
+    return Ok(false);
+  }
+
+  Ok(verify(&message, &parsed, key))
+}
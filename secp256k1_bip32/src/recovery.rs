@@ -0,0 +1,41 @@
+use secp256k1::{recover as secp_recover, util, Message, RecoveryId, Signature};
+
+use super::error::KeyError;
+
+// Recovers uncompressed public key from r || s || recovery id signature of the digest
+pub fn recover(digest: &[u8], signature: &[u8]) -> Result<Vec<u8>, KeyError> {
+  if signature.len() != util::SIGNATURE_SIZE + 1 {
+    return Err(KeyError::InvalidSignature(signature.len(), util::SIGNATURE_SIZE + 1));
+  }
+  let message = Message::parse_slice(digest)?;
+  let recovery_id = RecoveryId::parse(signature[util::SIGNATURE_SIZE])?;
+  let signature = Signature::parse_slice(&signature[..util::SIGNATURE_SIZE])?;
+  secp_recover(&message, &signature, &recovery_id)
+    .map(|key| Vec::from(key.serialize().as_ref()))
+    .map_err(|err| err.into())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::recover;
+  use bip39::Seed;
+  use hash::keccak256;
+  use private::XPrv;
+
+  #[test]
+  fn recover_signer() {
+    let seed = Seed::from_slice(&[0x42; 64]).unwrap();
+    let key = XPrv::from_seed(&seed).unwrap().derive(0).unwrap();
+    let digest = keccak256(b"message");
+    let signature = key.sign_digest(&digest).unwrap();
+    assert_eq!(recover(&digest, &signature).unwrap(), key.public().serialize());
+
+    let other = keccak256(b"other message");
+    assert_ne!(recover(&other, &signature).unwrap(), key.public().serialize());
+    assert!(recover(&digest, &signature[..64]).is_err());
+
+    let mut invalid = signature.clone();
+    invalid[64] = 4;
+    assert!(recover(&digest, &invalid).is_err());
+  }
+}
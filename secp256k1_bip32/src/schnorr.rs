@@ -0,0 +1,336 @@
+// BIP340 Schnorr signatures and BIP86 taproot tweak.
+//
+// libsecp256k1 0.2 has no BIP340 support, so this is built on its low level `curve` module
+// (field, scalar and group arithmetic with ecmult contexts). It is public but not a stable
+// API, and upgrading the dependency needs re-running the BIP340 vectors in tests below.
+// Requirements of that module relied on here:
+// - `Field` values must be normalized before `b32`, `is_odd` and `eq_var`.
+// - `ECMULT_GEN_CONTEXT.ecmult_gen` and `Scalar` arithmetic are constant time and are the
+//   only operations applied to secret values.
+// - `ECMULT_CONTEXT.ecmult` and `*_var` functions are variable time and are used on public
+//   data only (verification and public key tweak).
+
+use secp256k1::curve::{Affine, Field, Jacobian, Scalar, ECMULT_CONTEXT, ECMULT_GEN_CONTEXT};
+use secp256k1::{util, PublicKey, SecretKey};
+
+use super::error::KeyError;
+use super::hash::tagged_hash;
+
+pub const X_ONLY_KEY_SIZE: usize = 32;
+pub const SCHNORR_SIGNATURE_SIZE: usize = 64;
+
+// BIP340 signature with even Y nonce point. Aux is fresh randomness or zeroes.
+pub(crate) fn sign(key: &SecretKey, digest: &[u8], aux: &[u8]) -> Result<Vec<u8>, KeyError> {
+  if digest.len() != util::MESSAGE_SIZE || aux.len() != util::MESSAGE_SIZE {
+    return Err(KeyError::InvalidInputLength);
+  }
+  let (secret, point) = even_key(key.clone().into());
+  let pub_key = point.x.b32();
+
+  let aux_hash = tagged_hash("BIP0340/aux", aux);
+  let mut masked = secret.b32();
+  masked.iter_mut().zip(aux_hash.iter()).for_each(|(byte, mask)| *byte ^= mask);
+  let nonce = tagged_hash("BIP0340/nonce", &[&masked[..], &pub_key, digest].concat());
+  let mut nonce_scalar = Scalar::default();
+  let _ = nonce_scalar.set_b32(&nonce);
+  if nonce_scalar.is_zero() {
+    return Err(KeyError::InvalidMessage);
+  }
+  let (nonce_scalar, nonce_point) = even_key(nonce_scalar);
+  let r = nonce_point.x.b32();
+
+  let challenge = challenge(&r, &pub_key, digest);
+  let s = &nonce_scalar + &(&challenge * &secret);
+
+  let mut signature = Vec::with_capacity(SCHNORR_SIGNATURE_SIZE);
+  signature.extend_from_slice(&r);
+  signature.extend_from_slice(&s.b32());
+  Ok(signature)
+}
+
+pub fn verify(digest: &[u8], pub_key: &[u8], signature: &[u8]) -> Result<bool, KeyError> {
+  if signature.len() != SCHNORR_SIGNATURE_SIZE {
+    return Err(KeyError::InvalidSignature(signature.len(), SCHNORR_SIGNATURE_SIZE));
+  }
+  if digest.len() != util::MESSAGE_SIZE {
+    return Err(KeyError::InvalidInputLength);
+  }
+  let point = lift_x(pub_key)?;
+
+  let mut r = Field::default();
+  if !r.set_b32(&array32(&signature[..32])) {
+    return Ok(false);
+  }
+  let mut s = Scalar::default();
+  if s.set_b32(&array32(&signature[32..])) {
+    return Ok(false);
+  }
+  let challenge = challenge(&signature[..32], pub_key, digest);
+
+  // R = s * G - e * P
+  let mut result = Jacobian::default();
+  ECMULT_CONTEXT.ecmult(&mut result, &Jacobian::from_ge(&point), &challenge.neg(), &s);
+  if result.is_infinity() {
+    return Ok(false);
+  }
+  let mut nonce_point = Affine::from_gej(&result);
+  nonce_point.x.normalize();
+  nonce_point.y.normalize();
+  Ok(!nonce_point.y.is_odd() && nonce_point.x.eq_var(&r))
+}
+
+// BIP86 key path only tweak: Q = P + H_TapTweak(P) * G, where P has even Y
+pub(crate) fn tweak_secret(key: &SecretKey) -> Result<SecretKey, KeyError> {
+  let (secret, point) = even_key(key.clone().into());
+  let tweaked = &secret + &tweak(&point.x.b32())?;
+  SecretKey::parse(&tweaked.b32()).map_err(|err| err.into())
+}
+
+pub(crate) fn tweak_public(key: &PublicKey) -> Result<[u8; X_ONLY_KEY_SIZE], KeyError> {
+  let x_only = x_only(key);
+  let mut tweaked = Jacobian::default();
+  ECMULT_CONTEXT.ecmult(
+    &mut tweaked,
+    &Jacobian::from_ge(&lift_x(&x_only)?),
+    &Scalar::from_int(1),
+    &tweak(&x_only)?
+  );
+  if tweaked.is_infinity() {
+    return Err(KeyError::TweakOutOfRange);
+  }
+  let mut point = Affine::from_gej(&tweaked);
+  point.x.normalize();
+  Ok(point.x.b32())
+}
+
+pub(crate) fn x_only(key: &PublicKey) -> [u8; X_ONLY_KEY_SIZE] {
+  let mut x_only = [0u8; X_ONLY_KEY_SIZE];
+  x_only.copy_from_slice(&key.serialize_compressed()[1..]);
+  x_only
+}
+
+fn tweak(x_only: &[u8]) -> Result<Scalar, KeyError> {
+  let mut tweak = Scalar::default();
+  if tweak.set_b32(&tagged_hash("TapTweak", x_only)) {
+    return Err(KeyError::TweakOutOfRange);
+  }
+  Ok(tweak)
+}
+
+fn challenge(r: &[u8], pub_key: &[u8], digest: &[u8]) -> Scalar {
+  let hash = tagged_hash("BIP0340/challenge", &[r, pub_key, digest].concat());
+  let mut challenge = Scalar::default();
+  let _ = challenge.set_b32(&hash);
+  challenge
+}
+
+// Negates the scalar if its point has odd Y
+fn even_key(scalar: Scalar) -> (Scalar, Affine) {
+  let mut point = Jacobian::default();
+  ECMULT_GEN_CONTEXT.ecmult_gen(&mut point, &scalar);
+  let mut point = Affine::from_gej(&point);
+  point.x.normalize();
+  point.y.normalize();
+  if point.y.is_odd() {
+    (scalar.neg(), point.neg())
+  } else {
+    (scalar, point)
+  }
+}
+
+fn lift_x(x_only: &[u8]) -> Result<Affine, KeyError> {
+  if x_only.len() != X_ONLY_KEY_SIZE {
+    return Err(KeyError::InvalidPublicKey);
+  }
+  let mut x = Field::default();
+  let mut point = Affine::default();
+  if !x.set_b32(&array32(x_only)) || !point.set_xo_var(&x, false) {
+    return Err(KeyError::InvalidPublicKey);
+  }
+  point.x.normalize();
+  point.y.normalize();
+  Ok(point)
+}
+
+fn array32(slice: &[u8]) -> [u8; 32] {
+  let mut array = [0u8; 32];
+  array.copy_from_slice(slice);
+  array
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{sign, tweak_public, tweak_secret, verify, x_only};
+  use bip39::util::hex;
+  use secp256k1::{PublicKey, SecretKey};
+
+  // BIP340 test vectors: secret key, public key, aux, message, signature
+  const VECTORS: [[&str; 5]; 4] = [
+    [
+      "0000000000000000000000000000000000000000000000000000000000000003",
+      "f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9",
+      "0000000000000000000000000000000000000000000000000000000000000000",
+      "0000000000000000000000000000000000000000000000000000000000000000",
+      "e907831f80848d1069a5371b402410364bdf1c5f8307b0084c55f1ce2dca821525f66a4a85ea8b71e482a74f38\
+       2d2ce5ebeee8fdb2172f477df4900d310536c0"
+    ],
+    [
+      "b7e151628aed2a6abf7158809cf4f3c762e7160f38b4da56a784d9045190cfef",
+      "dff1d77f2a671c5f36183726db2341be58feae1da2deced843240f7b502ba659",
+      "0000000000000000000000000000000000000000000000000000000000000001",
+      "243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89",
+      "6896bd60eeae296db48a229ff71dfe071bde413e6d43f917dc8dcf8c78de33418906d11ac976abccb20b091292\
+       bff4ea897efcb639ea871cfa95f6de339e4b0a"
+    ],
+    [
+      "c90fdaa22168c234c4c6628b80dc1cd129024e088a67cc74020bbea63b14e5c9",
+      "dd308afec5777e13121fa72b9cc1b7cc0139715309b086c960e18fd969774eb8",
+      "c87aa53824b4d7ae2eb035a2b5bbbccc080e76cdc6d1692c4b0b62d798e6d906",
+      "7e2d58d8b3bcdf1abadec7829054f90dda9805aab56c77333024b9d0a508b75c",
+      "5831aaeed7b44bb74e5eab94ba9d4294c49bcf2a60728d8b4c200f50dd313c1bab745879a5ad954a72c45a91c3\
+       a51d3c7adea98d82f8481e0e1e03674a6f3fb7"
+    ],
+    [
+      "0b432b2677937381aef05bb02a66ecd012773062cf3fa2549e44f58ed2401710",
+      "25d1dff95105f5253c4022f628a996ad3a0d95fbf21d468a1b33f8c160d8f517",
+      "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+      "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+      "7eb0509757e246f19449885651611cb965ecc1a187dd51b64fda1edc9637d5ec97582b9cb13db3933705b32ba9\
+       82af5af25fd78881ebb32771fc5922efc66ea3"
+    ]
+  ];
+
+  // BIP340 verification test vectors 4-14: public key, message, signature, result
+  const VERIFY_VECTORS: [(&str, &str, &str, bool); 11] = [
+    // R with leading zero bytes
+    (
+      "d69c3509bb99e412e68b0fe8544e72837dfa30746d8be2aa65975f29d22dc7b9",
+      "4df3c3f68fcc83b27e9d42c90431a72499f17875c81a599b566c9889b9696703",
+      "00000000000000000000003b78ce563f89a0ed9414f5aa28ad0d96d6795f9c6376afb1548af603b3eb45c9f820\
+       7dee1060cb71c04e80f593060b07d28308d7f4",
+      true
+    ),
+    // public key is not on the curve
+    (
+      "eefdea4cdb677750a420fee807eacf21eb9898ae79b9768766e4faa04a2d4a34",
+      "243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89",
+      "6cff5c3ba86c69ea4b7376f31a9bcb4f74c1976089b2d9963da2e5543e17776969e89b4c5564d00349106b8497\
+       785dd7d1d713a8ae82b32fa79d5f7fc407d39b",
+      false
+    ),
+    // R has odd Y
+    (
+      "dff1d77f2a671c5f36183726db2341be58feae1da2deced843240f7b502ba659",
+      "243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89",
+      "fff97bd5755eeea420453a14355235d382f6472f8568a18b2f057a14602975563cc27944640ac607cd107ae109\
+       23d9ef7a73c643e166be5ebeafa34b1ac553e2",
+      false
+    ),
+    // negated message
+    (
+      "dff1d77f2a671c5f36183726db2341be58feae1da2deced843240f7b502ba659",
+      "243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89",
+      "1fa62e331edbc21c394792d2ab1100a7b432b013df3f6ff4f99fcb33e0e1515f28890b3edb6e7189b630448b51\
+       5ce4f8622a954cfe545735aaea5134fccdb2bd",
+      false
+    ),
+    // negated s
+    (
+      "dff1d77f2a671c5f36183726db2341be58feae1da2deced843240f7b502ba659",
+      "243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89",
+      "6cff5c3ba86c69ea4b7376f31a9bcb4f74c1976089b2d9963da2e5543e177769961764b3aa9b2ffcb6ef947b68\
+       87a226e8d7c93e00c5ed0c1834ff0d0c2e6da6",
+      false
+    ),
+    // R is infinity, x(inf) as 0
+    (
+      "dff1d77f2a671c5f36183726db2341be58feae1da2deced843240f7b502ba659",
+      "243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89",
+      "0000000000000000000000000000000000000000000000000000000000000000123dda8328af9c23a94c1feecf\
+       d123ba4fb73476f0d594dcb65c6425bd186051",
+      false
+    ),
+    // R is infinity, x(inf) as 1
+    (
+      "dff1d77f2a671c5f36183726db2341be58feae1da2deced843240f7b502ba659",
+      "243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89",
+      "00000000000000000000000000000000000000000000000000000000000000017615fbaf5ae28864013c099742\
+       deadb4dba87f11ac6754f93780d5a1837cf197",
+      false
+    ),
+    // r is not an X coordinate on the curve
+    (
+      "dff1d77f2a671c5f36183726db2341be58feae1da2deced843240f7b502ba659",
+      "243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89",
+      "4a298dacae57395a15d0795ddbfd1dcb564da82b0f269bc70a74f8220429ba1d69e89b4c5564d00349106b8497\
+       785dd7d1d713a8ae82b32fa79d5f7fc407d39b",
+      false
+    ),
+    // r is equal to field size
+    (
+      "dff1d77f2a671c5f36183726db2341be58feae1da2deced843240f7b502ba659",
+      "243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89",
+      "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f69e89b4c5564d00349106b8497\
+       785dd7d1d713a8ae82b32fa79d5f7fc407d39b",
+      false
+    ),
+    // s is equal to curve order
+    (
+      "dff1d77f2a671c5f36183726db2341be58feae1da2deced843240f7b502ba659",
+      "243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89",
+      "6cff5c3ba86c69ea4b7376f31a9bcb4f74c1976089b2d9963da2e5543e177769ffffffffffffffffffffffffff\
+       fffffebaaedce6af48a03bbfd25e8cd0364141",
+      false
+    ),
+    // public key exceeds field size
+    (
+      "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc30",
+      "243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89",
+      "6cff5c3ba86c69ea4b7376f31a9bcb4f74c1976089b2d9963da2e5543e17776969e89b4c5564d00349106b8497\
+       785dd7d1d713a8ae82b32fa79d5f7fc407d39b",
+      false
+    )
+  ];
+
+  #[test]
+  fn bip340_vectors() {
+    for vector in VECTORS.iter() {
+      let key = SecretKey::parse_slice(&hex::decode(vector[0]).unwrap()).unwrap();
+      let pub_key = hex::decode(vector[1]).unwrap();
+      let aux = hex::decode(vector[2]).unwrap();
+      let message = hex::decode(vector[3]).unwrap();
+      assert_eq!(&x_only(&PublicKey::from_secret_key(&key))[..], &pub_key[..]);
+      let signature = sign(&key, &message, &aux).unwrap();
+      assert_eq!(hex::encode(&signature), vector[4]);
+      assert!(verify(&message, &pub_key, &signature).unwrap());
+
+      let mut invalid = signature.clone();
+      invalid[63] ^= 1;
+      assert!(!verify(&message, &pub_key, &invalid).unwrap());
+      assert!(!verify(&[0x42; 32], &pub_key, &signature).unwrap());
+    }
+  }
+
+  #[test]
+  fn bip340_verify_vectors() {
+    for &(pub_key, message, signature, valid) in VERIFY_VECTORS.iter() {
+      let pub_key = hex::decode(pub_key).unwrap();
+      let message = hex::decode(message).unwrap();
+      let signature = hex::decode(signature).unwrap();
+      // Public keys which are not valid X coordinates are errors
+      assert_eq!(verify(&message, &pub_key, &signature).unwrap_or(false), valid);
+    }
+    let invalid_key = hex::decode(VERIFY_VECTORS[1].0).unwrap();
+    assert!(verify(&[0; 32], &invalid_key, &[1; 64]).is_err());
+  }
+
+  #[test]
+  fn taproot_tweak() {
+    let key = SecretKey::parse_slice(&[0x42; 32]).unwrap();
+    let output_key = tweak_public(&PublicKey::from_secret_key(&key)).unwrap();
+    let tweaked = tweak_secret(&key).unwrap();
+    assert_eq!(x_only(&PublicKey::from_secret_key(&tweaked)), output_key);
+    let signature = sign(&tweaked, &[1; 32], &[0; 32]).unwrap();
+    assert!(verify(&[1; 32], &output_key, &signature).unwrap());
+  }
+}
@@ -0,0 +1,85 @@
+// Extended key version bytes from BIP32 and SLIP-0132
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Version {
+  // xprv / xpub - P2PKH or P2SH
+  Xpub,
+  // yprv / ypub - P2WPKH in P2SH
+  Ypub,
+  // zprv / zpub - P2WPKH
+  Zpub,
+  // Yprv / Ypub - multi-signature P2WSH in P2SH
+  YpubMultisig,
+  // Zprv / Zpub - multi-signature P2WSH
+  ZpubMultisig,
+  // tprv / tpub - testnet P2PKH or P2SH
+  Tpub,
+  // uprv / upub - testnet P2WPKH in P2SH
+  Upub,
+  // vprv / vpub - testnet P2WPKH
+  Vpub,
+  // Uprv / Upub - testnet multi-signature P2WSH in P2SH
+  UpubMultisig,
+  // Vprv / Vpub - testnet multi-signature P2WSH
+  VpubMultisig
+}
+
+impl Version {
+  pub const ALL: [Version; 10] = [
+    Version::Xpub,
+    Version::Ypub,
+    Version::Zpub,
+    Version::YpubMultisig,
+    Version::ZpubMultisig,
+    Version::Tpub,
+    Version::Upub,
+    Version::Vpub,
+    Version::UpubMultisig,
+    Version::VpubMultisig
+  ];
+
+  pub fn public(&self) -> u32 {
+    match self {
+      &Version::Xpub => 0x0488b21e,
+      &Version::Ypub => 0x049d7cb2,
+      &Version::Zpub => 0x04b24746,
+      &Version::YpubMultisig => 0x0295b43f,
+      &Version::ZpubMultisig => 0x02aa7ed3,
+      &Version::Tpub => 0x043587cf,
+      &Version::Upub => 0x044a5262,
+      &Version::Vpub => 0x045f1cf6,
+      &Version::UpubMultisig => 0x024289ef,
+      &Version::VpubMultisig => 0x02575483
+    }
+  }
+
+  pub fn private(&self) -> u32 {
+    match self {
+      &Version::Xpub => 0x0488ade4,
+      &Version::Ypub => 0x049d7878,
+      &Version::Zpub => 0x04b2430c,
+      &Version::YpubMultisig => 0x0295b005,
+      &Version::ZpubMultisig => 0x02aa7a99,
+      &Version::Tpub => 0x04358394,
+      &Version::Upub => 0x044a4e28,
+      &Version::Vpub => 0x045f18bc,
+      &Version::UpubMultisig => 0x024285b5,
+      &Version::VpubMultisig => 0x02575048
+    }
+  }
+
+  pub fn is_testnet(&self) -> bool {
+    *self == Version::Tpub
+      || *self == Version::Upub
+      || *self == Version::Vpub
+      || *self == Version::UpubMultisig
+      || *self == Version::VpubMultisig
+  }
+
+  pub fn from_public(bytes: u32) -> Option<Self> {
+    Self::ALL.iter().find(|version| version.public() == bytes).cloned()
+  }
+
+  pub fn from_private(bytes: u32) -> Option<Self> {
+    Self::ALL.iter().find(|version| version.private() == bytes).cloned()
+  }
+}